@@ -0,0 +1,114 @@
+#include "Reactor.hpp"
+
+#include <boost/asio/io_context.hpp>
+#include <sdbusplus/asio/connection.hpp>
+#include <sdbusplus/asio/object_server.hpp>
+
+#include <algorithm>
+#include <cstddef>
+#include <cstdlib>
+#include <iostream>
+#include <memory>
+#include <set>
+#include <span>
+#include <string>
+#include <string_view>
+#include <vector>
+
+namespace reactor
+{
+
+struct Entry
+{
+    std::string_view name;
+    Start start;
+};
+
+static std::vector<Entry>& registry()
+{
+    static std::vector<Entry> entries;
+    return entries;
+}
+
+Registration::Registration(const char* name, Start start)
+{
+    registry().emplace_back(name, start);
+}
+
+static const Entry* findEntry(std::string_view name)
+{
+    auto it = std::ranges::find(registry(), name, &Entry::name);
+    return it == registry().end() ? nullptr : &*it;
+}
+
+// The reactors started by main()
+static boost::asio::io_context* hostIo = nullptr;
+static int exitStatus = EXIT_SUCCESS;
+
+void addManager(sdbusplus::asio::object_server& objectServer,
+                const std::string& path)
+{
+    static std::set<std::string> managed;
+    if (managed.insert(path).second)
+    {
+        objectServer.add_manager(path);
+    }
+}
+
+void fail(std::string_view name)
+{
+    std::cerr << "Sensor type " << name << " failed\n";
+    exitStatus = EXIT_FAILURE;
+    if (hostIo != nullptr)
+    {
+        hostIo->stop();
+    }
+}
+
+} // namespace reactor
+
+// Runs the reactors named on the command line in a single io_context and
+// connection, or all of the registered ones when none are named.
+int main(int argc, char** argv)
+{
+    std::vector<const reactor::Entry*> selected;
+    std::span<char*> args(argv, static_cast<size_t>(argc));
+    for (const char* name : args.subspan(1))
+    {
+        const reactor::Entry* entry = reactor::findEntry(name);
+        if (entry == nullptr)
+        {
+            std::cerr << "Unknown sensor type " << name << ", available:";
+            for (const reactor::Entry& available : reactor::registry())
+            {
+                std::cerr << " " << available.name;
+            }
+            std::cerr << "\n";
+            return EXIT_FAILURE;
+        }
+        if (std::ranges::find(selected, entry) == selected.end())
+        {
+            selected.emplace_back(entry);
+        }
+    }
+    if (selected.empty())
+    {
+        for (const reactor::Entry& entry : reactor::registry())
+        {
+            selected.emplace_back(&entry);
+        }
+    }
+
+    boost::asio::io_context io;
+    auto systemBus = std::make_shared<sdbusplus::asio::connection>(io);
+    sdbusplus::asio::object_server objectServer(systemBus, true);
+
+    reactor::hostIo = &io;
+    for (const reactor::Entry* entry : selected)
+    {
+        entry->start(io, systemBus, objectServer);
+    }
+
+    io.run();
+    return reactor::exitStatus;
+}
@@ -0,0 +1,40 @@
+#pragma once
+
+#include <boost/asio/io_context.hpp>
+#include <sdbusplus/asio/connection.hpp>
+#include <sdbusplus/asio/object_server.hpp>
+
+#include <memory>
+#include <string>
+#include <string_view>
+
+namespace reactor
+{
+
+// Sets up the sensors, matches and timers of one sensor daemon, requests its
+// bus name and returns. Whatever the reactor keeps must outlive the call, the
+// io_context is only run once every reactor of the process has been started.
+using Start = void (*)(boost::asio::io_context& io,
+                       std::shared_ptr<sdbusplus::asio::connection>& systemBus,
+                       sdbusplus::asio::object_server& objectServer);
+
+// Makes a reactor available to the main() in Reactor.cpp. Each stand-alone
+// daemon links a single registration, unifiedsensor links one per sensor type
+// enabled at build time.
+struct Registration
+{
+    Registration(const char* name, Start start);
+};
+
+// Adds an object manager at path, unless another reactor of the process has
+// already added one there.
+void addManager(sdbusplus::asio::object_server& objectServer,
+                const std::string& path);
+
+// Reports that a reactor can't carry on, e.g. because a service it depends on
+// went away. The process exits with a failure, taking the other reactors of
+// unifiedsensor with it, and the Restart=always of its unit starts it again
+// with all of them set up afresh.
+void fail(std::string_view name);
+
+} // namespace reactor
@@ -0,0 +1,528 @@
+#include "SensorHistory.hpp"
+
+#include "FileHandle.hpp"
+#include "Utils.hpp"
+#include "VariantVisitors.hpp"
+
+#include <fcntl.h>
+#include <unistd.h>
+
+#include <boost/algorithm/string/replace.hpp>
+#include <boost/asio/error.hpp>
+#include <boost/asio/steady_timer.hpp>
+#include <sdbusplus/asio/connection.hpp>
+#include <sdbusplus/asio/object_server.hpp>
+
+#include <algorithm>
+#include <chrono>
+#include <cmath>
+#include <cstddef>
+#include <cstdint>
+#include <filesystem>
+#include <iostream>
+#include <memory>
+#include <optional>
+#include <string>
+#include <string_view>
+#include <system_error>
+#include <utility>
+#include <variant>
+#include <vector>
+
+namespace history
+{
+
+size_t Config::capacity() const
+{
+    auto records = static_cast<size_t>(retention / resolution);
+    return std::clamp<size_t>(records, 1, maxRecords);
+}
+
+std::optional<Config> parseConfig(const SensorBaseConfigMap& cfg)
+{
+    Config config;
+
+    auto retentionFind = cfg.find("Retention");
+    if (retentionFind != cfg.end())
+    {
+        config.retention = std::chrono::seconds(
+            std::visit(VariantToUnsignedIntVisitor(), retentionFind->second));
+    }
+    auto resolutionFind = cfg.find("Resolution");
+    if (resolutionFind != cfg.end())
+    {
+        config.resolution = std::chrono::seconds(
+            std::visit(VariantToUnsignedIntVisitor(), resolutionFind->second));
+    }
+    auto syncFind = cfg.find("SyncInterval");
+    if (syncFind != cfg.end())
+    {
+        config.syncInterval = std::chrono::seconds(
+            std::visit(VariantToUnsignedIntVisitor(), syncFind->second));
+    }
+    auto persistentFind = cfg.find("Persistent");
+    if (persistentFind != cfg.end())
+    {
+        config.persistent = std::visit(VariantToUnsignedIntVisitor(),
+                                       persistentFind->second) != 0U;
+    }
+
+    if (config.retention.count() == 0 || config.resolution.count() == 0 ||
+        config.syncInterval.count() == 0)
+    {
+        std::cerr << "History Retention, Resolution and SyncInterval must be "
+                     "non-zero\n";
+        return std::nullopt;
+    }
+    if (config.resolution > config.retention)
+    {
+        std::cerr << "History Resolution exceeds Retention\n";
+        return std::nullopt;
+    }
+    // Files that haven't been synced for as long as they keep records are
+    // removed, so a file in use is synced at least twice as often
+    std::chrono::seconds kept =
+        config.resolution * static_cast<int64_t>(config.capacity());
+    config.syncInterval = std::min(
+        config.syncInterval, std::max(kept / 2, std::chrono::seconds(1)));
+    return config;
+}
+
+RingBuffer::RingBuffer(size_t capacity, std::pair<double, double> range,
+                       const std::filesystem::path& backingFile,
+                       std::chrono::seconds resolution) :
+    records(std::max<size_t>(capacity, 1), Record{0, noValue}),
+    resolution(static_cast<uint32_t>(std::max<int64_t>(resolution.count(), 1))),
+    minValue(range.first), maxValue(range.second)
+{
+    if (!backingFile.empty())
+    {
+        openBackingFile(backingFile);
+    }
+}
+
+RingBuffer::~RingBuffer()
+{
+    sync();
+}
+
+void RingBuffer::openBackingFile(const std::filesystem::path& path)
+{
+    std::error_code ec;
+    std::filesystem::create_directories(path.parent_path(), ec);
+    if (ec)
+    {
+        std::cerr << "Unable to create " << path.parent_path() << ": "
+                  << ec.message() << "\n";
+        return;
+    }
+
+    // NOLINTNEXTLINE(cppcoreguidelines-pro-type-vararg)
+    int fd = open(path.c_str(), O_RDWR | O_CREAT | O_CLOEXEC, 0644);
+    if (fd < 0)
+    {
+        std::cerr << "Unable to open history file " << path << "\n";
+        return;
+    }
+    file.emplace(fd);
+
+    if (load())
+    {
+        return;
+    }
+
+    // Missing, stale or incompatible, start over
+    off_t size = static_cast<off_t>(sizeof(Header) +
+                                    (records.size() * sizeof(Record)));
+    if (ftruncate(file->handle(), size) < 0)
+    {
+        std::cerr << "Unable to size history file " << path << "\n";
+        file.reset();
+        return;
+    }
+    if (!storeHeader())
+    {
+        file.reset();
+    }
+}
+
+bool RingBuffer::load()
+{
+    Header header{};
+    if (pread(file->handle(), &header, sizeof(header), 0) !=
+        static_cast<ssize_t>(sizeof(header)))
+    {
+        return false;
+    }
+    // The stored values can only be read back with the same range
+    if (header.magic != fileMagic || header.version != fileVersion ||
+        header.capacity != records.size() || header.head >= records.size() ||
+        header.count > records.size() || header.resolution != resolution ||
+        header.min != minValue || header.max != maxValue)
+    {
+        return false;
+    }
+
+    size_t bytes = records.size() * sizeof(Record);
+    if (pread(file->handle(), records.data(), bytes, sizeof(Header)) !=
+        static_cast<ssize_t>(bytes))
+    {
+        std::fill(records.begin(), records.end(), Record{0, noValue});
+        return false;
+    }
+    head = header.head;
+    count = header.count;
+    oldestTimestamp = header.oldestTimestamp;
+    newestTimestamp = header.newestTimestamp;
+    return true;
+}
+
+bool RingBuffer::storeRecords(size_t first, size_t number)
+{
+    off_t offset =
+        static_cast<off_t>(sizeof(Header) + (first * sizeof(Record)));
+    size_t bytes = number * sizeof(Record);
+    return pwrite(file->handle(), &records[first], bytes, offset) ==
+           static_cast<ssize_t>(bytes);
+}
+
+bool RingBuffer::storeHeader()
+{
+    Header header{fileMagic,
+                  fileVersion,
+                  static_cast<uint32_t>(records.size()),
+                  static_cast<uint32_t>(head),
+                  static_cast<uint32_t>(count),
+                  resolution,
+                  oldestTimestamp,
+                  newestTimestamp,
+                  minValue,
+                  maxValue};
+    return pwrite(file->handle(), &header, sizeof(header), 0) ==
+           static_cast<ssize_t>(sizeof(header));
+}
+
+uint16_t RingBuffer::quantize(double value) const
+{
+    if (!std::isfinite(value) || !(maxValue > minValue))
+    {
+        return noValue;
+    }
+    double step =
+        std::round((value - minValue) / (maxValue - minValue) * steps);
+    return static_cast<uint16_t>(std::clamp(step, 0.0, steps));
+}
+
+double RingBuffer::dequantize(uint16_t value) const
+{
+    return minValue +
+           (static_cast<double>(value) * (maxValue - minValue) / steps);
+}
+
+void RingBuffer::pushRecord(Record record)
+{
+    records[head] = record;
+    head = (head + 1) % records.size();
+    if (count < records.size())
+    {
+        count++;
+    }
+    else
+    {
+        // The gap of the new oldest record was counted from the one it
+        // replaced
+        oldestTimestamp +=
+            static_cast<uint64_t>(records[head].gap) * resolution;
+    }
+    unsynced = std::min(unsynced + 1, records.size());
+}
+
+void RingBuffer::push(uint64_t timestamp, double value)
+{
+    if (count == 0)
+    {
+        oldestTimestamp = timestamp;
+        newestTimestamp = timestamp;
+        pushRecord(Record{0, quantize(value)});
+        return;
+    }
+    // Records only move forward, one resolution period at a time
+    if (timestamp < newestTimestamp + resolution)
+    {
+        return;
+    }
+
+    uint64_t gap = (timestamp - newestTimestamp) / resolution;
+    if (gap / maxGap >= records.size())
+    {
+        // Nothing recorded so far would be kept across this gap
+        count = 0;
+        push(timestamp, value);
+        return;
+    }
+    newestTimestamp += gap * resolution;
+    for (; gap > maxGap; gap -= maxGap)
+    {
+        pushRecord(Record{maxGap, noValue});
+    }
+    pushRecord(Record{static_cast<uint16_t>(gap), quantize(value)});
+}
+
+void RingBuffer::sync()
+{
+    if (!file)
+    {
+        return;
+    }
+
+    // The header is written even without new records, which marks the file
+    // as in use for removeExpiredFiles(). The unsynced records end at head,
+    // and wrap around at most once.
+    size_t first = (head + records.size() - unsynced) % records.size();
+    size_t tail = std::min(unsynced, records.size() - first);
+    bool stored = tail == 0 || storeRecords(first, tail);
+    if (stored && tail < unsynced)
+    {
+        stored = storeRecords(0, unsynced - tail);
+    }
+    if (!stored || !storeHeader())
+    {
+        std::cerr << "Error writing history file, keeping history in memory "
+                     "only\n";
+        file.reset();
+        return;
+    }
+    unsynced = 0;
+}
+
+std::optional<std::chrono::seconds>
+    RingBuffer::fileRetention(const std::filesystem::path& path)
+{
+    // NOLINTNEXTLINE(cppcoreguidelines-pro-type-vararg)
+    int fd = open(path.c_str(), O_RDONLY | O_CLOEXEC);
+    if (fd < 0)
+    {
+        return std::nullopt;
+    }
+    FileHandle handle(fd);
+    Header header{};
+    if (pread(handle.handle(), &header, sizeof(header), 0) !=
+            static_cast<ssize_t>(sizeof(header)) ||
+        header.magic != fileMagic || header.version != fileVersion)
+    {
+        return std::nullopt;
+    }
+    return std::chrono::seconds(static_cast<uint64_t>(header.capacity) *
+                                header.resolution);
+}
+
+void removeExpiredFiles(const std::filesystem::path& directory,
+                        std::filesystem::file_time_type now)
+{
+    std::error_code ec;
+    for (const auto& entry :
+         std::filesystem::directory_iterator(directory, ec))
+    {
+        std::optional<std::chrono::seconds> retention =
+            RingBuffer::fileRetention(entry.path());
+        if (!retention)
+        {
+            continue;
+        }
+        std::filesystem::file_time_type written =
+            std::filesystem::last_write_time(entry.path(), ec);
+        if (ec || written + *retention > now)
+        {
+            continue;
+        }
+        std::filesystem::remove(entry.path(), ec);
+    }
+}
+
+std::vector<Sample> RingBuffer::query(uint64_t start, uint64_t end) const
+{
+    std::vector<Sample> samples;
+    size_t oldest = (head + records.size() - count) % records.size();
+    uint64_t timestamp = oldestTimestamp;
+    for (size_t ii = 0; ii < count; ii++)
+    {
+        const Record& record = records[(oldest + ii) % records.size()];
+        if (ii != 0)
+        {
+            timestamp += static_cast<uint64_t>(record.gap) * resolution;
+        }
+        if (timestamp > end)
+        {
+            break;
+        }
+        if (timestamp < start || record.value == noValue)
+        {
+            continue;
+        }
+        samples.emplace_back(timestamp, dequantize(record.value));
+    }
+    return samples;
+}
+
+Downsampler::Downsampler(std::chrono::seconds resolution, RingBuffer& ring) :
+    resolution(std::max<uint64_t>(resolution.count(), 1)), ring(ring)
+{}
+
+void Downsampler::addSample(double value, uint64_t now)
+{
+    uint64_t period = now - (now % resolution);
+    if (periodCount != 0 && period != periodStart)
+    {
+        ring.push(periodStart, periodSum / static_cast<double>(periodCount));
+        periodCount = 0;
+        periodSum = 0.0;
+    }
+
+    // Periods where the sensor was unavailable are left as gaps
+    if (!std::isfinite(value))
+    {
+        return;
+    }
+    periodStart = period;
+    periodCount++;
+    periodSum += value;
+}
+
+SensorHistory::SensorHistory(std::shared_ptr<sdbusplus::asio::connection> conn,
+                             std::string sensorPath,
+                             std::pair<double, double> range) :
+    dbusConnection(std::move(conn)), sensorPath(std::move(sensorPath)),
+    range(range)
+{}
+
+SensorHistory::~SensorHistory()
+{
+    if (syncTimer)
+    {
+        syncTimer->cancel();
+    }
+}
+
+std::string SensorHistory::fileName(const std::string& sensorPath)
+{
+    constexpr std::string_view sensorsRoot = "/xyz/openbmc_project/sensors/";
+    std::string name = sensorPath;
+    if (name.starts_with(sensorsRoot))
+    {
+        name.erase(0, sensorsRoot.size());
+    }
+    return boost::replace_all_copy(name, "/", "_");
+}
+
+void SensorHistory::loadConfiguration(const SensorData& sensorData,
+                                      const std::string& configInterface)
+{
+    std::string recordPrefix = configInterface + ".History";
+    for (const auto& [intf, cfg] : sensorData)
+    {
+        if (!intf.starts_with(recordPrefix))
+        {
+            continue;
+        }
+        std::optional<Config> config = parseConfig(cfg);
+        if (config)
+        {
+            start(*config);
+        }
+    }
+}
+
+void SensorHistory::start(const Config& config)
+{
+    if (ring)
+    {
+        std::cerr << "Duplicate History record for " << sensorPath << "\n";
+        return;
+    }
+    // The samples are stored relative to the range of the readings
+    if (!std::isfinite(range.first) || !std::isfinite(range.second) ||
+        range.first >= range.second)
+    {
+        std::cerr << "No reading range to keep a history for " << sensorPath
+                  << "\n";
+        return;
+    }
+
+    std::filesystem::path file =
+        std::filesystem::path(config.persistent ? persistentDirectory
+                                                : volatileDirectory) /
+        fileName(sensorPath);
+    ring = std::make_unique<RingBuffer>(config.capacity(), range, file,
+                                        config.resolution);
+    downsampler = std::make_unique<Downsampler>(config.resolution, *ring);
+
+    interface = std::make_shared<sdbusplus::asio::dbus_interface>(
+        dbusConnection, sensorPath, interfaceName);
+    interface->register_property(
+        "Retention", static_cast<uint64_t>(config.retention.count()));
+    interface->register_property(
+        "Resolution", static_cast<uint64_t>(config.resolution.count()));
+    interface->register_property("Persistent", config.persistent);
+    interface->register_method(
+        "GetSamples", [this](uint64_t start, uint64_t end) {
+            return ring->query(start, end);
+        });
+    if (!interface->initialize())
+    {
+        std::cerr << "error initializing history interface for " << sensorPath
+                  << "\n";
+    }
+
+    syncInterval = config.syncInterval;
+    syncTimer.emplace(dbusConnection->get_io_context());
+    scheduleSync();
+}
+
+void SensorHistory::scheduleSync()
+{
+    syncTimer->expires_after(syncInterval);
+    syncTimer->async_wait([weakRef{weak_from_this()}](
+                              const boost::system::error_code& ec) {
+        if (ec == boost::asio::error::operation_aborted)
+        {
+            return; // we're being canceled
+        }
+        auto self = weakRef.lock();
+        if (!self)
+        {
+            return;
+        }
+        self->sync();
+        self->scheduleSync();
+    });
+}
+
+void SensorHistory::sync()
+{
+    ring->sync();
+
+    // Once per process and interval, whichever sensor syncs first
+    static std::optional<std::chrono::steady_clock::time_point> lastCleanup;
+    auto now = std::chrono::steady_clock::now();
+    if (lastCleanup && now - *lastCleanup < cleanupInterval)
+    {
+        return;
+    }
+    lastCleanup = now;
+    auto fileNow = std::filesystem::file_time_type::clock::now();
+    removeExpiredFiles(volatileDirectory, fileNow);
+    removeExpiredFiles(persistentDirectory, fileNow);
+}
+
+void SensorHistory::addSample(double value)
+{
+    if (!downsampler)
+    {
+        return;
+    }
+    auto now = std::chrono::duration_cast<std::chrono::seconds>(
+        std::chrono::system_clock::now().time_since_epoch());
+    downsampler->addSample(value, static_cast<uint64_t>(now.count()));
+}
+
+} // namespace history
@@ -0,0 +1,206 @@
+#pragma once
+
+#include "FileHandle.hpp"
+#include "Utils.hpp"
+
+#include <boost/asio/steady_timer.hpp>
+#include <sdbusplus/asio/connection.hpp>
+#include <sdbusplus/asio/object_server.hpp>
+
+#include <chrono>
+#include <cstddef>
+#include <cstdint>
+#include <filesystem>
+#include <memory>
+#include <optional>
+#include <string>
+#include <tuple>
+#include <utility>
+#include <vector>
+
+namespace history
+{
+
+constexpr const char* interfaceName = "xyz.openbmc_project.Sensor.History";
+
+// tmpfs survives a host crash and a daemon restart, but not a BMC reboot.
+constexpr const char* volatileDirectory = "/run/dbus-sensors/history";
+constexpr const char* persistentDirectory = "/var/lib/dbus-sensors/history";
+
+constexpr std::chrono::seconds defaultRetention{3600};
+constexpr std::chrono::seconds defaultResolution{10};
+// Records are kept in memory and written to the backing file in batches, so
+// that a persistent history doesn't wear out the flash
+constexpr std::chrono::seconds defaultSyncInterval{300};
+// How often the history directories are scanned for expired files
+constexpr std::chrono::hours cleanupInterval{1};
+// Upper bound on the records kept per sensor, whatever the configuration
+constexpr size_t maxRecords = 65536;
+
+struct Config
+{
+    std::chrono::seconds retention = defaultRetention;
+    std::chrono::seconds resolution = defaultResolution;
+    std::chrono::seconds syncInterval = defaultSyncInterval;
+    bool persistent = false;
+
+    size_t capacity() const;
+};
+
+std::optional<Config> parseConfig(const SensorBaseConfigMap& cfg);
+
+// Seconds since the epoch, and the average reading over one resolution period
+using Sample = std::tuple<uint64_t, double>;
+
+// Bounded ring of compressed records, optionally mirrored to a backing file so
+// that it outlives the daemon. A record only takes 4 bytes: its timestamp is
+// the number of resolution periods since the previous record, and its value is
+// quantized to 16 bits over the reading range of the sensor. Pushed records
+// only reach the file with the next sync().
+class RingBuffer
+{
+  public:
+    // The range is the (min, max) of the readings, values outside it are
+    // clamped
+    RingBuffer(size_t capacity, std::pair<double, double> range,
+               const std::filesystem::path& backingFile = {},
+               std::chrono::seconds resolution = defaultResolution);
+    ~RingBuffer();
+
+    RingBuffer(const RingBuffer&) = delete;
+    RingBuffer(RingBuffer&&) = delete;
+    RingBuffer& operator=(const RingBuffer&) = delete;
+    RingBuffer& operator=(RingBuffer&&) = delete;
+
+    void push(uint64_t timestamp, double value);
+    void sync();
+
+    // How long the records of a backing file are kept, if it is one
+    static std::optional<std::chrono::seconds>
+        fileRetention(const std::filesystem::path& path);
+    std::vector<Sample> query(uint64_t start, uint64_t end) const;
+
+    size_t size() const
+    {
+        return count;
+    }
+
+    size_t capacity() const
+    {
+        return records.size();
+    }
+
+  private:
+    struct Record
+    {
+        // Resolution periods since the previous record
+        uint16_t gap;
+        uint16_t value;
+    };
+
+    struct Header
+    {
+        uint32_t magic;
+        uint32_t version;
+        uint32_t capacity;
+        uint32_t head;
+        uint32_t count;
+        uint32_t resolution;
+        uint64_t oldestTimestamp;
+        uint64_t newestTimestamp;
+        double min;
+        double max;
+    };
+
+    static constexpr uint32_t fileMagic = 0x48534244; // "DBSH"
+    static constexpr uint32_t fileVersion = 3;
+    // Marks a record that only bridges a gap too long for one record
+    static constexpr uint16_t noValue = 0xffff;
+    static constexpr uint16_t maxGap = 0xffff;
+    static constexpr double steps = noValue - 1;
+
+    void openBackingFile(const std::filesystem::path& path);
+    bool load();
+    bool storeRecords(size_t first, size_t number);
+    bool storeHeader();
+    void pushRecord(Record record);
+    uint16_t quantize(double value) const;
+    double dequantize(uint16_t value) const;
+
+    std::vector<Record> records;
+    uint32_t resolution;
+    double minValue;
+    double maxValue;
+    // Index of the next record to write
+    size_t head = 0;
+    size_t count = 0;
+    // Seconds since the epoch of the oldest and the newest record
+    uint64_t oldestTimestamp = 0;
+    uint64_t newestTimestamp = 0;
+    // Records pushed since the last sync
+    size_t unsynced = 0;
+    std::optional<FileHandle> file;
+};
+
+// Removes the history files in directory whose records have all expired, the
+// remains of sensors that no longer exist. A file that is still in use is
+// written at least once per sync interval.
+void removeExpiredFiles(const std::filesystem::path& directory,
+                        std::filesystem::file_time_type now);
+
+// Averages the readings of a sensor over each resolution period and keeps the
+// results for the retention period.
+class Downsampler
+{
+  public:
+    Downsampler(std::chrono::seconds resolution, RingBuffer& ring);
+
+    void addSample(double value, uint64_t now);
+
+  private:
+    uint64_t resolution;
+    RingBuffer& ring;
+    uint64_t periodStart = 0;
+    size_t periodCount = 0;
+    double periodSum = 0.0;
+};
+
+class SensorHistory : public std::enable_shared_from_this<SensorHistory>
+{
+  public:
+    // The range is the (min, max) of the sensor's readings
+    SensorHistory(std::shared_ptr<sdbusplus::asio::connection> conn,
+                  std::string sensorPath, std::pair<double, double> range);
+    ~SensorHistory();
+
+    SensorHistory(const SensorHistory&) = delete;
+    SensorHistory(SensorHistory&&) = delete;
+    SensorHistory& operator=(const SensorHistory&) = delete;
+    SensorHistory& operator=(SensorHistory&&) = delete;
+
+    // Starts recording if the sensor's entity-manager configuration has a
+    // History record.
+    void loadConfiguration(const SensorData& sensorData,
+                           const std::string& configInterface);
+
+    void start(const Config& config);
+    void addSample(double value);
+    void sync();
+
+    // "voltage/P12V" becomes "voltage_P12V"
+    static std::string fileName(const std::string& sensorPath);
+
+  private:
+    std::shared_ptr<sdbusplus::asio::connection> dbusConnection;
+    std::string sensorPath;
+    std::pair<double, double> range;
+    std::unique_ptr<RingBuffer> ring;
+    std::unique_ptr<Downsampler> downsampler;
+    std::shared_ptr<sdbusplus::asio::dbus_interface> interface;
+    std::optional<boost::asio::steady_timer> syncTimer;
+    std::chrono::seconds syncInterval = defaultSyncInterval;
+
+    void scheduleSync();
+};
+
+} // namespace history
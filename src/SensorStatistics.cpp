@@ -0,0 +1,259 @@
+#include "SensorStatistics.hpp"
+
+#include "Utils.hpp"
+#include "VariantVisitors.hpp"
+
+#include <sdbusplus/asio/connection.hpp>
+#include <sdbusplus/asio/object_server.hpp>
+#include <sdbusplus/vtable.hpp>
+
+#include <algorithm>
+#include <chrono>
+#include <cmath>
+#include <cstddef>
+#include <cstdint>
+#include <iostream>
+#include <limits>
+#include <memory>
+#include <optional>
+#include <string>
+#include <tuple>
+#include <utility>
+#include <variant>
+#include <vector>
+
+namespace statistics
+{
+
+Window::Window(std::chrono::seconds interval, size_t bucketCount) :
+    length(interval),
+    bucketLength(std::chrono::duration_cast<Clock::duration>(interval) /
+                 static_cast<Clock::rep>(std::max<size_t>(bucketCount, 1)))
+{}
+
+bool Window::expired(const Bucket& bucket, Clock::time_point now) const
+{
+    return bucket.start + bucketLength <= now - length;
+}
+
+void Window::addSample(double value, Clock::time_point now)
+{
+    // NaN is used for readings that are unavailable, don't let them poison
+    // the aggregates
+    if (!std::isfinite(value))
+    {
+        return;
+    }
+
+    while (!buckets.empty() && expired(buckets.front(), now))
+    {
+        buckets.pop_front();
+    }
+
+    if (buckets.empty() || now >= buckets.back().start + bucketLength)
+    {
+        buckets.emplace_back().start = now;
+    }
+
+    Bucket& bucket = buckets.back();
+    bucket.count++;
+    double delta = value - bucket.mean;
+    bucket.mean += delta / static_cast<double>(bucket.count);
+    bucket.m2 += delta * (value - bucket.mean);
+    bucket.minimum = std::min(bucket.minimum, value);
+    bucket.maximum = std::max(bucket.maximum, value);
+}
+
+Summary Window::summarize(Clock::time_point now) const
+{
+    Summary summary;
+    double mean = 0.0;
+    double m2 = 0.0;
+    double minimum = std::numeric_limits<double>::max();
+    double maximum = std::numeric_limits<double>::lowest();
+
+    for (const Bucket& bucket : buckets)
+    {
+        if (expired(bucket, now) || bucket.count == 0)
+        {
+            continue;
+        }
+
+        // Chan et al. parallel combination of the per-bucket moments
+        uint64_t total = summary.count + bucket.count;
+        double delta = bucket.mean - mean;
+        mean += delta * static_cast<double>(bucket.count) /
+                static_cast<double>(total);
+        m2 += bucket.m2 + delta * delta *
+                              static_cast<double>(summary.count) *
+                              static_cast<double>(bucket.count) /
+                              static_cast<double>(total);
+        summary.count = total;
+        minimum = std::min(minimum, bucket.minimum);
+        maximum = std::max(maximum, bucket.maximum);
+    }
+
+    if (summary.count == 0)
+    {
+        return summary;
+    }
+
+    summary.minimum = minimum;
+    summary.maximum = maximum;
+    summary.average = mean;
+    summary.standardDeviation =
+        std::sqrt(m2 / static_cast<double>(summary.count));
+    return summary;
+}
+
+void Window::reset()
+{
+    buckets.clear();
+}
+
+std::optional<WindowConfig> parseWindowConfig(const SensorBaseConfigMap& cfg)
+{
+    auto intervalFind = cfg.find("Interval");
+    if (intervalFind == cfg.end())
+    {
+        std::cerr << "Statistics configuration missing Interval\n";
+        return std::nullopt;
+    }
+    unsigned int interval =
+        std::visit(VariantToUnsignedIntVisitor(), intervalFind->second);
+    if (interval == 0)
+    {
+        std::cerr << "Statistics Interval must be greater than zero\n";
+        return std::nullopt;
+    }
+
+    WindowConfig config{std::chrono::seconds(interval)};
+
+    auto bucketsFind = cfg.find("Buckets");
+    if (bucketsFind != cfg.end())
+    {
+        unsigned int buckets =
+            std::visit(VariantToUnsignedIntVisitor(), bucketsFind->second);
+        if (buckets != 0)
+        {
+            config.bucketCount = buckets;
+        }
+    }
+    return config;
+}
+
+SensorStatistics::SensorStatistics(
+    std::shared_ptr<sdbusplus::asio::connection> conn, std::string sensorPath) :
+    dbusConnection(std::move(conn)), sensorPath(std::move(sensorPath))
+{}
+
+SensorStatistics::~SensorStatistics() = default;
+
+void SensorStatistics::loadConfiguration(const SensorData& sensorData,
+                                         const std::string& configInterface)
+{
+    std::string recordPrefix = configInterface + ".Statistics";
+    for (const auto& [intf, cfg] : sensorData)
+    {
+        if (!intf.starts_with(recordPrefix))
+        {
+            continue;
+        }
+        std::optional<WindowConfig> config = parseWindowConfig(cfg);
+        if (config)
+        {
+            addWindow(*config);
+        }
+    }
+}
+
+void SensorStatistics::addWindow(const WindowConfig& config)
+{
+    for (const Window& window : windows)
+    {
+        if (window.interval() == config.interval)
+        {
+            std::cerr << "Duplicate statistics interval "
+                      << config.interval.count() << " for " << sensorPath
+                      << "\n";
+            return;
+        }
+    }
+    windows.emplace_back(config.interval, config.bucketCount);
+    if (!interface)
+    {
+        createInterface();
+    }
+}
+
+void SensorStatistics::createInterface()
+{
+    // The windows are configured one record at a time, so the properties are
+    // computed from the windows present when they are read. This also lets
+    // consumers query the aggregates at their own pace without a signal per
+    // sample.
+    interface = std::make_shared<sdbusplus::asio::dbus_interface>(
+        dbusConnection, sensorPath, interfaceName);
+    interface->register_property_r(
+        "Intervals", std::vector<uint64_t>{},
+        sdbusplus::vtable::property_::none,
+        [this](const std::vector<uint64_t>&) {
+            std::vector<uint64_t> intervals;
+            for (const Window& window : windows)
+            {
+                intervals.emplace_back(window.interval().count());
+            }
+            return intervals;
+        });
+    interface->register_property_r(
+        "Summaries", std::vector<SummaryEntry>{},
+        sdbusplus::vtable::property_::none,
+        [this](const std::vector<SummaryEntry>&) {
+            return summaries(Window::Clock::now());
+        });
+    interface->register_method("Reset", [this]() { reset(); });
+
+    if (!interface->initialize())
+    {
+        std::cerr << "error initializing statistics interface for "
+                  << sensorPath << "\n";
+    }
+}
+
+void SensorStatistics::addSample(double value)
+{
+    if (windows.empty())
+    {
+        return;
+    }
+    Window::Clock::time_point now = Window::Clock::now();
+    for (Window& window : windows)
+    {
+        window.addSample(value, now);
+    }
+}
+
+std::vector<SensorStatistics::SummaryEntry>
+    SensorStatistics::summaries(Window::Clock::time_point now) const
+{
+    std::vector<SummaryEntry> result;
+    result.reserve(windows.size());
+    for (const Window& window : windows)
+    {
+        Summary summary = window.summarize(now);
+        result.emplace_back(window.interval().count(), summary.count,
+                            summary.minimum, summary.maximum, summary.average,
+                            summary.standardDeviation);
+    }
+    return result;
+}
+
+void SensorStatistics::reset()
+{
+    for (Window& window : windows)
+    {
+        window.reset();
+    }
+}
+
+} // namespace statistics
@@ -0,0 +1,120 @@
+#pragma once
+
+#include "Utils.hpp"
+
+#include <sdbusplus/asio/connection.hpp>
+#include <sdbusplus/asio/object_server.hpp>
+
+#include <chrono>
+#include <cstddef>
+#include <cstdint>
+#include <deque>
+#include <limits>
+#include <memory>
+#include <optional>
+#include <string>
+#include <tuple>
+#include <vector>
+
+namespace statistics
+{
+
+constexpr const char* interfaceName = "xyz.openbmc_project.Sensor.Statistics";
+
+// Each window is split into this many buckets. Memory use per window is
+// therefore constant regardless of the poll rate, and the window slides
+// forward in steps of interval / bucketCount.
+constexpr size_t defaultBucketCount = 60;
+
+struct Summary
+{
+    uint64_t count = 0;
+    double minimum = std::numeric_limits<double>::quiet_NaN();
+    double maximum = std::numeric_limits<double>::quiet_NaN();
+    double average = std::numeric_limits<double>::quiet_NaN();
+    double standardDeviation = std::numeric_limits<double>::quiet_NaN();
+};
+
+class Window
+{
+  public:
+    using Clock = std::chrono::steady_clock;
+
+    explicit Window(std::chrono::seconds interval,
+                    size_t bucketCount = defaultBucketCount);
+
+    void addSample(double value, Clock::time_point now);
+    Summary summarize(Clock::time_point now) const;
+    void reset();
+
+    std::chrono::seconds interval() const
+    {
+        return length;
+    }
+
+  private:
+    struct Bucket
+    {
+        Clock::time_point start;
+        uint64_t count = 0;
+        double mean = 0.0;
+        // Sum of squared differences from the mean (Welford)
+        double m2 = 0.0;
+        double minimum = std::numeric_limits<double>::max();
+        double maximum = std::numeric_limits<double>::lowest();
+    };
+
+    bool expired(const Bucket& bucket, Clock::time_point now) const;
+
+    std::chrono::seconds length;
+    Clock::duration bucketLength;
+    std::deque<Bucket> buckets;
+};
+
+// Parameters of one Exposes "Statistics" record
+struct WindowConfig
+{
+    std::chrono::seconds interval;
+    size_t bucketCount = defaultBucketCount;
+};
+
+std::optional<WindowConfig> parseWindowConfig(const SensorBaseConfigMap& cfg);
+
+// The windows of a sensor, published on the sensor's own object
+class SensorStatistics : public std::enable_shared_from_this<SensorStatistics>
+{
+  public:
+    // One window: its interval, the sample count and the minimum, maximum,
+    // average and standard deviation of the samples
+    using SummaryEntry =
+        std::tuple<uint64_t, uint64_t, double, double, double, double>;
+
+    SensorStatistics(std::shared_ptr<sdbusplus::asio::connection> conn,
+                     std::string sensorPath);
+    ~SensorStatistics();
+
+    SensorStatistics(const SensorStatistics&) = delete;
+    SensorStatistics(SensorStatistics&&) = delete;
+    SensorStatistics& operator=(const SensorStatistics&) = delete;
+    SensorStatistics& operator=(SensorStatistics&&) = delete;
+
+    // Creates a window for each Statistics record of the sensor's
+    // entity-manager configuration.
+    void loadConfiguration(const SensorData& sensorData,
+                           const std::string& configInterface);
+
+    void addWindow(const WindowConfig& config);
+    void addSample(double value);
+    std::vector<SummaryEntry> summaries(Window::Clock::time_point now) const;
+    void reset();
+
+  private:
+    std::shared_ptr<sdbusplus::asio::connection> dbusConnection;
+    std::string sensorPath;
+    std::vector<Window> windows;
+    std::shared_ptr<sdbusplus::asio::dbus_interface> interface;
+
+    void createInterface();
+};
+
+} // namespace statistics
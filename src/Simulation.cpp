@@ -0,0 +1,381 @@
+#include "Simulation.hpp"
+
+#include "SensorPaths.hpp"
+#include "dbus-sensor_config.h"
+
+#include <nlohmann/json.hpp>
+
+#include <algorithm>
+#include <chrono>
+#include <cmath>
+#include <cstdint>
+#include <cstdlib>
+#include <filesystem>
+#include <fstream>
+#include <iostream>
+#include <map>
+#include <memory>
+#include <numbers>
+#include <optional>
+#include <random>
+#include <string>
+#include <utility>
+#include <vector>
+
+namespace simulation
+{
+
+static std::optional<std::chrono::milliseconds>
+    parseSeconds(const nlohmann::json& json, const char* key)
+{
+    auto find = json.find(key);
+    if (find == json.end())
+    {
+        return std::nullopt;
+    }
+    if (!find->is_number() || find->get<double>() < 0.0)
+    {
+        std::cerr << key << " must be a number of seconds\n";
+        return std::nullopt;
+    }
+    return std::chrono::milliseconds(
+        static_cast<int64_t>(std::llround(find->get<double>() * 1000.0)));
+}
+
+static double parseNumber(const nlohmann::json& json, const char* key,
+                          double defaultValue)
+{
+    auto find = json.find(key);
+    if (find == json.end() || !find->is_number())
+    {
+        return defaultValue;
+    }
+    return find->get<double>();
+}
+
+static std::optional<Step> parseStep(const nlohmann::json& json)
+{
+    static const std::map<std::string, StepType> types = {
+        {"Constant", StepType::constant}, {"Ramp", StepType::ramp},
+        {"Sine", StepType::sine},         {"Noise", StepType::noise},
+        {"Step", StepType::step},         {"Fault", StepType::fault}};
+
+    if (!json.is_object())
+    {
+        return std::nullopt;
+    }
+    auto typeFind = json.find("Type");
+    if (typeFind == json.end() || !typeFind->is_string())
+    {
+        std::cerr << "Simulation step without a Type\n";
+        return std::nullopt;
+    }
+    auto type = types.find(typeFind->get<std::string>());
+    if (type == types.end())
+    {
+        std::cerr << "Unknown simulation step " << typeFind->get<std::string>()
+                  << "\n";
+        return std::nullopt;
+    }
+
+    Step step;
+    step.type = type->second;
+    std::optional<std::chrono::milliseconds> duration =
+        parseSeconds(json, "Duration");
+    if (!duration || duration->count() == 0)
+    {
+        std::cerr << "Simulation step needs a positive Duration\n";
+        return std::nullopt;
+    }
+    step.duration = *duration;
+    step.value = parseNumber(json, "Value", 0.0);
+    step.from = parseNumber(json, "From", step.value);
+    step.to = parseNumber(json, "To", step.value);
+    step.amplitude = parseNumber(json, "Amplitude", 0.0);
+    step.noise = parseNumber(json, "Noise", 0.0);
+    step.period = parseSeconds(json, "Period").value_or(step.period);
+    step.at = parseSeconds(json, "At").value_or(step.duration / 2);
+    if (step.period.count() == 0)
+    {
+        std::cerr << "Simulation step needs a positive Period\n";
+        return std::nullopt;
+    }
+    return step;
+}
+
+std::optional<Scenario> parseScenario(const nlohmann::json& json)
+{
+    if (!json.is_object())
+    {
+        return std::nullopt;
+    }
+    auto stepsFind = json.find("Steps");
+    if (stepsFind == json.end() || !stepsFind->is_array() ||
+        stepsFind->empty())
+    {
+        std::cerr << "Simulation scenario needs a list of Steps\n";
+        return std::nullopt;
+    }
+
+    Scenario scenario;
+    for (const nlohmann::json& stepJson : *stepsFind)
+    {
+        std::optional<Step> step = parseStep(stepJson);
+        if (!step)
+        {
+            return std::nullopt;
+        }
+        scenario.steps.emplace_back(*step);
+    }
+
+    auto loopFind = json.find("Loop");
+    if (loopFind != json.end() && loopFind->is_boolean())
+    {
+        scenario.loop = loopFind->get<bool>();
+    }
+    auto seedFind = json.find("Seed");
+    if (seedFind != json.end() && seedFind->is_number_unsigned())
+    {
+        scenario.seed = seedFind->get<uint32_t>();
+    }
+    return scenario;
+}
+
+std::chrono::milliseconds Scenario::length() const
+{
+    std::chrono::milliseconds total{0};
+    for (const Step& step : steps)
+    {
+        total += step.duration;
+    }
+    return total;
+}
+
+static double stepValue(const Step& step, std::chrono::milliseconds elapsed,
+                        std::mt19937& rng)
+{
+    double seconds = std::chrono::duration<double>(elapsed).count();
+    double value = step.value;
+    switch (step.type)
+    {
+        case StepType::constant:
+        case StepType::fault:
+            break;
+        case StepType::ramp:
+            value = step.from +
+                    ((step.to - step.from) * seconds /
+                     std::chrono::duration<double>(step.duration).count());
+            break;
+        case StepType::sine:
+            value = step.value +
+                    (step.amplitude *
+                     std::sin(2.0 * std::numbers::pi * seconds /
+                              std::chrono::duration<double>(step.period)
+                                  .count()));
+            break;
+        case StepType::noise:
+            if (step.amplitude > 0.0)
+            {
+                value += std::normal_distribution<double>(
+                    0.0, step.amplitude)(rng);
+            }
+            break;
+        case StepType::step:
+            value = elapsed < step.at ? step.from : step.to;
+            break;
+    }
+    if (step.noise > 0.0)
+    {
+        value += std::normal_distribution<double>(0.0, step.noise)(rng);
+    }
+    return value;
+}
+
+std::optional<double> Scenario::valueAt(std::chrono::milliseconds elapsed,
+                                        std::mt19937& rng) const
+{
+    std::chrono::milliseconds total = length();
+    if (total.count() == 0)
+    {
+        return std::nullopt;
+    }
+    if (elapsed >= total)
+    {
+        // A scenario that does not loop holds the end of its last step
+        elapsed = loop ? elapsed % total : total;
+    }
+
+    for (const Step& step : steps)
+    {
+        if (elapsed < step.duration || &step == &steps.back())
+        {
+            if (step.type == StepType::fault)
+            {
+                return std::nullopt;
+            }
+            return stepValue(step, std::min(elapsed, step.duration), rng);
+        }
+        elapsed -= step.duration;
+    }
+    return std::nullopt;
+}
+
+Source::Source(const Scenario& scenario, Clock::time_point start) :
+    scenario(scenario), start(start), rng(scenario.seed)
+{}
+
+std::optional<double> Source::read(Clock::time_point now)
+{
+    return scenario.valueAt(
+        std::chrono::duration_cast<std::chrono::milliseconds>(now - start),
+        rng);
+}
+
+// FNV-1a, so that a scenario without a Seed gives the same readings on every
+// run and every platform
+static uint32_t nameSeed(const std::string& name)
+{
+    uint32_t hash = 2166136261U;
+    for (char c : name)
+    {
+        hash ^= static_cast<uint8_t>(c);
+        hash *= 16777619U;
+    }
+    return hash;
+}
+
+std::optional<ScenarioFile> ScenarioFile::parse(const nlohmann::json& json)
+{
+    if (!json.is_object())
+    {
+        return std::nullopt;
+    }
+
+    ScenarioFile file;
+    auto sensorsFind = json.find("Sensors");
+    if (sensorsFind != json.end())
+    {
+        if (!sensorsFind->is_object())
+        {
+            std::cerr << "Sensors must map sensor names to scenarios\n";
+            return std::nullopt;
+        }
+        for (const auto& [name, scenarioJson] : sensorsFind->items())
+        {
+            std::optional<Scenario> scenario = parseScenario(scenarioJson);
+            if (!scenario)
+            {
+                std::cerr << "Invalid simulation scenario for " << name
+                          << "\n";
+                return std::nullopt;
+            }
+            if (!scenarioJson.contains("Seed"))
+            {
+                scenario->seed = nameSeed(name);
+            }
+            file.scenarios.emplace_back(
+                sensor_paths::escapePathForDbus(name), std::move(*scenario));
+        }
+    }
+
+    auto defaultFind = json.find("Default");
+    if (defaultFind != json.end())
+    {
+        file.defaultScenario = parseScenario(*defaultFind);
+        if (!file.defaultScenario)
+        {
+            std::cerr << "Invalid Default simulation scenario\n";
+            return std::nullopt;
+        }
+    }
+    return file;
+}
+
+std::optional<ScenarioFile> ScenarioFile::load(
+    const std::filesystem::path& path)
+{
+    std::ifstream stream(path);
+    if (!stream.good())
+    {
+        std::cerr << "Unable to open " << path << "\n";
+        return std::nullopt;
+    }
+    nlohmann::json json = nlohmann::json::parse(stream, nullptr, false, true);
+    if (json.is_discarded())
+    {
+        std::cerr << "Syntax error in " << path << "\n";
+        return std::nullopt;
+    }
+    return parse(json);
+}
+
+const Scenario* ScenarioFile::find(const std::string& sensorName) const
+{
+    std::string escaped = sensor_paths::escapePathForDbus(sensorName);
+    for (const auto& [name, scenario] : scenarios)
+    {
+        if (name == escaped)
+        {
+            return &scenario;
+        }
+    }
+    if (defaultScenario)
+    {
+        return &*defaultScenario;
+    }
+    return nullptr;
+}
+
+static const std::optional<ScenarioFile>& scenarioFile()
+{
+    static const std::optional<ScenarioFile> file =
+        []() -> std::optional<ScenarioFile> {
+        if constexpr (!simulationSupport)
+        {
+            return std::nullopt;
+        }
+        // NOLINTNEXTLINE(concurrency-mt-unsafe)
+        const char* path = std::getenv(scenarioEnvironment);
+        if (path == nullptr || *path == '\0')
+        {
+            return std::nullopt;
+        }
+        std::optional<ScenarioFile> loaded = ScenarioFile::load(path);
+        if (!loaded)
+        {
+            std::cerr << "Not simulating, " << path << " is invalid\n";
+            return std::nullopt;
+        }
+        std::cerr << "Simulating sensor readings from " << path << "\n";
+        return loaded;
+    }();
+    return file;
+}
+
+bool enabled()
+{
+    return scenarioFile().has_value();
+}
+
+std::shared_ptr<Source> getSource(const std::string& sensorName)
+{
+    const std::optional<ScenarioFile>& file = scenarioFile();
+    if (!file)
+    {
+        return nullptr;
+    }
+    const Scenario* scenario = file->find(sensorName);
+    if (scenario == nullptr)
+    {
+        return nullptr;
+    }
+    Scenario copy = *scenario;
+    if (copy.seed == 0)
+    {
+        // The default scenario differs from sensor to sensor
+        copy.seed = nameSeed(sensor_paths::escapePathForDbus(sensorName));
+    }
+    return std::make_shared<Source>(copy);
+}
+
+} // namespace simulation
@@ -0,0 +1,147 @@
+#pragma once
+
+#include <boost/container/flat_set.hpp>
+#include <nlohmann/json.hpp>
+
+#include <algorithm>
+#include <chrono>
+#include <cstdint>
+#include <filesystem>
+#include <iostream>
+#include <memory>
+#include <optional>
+#include <random>
+#include <string>
+#include <vector>
+
+// Scripted readings for developing and testing without hardware. In a build
+// with the simulation option, when the environment variable below names a
+// scenario file, the hwmon based daemons
+// create their sensors from the entity-manager configuration alone and take
+// the readings from the scenario instead of sysfs.
+namespace simulation
+{
+
+constexpr const char* scenarioEnvironment = "DBUS_SENSORS_SIMULATION";
+
+enum class StepType
+{
+    // Value for the whole step
+    constant,
+    // From to To, linearly over the step
+    ramp,
+    // Value + Amplitude * sin(2 * pi * t / Period)
+    sine,
+    // Value with gaussian noise of standard deviation Amplitude
+    noise,
+    // From until At seconds into the step, then To
+    step,
+    // Every read fails
+    fault
+};
+
+struct Step
+{
+    StepType type = StepType::constant;
+    std::chrono::milliseconds duration{0};
+    double value = 0.0;
+    double from = 0.0;
+    double to = 0.0;
+    double amplitude = 0.0;
+    std::chrono::milliseconds period{1000};
+    std::chrono::milliseconds at{0};
+    // Gaussian noise added on top of any step but a fault
+    double noise = 0.0;
+};
+
+class Scenario
+{
+  public:
+    std::vector<Step> steps;
+    // Start over after the last step, otherwise the last step is held
+    bool loop = true;
+    uint32_t seed = 0;
+
+    std::chrono::milliseconds length() const;
+
+    // The reading elapsed into the scenario, nullopt for a failed read
+    std::optional<double> valueAt(std::chrono::milliseconds elapsed,
+                                  std::mt19937& rng) const;
+};
+
+std::optional<Scenario> parseScenario(const nlohmann::json& json);
+
+// Plays a scenario from the time it is created
+class Source
+{
+  public:
+    using Clock = std::chrono::steady_clock;
+
+    explicit Source(const Scenario& scenario,
+                    Clock::time_point start = Clock::now());
+
+    std::optional<double> read(Clock::time_point now = Clock::now());
+
+  private:
+    Scenario scenario;
+    Clock::time_point start;
+    std::mt19937 rng;
+};
+
+// The scenarios of a file, by sensor name. Sensors without an entry of their
+// own play the "Default" scenario if there is one.
+class ScenarioFile
+{
+  public:
+    static std::optional<ScenarioFile> load(const std::filesystem::path& path);
+    static std::optional<ScenarioFile> parse(const nlohmann::json& json);
+
+    const Scenario* find(const std::string& sensorName) const;
+
+  private:
+    std::vector<std::pair<std::string, Scenario>> scenarios;
+    std::optional<Scenario> defaultScenario;
+};
+
+// True when simulation is built in and the daemon was started with a valid
+// scenario file
+bool enabled();
+
+// A new source for the named sensor, nullptr if simulation is off or the
+// scenario file has nothing for it.
+std::shared_ptr<Source> getSource(const std::string& sensorName);
+
+// The source of a configured sensor that is about to be created while
+// simulating, nullptr if it is to be skipped: on rescans only the sensors
+// that were signaled are recreated, and a sensor without a scenario is not
+// created at all.
+template <typename SensorMap>
+std::shared_ptr<Source> sourceFor(
+    const std::string& sensorName, const SensorMap& sensors,
+    const std::shared_ptr<boost::container::flat_set<std::string>>&
+        sensorsChanged)
+{
+    auto findSensor = sensors.find(sensorName);
+    if (sensorsChanged && findSensor != sensors.end())
+    {
+        auto it = std::ranges::find_if(
+            *sensorsChanged, [&findSensor](const std::string& changed) {
+                return findSensor->second &&
+                       changed.ends_with(findSensor->second->name);
+            });
+        if (it == sensorsChanged->end())
+        {
+            return nullptr;
+        }
+        sensorsChanged->erase(it);
+    }
+
+    std::shared_ptr<Source> source = getSource(sensorName);
+    if (!source)
+    {
+        std::cerr << "No simulation scenario for " << sensorName << "\n";
+    }
+    return source;
+}
+
+} // namespace simulation
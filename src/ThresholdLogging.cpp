@@ -0,0 +1,228 @@
+#include "ThresholdLogging.hpp"
+
+#include "Thresholds.hpp"
+#include "Utils.hpp"
+#include "VariantVisitors.hpp"
+
+#include <sdbusplus/asio/connection.hpp>
+#include <sdbusplus/message/native_types.hpp>
+
+#include <cstdint>
+#include <iostream>
+#include <map>
+#include <memory>
+#include <optional>
+#include <stdexcept>
+#include <string>
+#include <utility>
+#include <variant>
+
+namespace thresholds
+{
+
+static std::string registryLevel(Level level)
+{
+    switch (level)
+    {
+        case Level::CRITICAL:
+        case Level::SOFTSHUTDOWN:
+        case Level::HARDSHUTDOWN:
+            return "Critical";
+        default:
+            return "Warning";
+    }
+}
+
+static std::string levelName(Level level)
+{
+    for (const ThresholdDefinition& prop : thresProp)
+    {
+        if (prop.level == level)
+        {
+            return prop.levelName;
+        }
+    }
+    return "";
+}
+
+std::string messageId(Level level, Direction direction)
+{
+    std::string name = levelName(level);
+    if (level == Level::RATEOFCHANGE)
+    {
+        name = levelName(Level::WARNING);
+    }
+    std::string bound = direction == Direction::HIGH ? "ReadingAboveUpper"
+                                                     : "ReadingBelowLower";
+    return "xyz.openbmc_project.Sensor.Threshold." + bound + name + "Threshold";
+}
+
+std::string redfishMessageId(Level level, Direction direction)
+{
+    std::string side = direction == Direction::HIGH ? "High" : "Low";
+    return "OpenBMC.0.1.SensorThreshold" + registryLevel(level) + side +
+           "Going" + side;
+}
+
+std::string logSeverity(Level level)
+{
+    switch (level)
+    {
+        case Level::CRITICAL:
+        case Level::SOFTSHUTDOWN:
+        case Level::HARDSHUTDOWN:
+            return "xyz.openbmc_project.Logging.Entry.Level.Critical";
+        default:
+            return "xyz.openbmc_project.Logging.Entry.Level.Warning";
+    }
+}
+
+std::optional<bool> parseEventLogConfig(const SensorBaseConfigMap& cfg)
+{
+    auto find = cfg.find("EventLog");
+    if (find == cfg.end())
+    {
+        return std::nullopt;
+    }
+    try
+    {
+        return std::visit(VariantToUnsignedIntVisitor(), find->second) != 0U;
+    }
+    catch (const std::invalid_argument&)
+    {
+        std::cerr << "EventLog must be a boolean\n";
+        return std::nullopt;
+    }
+}
+
+LogEntries::LogEntries(std::shared_ptr<sdbusplus::asio::connection> conn,
+                       const std::string& sensorName,
+                       const std::string& sensorPath) :
+    dbusConnection(std::move(conn)), sensorName(sensorName),
+    sensorPath(sensorPath)
+{}
+
+void LogEntries::loadConfiguration(const SensorData& sensorData,
+                                   const std::string& configInterface)
+{
+    // Only the sensor's own record is needed, not its threshold records
+    auto cfgFind = sensorData.find(configInterface);
+    if (cfgFind == sensorData.end())
+    {
+        return;
+    }
+    enable = parseEventLogConfig(cfgFind->second).value_or(false);
+    if (!enable)
+    {
+        return;
+    }
+    for (auto& [key, entry] : entries)
+    {
+        if (entry.asserted && !entry.logged)
+        {
+            create(key.first, key.second);
+        }
+    }
+}
+
+void LogEntries::assertAlarm(Level level, Direction direction, double reading,
+                             double thresholdValue)
+{
+    // The alarm is remembered even while logging is off, so that it can be
+    // logged once the configuration arrives
+    Entry& entry = entries[{level, direction}];
+    if (entry.asserted)
+    {
+        return;
+    }
+    entry.asserted = true;
+    entry.logged = false;
+    entry.path.clear();
+    entry.reading = reading;
+    entry.thresholdValue = thresholdValue;
+    if (enable)
+    {
+        create(level, direction);
+    }
+}
+
+void LogEntries::create(Level level, Direction direction)
+{
+    Entry& entry = entries[{level, direction}];
+    entry.logged = true;
+    uint64_t generation = ++entry.generation;
+
+    std::string side = direction == Direction::HIGH ? "High" : "Low";
+    std::string readingText = std::to_string(entry.reading);
+    std::string thresholdText = std::to_string(entry.thresholdValue);
+    std::map<std::string, std::string> additionalData = {
+        {"SENSOR_NAME", sensorName},
+        {"SENSOR_PATH", sensorPath},
+        {"THRESHOLD_LEVEL", levelName(level)},
+        {"DIRECTION", side},
+        {"READING", readingText},
+        {"THRESHOLD_VALUE", thresholdText},
+        {"REDFISH_MESSAGE_ID", redfishMessageId(level, direction)},
+        {"REDFISH_MESSAGE_ARGS",
+         sensorName + "," + readingText + "," + thresholdText}};
+
+    std::weak_ptr<LogEntries> weakRef = weak_from_this();
+    dbusConnection->async_method_call(
+        [weakRef, level, direction,
+         generation](const boost::system::error_code& ec,
+                     const sdbusplus::message::object_path& entryPath) {
+            if (ec)
+            {
+                std::cerr << "Failed to create threshold log entry: "
+                          << ec.message() << "\n";
+                return;
+            }
+            auto self = weakRef.lock();
+            if (!self)
+            {
+                return;
+            }
+            Entry& entry = self->entries[{level, direction}];
+            if (!entry.asserted || entry.generation != generation)
+            {
+                // The alarm went away while the entry was being created
+                self->resolve(entryPath.str);
+                return;
+            }
+            entry.path = entryPath.str;
+        },
+        logging::busName, logging::path, logging::createInterface, "Create",
+        messageId(level, direction), logSeverity(level), additionalData);
+}
+
+void LogEntries::deassertAlarm(Level level, Direction direction)
+{
+    auto find = entries.find({level, direction});
+    if (find == entries.end() || !find->second.asserted)
+    {
+        return;
+    }
+    find->second.asserted = false;
+    find->second.logged = false;
+    if (!find->second.path.empty())
+    {
+        resolve(find->second.path);
+        find->second.path.clear();
+    }
+}
+
+void LogEntries::resolve(const std::string& entryPath)
+{
+    dbusConnection->async_method_call(
+        [entryPath](const boost::system::error_code& ec) {
+            if (ec)
+            {
+                std::cerr << "Failed to resolve " << entryPath << ": "
+                          << ec.message() << "\n";
+            }
+        },
+        logging::busName, entryPath, properties::interface, properties::set,
+        logging::entryInterface, "Resolved", std::variant<bool>(true));
+}
+
+} // namespace thresholds
@@ -0,0 +1,90 @@
+#pragma once
+
+#include "Thresholds.hpp"
+#include "Utils.hpp"
+
+#include <sdbusplus/asio/connection.hpp>
+
+#include <cstdint>
+#include <map>
+#include <memory>
+#include <optional>
+#include <string>
+#include <utility>
+
+namespace thresholds
+{
+
+namespace logging
+{
+constexpr const char* busName = "xyz.openbmc_project.Logging";
+constexpr const char* path = "/xyz/openbmc_project/logging";
+constexpr const char* createInterface = "xyz.openbmc_project.Logging.Create";
+constexpr const char* entryInterface = "xyz.openbmc_project.Logging.Entry";
+} // namespace logging
+
+// "xyz.openbmc_project.Sensor.Threshold.ReadingAboveUpperCriticalThreshold" and
+// friends from phosphor-dbus-interfaces. There are no rate of change events,
+// those thresholds are reported as warnings.
+std::string messageId(Level level, Direction direction);
+
+// "SensorThresholdCriticalHighGoingHigh" and friends from the OpenBMC message
+// registry. The registry only knows warning and critical thresholds, so the
+// other levels are reported as the closer of the two.
+std::string redfishMessageId(Level level, Direction direction);
+
+// xyz.openbmc_project.Logging.Entry.Level of an entry for the given level
+std::string logSeverity(Level level);
+
+// The "EventLog" property of a sensor's Exposes record, if it has one
+std::optional<bool> parseEventLogConfig(const SensorBaseConfigMap& cfg);
+
+// Mirrors the threshold alarms of one sensor in the phosphor-logging event log
+// when its configuration asks for it: an entry is created when an alarm is
+// asserted and marked resolved when it is deasserted again.
+class LogEntries : public std::enable_shared_from_this<LogEntries>
+{
+  public:
+    LogEntries(std::shared_ptr<sdbusplus::asio::connection> conn,
+               const std::string& sensorName, const std::string& sensorPath);
+
+    // Reads the "EventLog" property of the sensor's own configuration
+    // interface, entries are only created once it is known to be true. Alarms
+    // asserted before then are logged when it is loaded.
+    void loadConfiguration(const SensorData& sensorData,
+                           const std::string& configInterface);
+
+    void assertAlarm(Level level, Direction direction, double reading,
+                     double thresholdValue);
+    void deassertAlarm(Level level, Direction direction);
+
+    bool enabled() const
+    {
+        return enable;
+    }
+
+  private:
+    struct Entry
+    {
+        // Empty until phosphor-logging has answered
+        std::string path;
+        bool asserted = false;
+        // Whether an entry was requested for the current assertion
+        bool logged = false;
+        double reading = 0.0;
+        double thresholdValue = 0.0;
+        // Tells the answer to an earlier assertion from the current one
+        uint64_t generation = 0;
+    };
+
+    std::shared_ptr<sdbusplus::asio::connection> dbusConnection;
+    std::string sensorName;
+    std::string sensorPath;
+    bool enable = false;
+    std::map<std::pair<Level, Direction>, Entry> entries;
+
+    void create(Level level, Direction direction);
+    void resolve(const std::string& entryPath);
+};
+
+} // namespace thresholds
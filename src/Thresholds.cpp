@@ -13,8 +13,10 @@
 #include <sdbusplus/exception.hpp>
 #include <sdbusplus/message.hpp>
 
+#include <algorithm>
 #include <array>
 #include <chrono>
+#include <cmath>
 #include <cstddef>
 #include <cstdint>
 #include <iostream>
@@ -29,10 +31,16 @@
 static constexpr bool debug = false;
 namespace thresholds
 {
+static constexpr std::chrono::seconds defaultRateWindow{10};
+
 Level findThresholdLevel(uint8_t sev)
 {
     for (const ThresholdDefinition& prop : thresProp)
     {
+        if (prop.level == Level::RATEOFCHANGE)
+        {
+            continue; // only selected by Type
+        }
         if (prop.sevOrder == sev)
         {
             return prop.level;
@@ -41,6 +49,44 @@ Level findThresholdLevel(uint8_t sev)
     return Level::ERROR;
 }
 
+static bool isRateOfChange(const SensorBaseConfigMap& cfg)
+{
+    auto typeFind = cfg.find("Type");
+    return typeFind != cfg.end() &&
+           std::visit(VariantToStringVisitor(), typeFind->second) ==
+               "RateOfChange";
+}
+
+static Level findThresholdLevel(const SensorBaseConfigMap& cfg,
+                                SensorBaseConfigMap::const_iterator severity)
+{
+    if (isRateOfChange(cfg))
+    {
+        return Level::RATEOFCHANGE;
+    }
+    return findThresholdLevel(
+        std::visit(VariantToUnsignedIntVisitor(), severity->second));
+}
+
+bool Threshold::debounce(bool assert)
+{
+    if (assert)
+    {
+        deassertStreak = 0;
+        assertStreak = std::min(assertStreak + 1, assertSamples);
+        return assertStreak >= assertSamples;
+    }
+    assertStreak = 0;
+    deassertStreak = std::min(deassertStreak + 1, deassertSamples);
+    return deassertStreak >= deassertSamples;
+}
+
+void Threshold::resetDebounce()
+{
+    assertStreak = 0;
+    deassertStreak = 0;
+}
+
 Direction findThresholdDirection(const std::string& direct)
 {
     if (direct == "greater than")
@@ -108,20 +154,18 @@ bool parseThresholdsFromConfig(
         auto directionFind = cfg.find("Direction");
         auto severityFind = cfg.find("Severity");
         auto valueFind = cfg.find("Value");
-        if (valueFind == cfg.end() || severityFind == cfg.end() ||
-            directionFind == cfg.end())
+        if (valueFind == cfg.end() || directionFind == cfg.end() ||
+            (severityFind == cfg.end() && !isRateOfChange(cfg)))
         {
             std::cerr << "Malformed threshold on configuration interface "
                       << intf << "\n";
             return false;
         }
-        unsigned int severity =
-            std::visit(VariantToUnsignedIntVisitor(), severityFind->second);
 
         std::string directions =
             std::visit(VariantToStringVisitor(), directionFind->second);
 
-        Level level = findThresholdLevel(severity);
+        Level level = findThresholdLevel(cfg, severityFind);
         Direction direction = findThresholdDirection(directions);
 
         if ((level == Level::ERROR) || (direction == Direction::ERROR))
@@ -130,24 +174,79 @@ bool parseThresholdsFromConfig(
         }
         double val = std::visit(VariantToDoubleVisitor(), valueFind->second);
 
-        thresholdVector.emplace_back(level, direction, val, hysteresis);
+        bool writeable = true;
+        auto writeableFind = cfg.find("Writeable");
+        if (writeableFind != cfg.end())
+        {
+            writeable = std::visit(VariantToUnsignedIntVisitor(),
+                                   writeableFind->second) != 0U;
+        }
+
+        Threshold& threshold = thresholdVector.emplace_back(
+            level, direction, val, hysteresis, writeable);
+        threshold.hysteresisConfigured = hysteresisFind != cfg.end();
+
+        auto latchedFind = cfg.find("Latched");
+        if (latchedFind != cfg.end())
+        {
+            threshold.latched = std::visit(VariantToUnsignedIntVisitor(),
+                                           latchedFind->second) != 0U;
+        }
+
+        auto assertSamplesFind = cfg.find("AssertSamples");
+        if (assertSamplesFind != cfg.end())
+        {
+            threshold.assertSamples = std::max(
+                1U, std::visit(VariantToUnsignedIntVisitor(),
+                               assertSamplesFind->second));
+        }
+        auto deassertSamplesFind = cfg.find("DeassertSamples");
+        if (deassertSamplesFind != cfg.end())
+        {
+            threshold.deassertSamples = std::max(
+                1U, std::visit(VariantToUnsignedIntVisitor(),
+                               deassertSamplesFind->second));
+        }
+
+        if (level == Level::RATEOFCHANGE)
+        {
+            std::chrono::duration<double> window = defaultRateWindow;
+            auto windowFind = cfg.find("Window");
+            if (windowFind != cfg.end())
+            {
+                window = std::chrono::duration<double>(
+                    std::visit(VariantToDoubleVisitor(), windowFind->second));
+            }
+            threshold.rateWindow =
+                std::chrono::duration_cast<std::chrono::milliseconds>(window);
+            if (threshold.rateWindow.count() <= 0)
+            {
+                std::cerr << "Invalid rate-of-change Window on configuration "
+                             "interface "
+                          << intf << "\n";
+                thresholdVector.pop_back();
+            }
+        }
     }
     return true;
 }
 
-void persistThreshold(const std::string& path, const std::string& baseInterface,
-                      const thresholds::Threshold& threshold,
-                      std::shared_ptr<sdbusplus::asio::connection>& conn,
-                      size_t thresholdCount, const std::string& labelMatch)
+// Writes one property of the entity-manager record that threshold was parsed
+// from
+static void persistThresholdProperty(
+    const std::string& path, const std::string& baseInterface,
+    const thresholds::Threshold& threshold,
+    std::shared_ptr<sdbusplus::asio::connection>& conn, size_t thresholdCount,
+    const std::string& labelMatch, const std::string& property, double newValue)
 {
     for (size_t ii = 0; ii < thresholdCount; ii++)
     {
         std::string thresholdInterface =
             baseInterface + ".Thresholds" + std::to_string(ii);
         conn->async_method_call(
-            [&, path, threshold, thresholdInterface,
-             labelMatch](const boost::system::error_code& ec,
-                         const SensorBaseConfigMap& result) {
+            [&, path, threshold, thresholdInterface, labelMatch, property,
+             newValue](const boost::system::error_code& ec,
+                       const SensorBaseConfigMap& result) {
                 if (ec)
                 {
                     return; // threshold not supported
@@ -172,24 +271,32 @@ void persistThreshold(const std::string& path, const std::string& baseInterface,
                 auto directionFind = result.find("Direction");
                 auto severityFind = result.find("Severity");
                 auto valueFind = result.find("Value");
-                if (valueFind == result.end() || severityFind == result.end() ||
-                    directionFind == result.end())
+                if (valueFind == result.end() || directionFind == result.end() ||
+                    (severityFind == result.end() && !isRateOfChange(result)))
                 {
                     std::cerr << "Malformed threshold in configuration\n";
                     return;
                 }
-                unsigned int severity = std::visit(
-                    VariantToUnsignedIntVisitor(), severityFind->second);
 
                 std::string dir =
                     std::visit(VariantToStringVisitor(), directionFind->second);
-                if ((findThresholdLevel(severity) != threshold.level) ||
+                if ((findThresholdLevel(result, severityFind) !=
+                     threshold.level) ||
                     (findThresholdDirection(dir) != threshold.direction))
                 {
                     return; // not the droid we're looking for
                 }
 
-                std::variant<double> value(threshold.value);
+                // entity-manager only has the properties of the record
+                if (result.find(property) == result.end())
+                {
+                    std::cerr << "Unable to persist " << property << ", "
+                              << thresholdInterface << " of " << path
+                              << " doesn't have it\n";
+                    return;
+                }
+
+                std::variant<double> value(newValue);
                 conn->async_method_call(
                     [](const boost::system::error_code& ec) {
                         if (ec)
@@ -199,13 +306,34 @@ void persistThreshold(const std::string& path, const std::string& baseInterface,
                         }
                     },
                     entityManagerName, path, "org.freedesktop.DBus.Properties",
-                    "Set", thresholdInterface, "Value", value);
+                    "Set", thresholdInterface, property, value);
             },
             entityManagerName, path, "org.freedesktop.DBus.Properties",
             "GetAll", thresholdInterface);
     }
 }
 
+void persistThreshold(const std::string& path, const std::string& baseInterface,
+                      const thresholds::Threshold& threshold,
+                      std::shared_ptr<sdbusplus::asio::connection>& conn,
+                      size_t thresholdCount, const std::string& labelMatch)
+{
+    persistThresholdProperty(path, baseInterface, threshold, conn,
+                             thresholdCount, labelMatch, "Value",
+                             threshold.value);
+}
+
+void persistHysteresis(const std::string& path,
+                       const std::string& baseInterface,
+                       const thresholds::Threshold& threshold,
+                       std::shared_ptr<sdbusplus::asio::connection>& conn,
+                       size_t thresholdCount, const std::string& labelMatch)
+{
+    persistThresholdProperty(path, baseInterface, threshold, conn,
+                             thresholdCount, labelMatch, "Hysteresis",
+                             threshold.hysteresis);
+}
+
 void updateThresholds(Sensor* sensor)
 {
     for (const auto& threshold : sensor->thresholds)
@@ -225,6 +353,9 @@ void updateThresholds(Sensor* sensor)
             continue;
         }
         interface->set_property(property, threshold.value);
+        interface->set_property(
+            Sensor::propertyHysteresis(threshold.level, threshold.direction),
+            threshold.hysteresis);
     }
 }
 
@@ -249,7 +380,8 @@ struct ChangeParam
     double assertValue;
 };
 
-static std::vector<ChangeParam> checkThresholds(Sensor* sensor, double value)
+static std::vector<ChangeParam> checkThresholds(Sensor* sensor,
+                                                double reading)
 {
     std::vector<ChangeParam> thresholdChanges;
     if (sensor->thresholds.empty())
@@ -257,8 +389,20 @@ static std::vector<ChangeParam> checkThresholds(Sensor* sensor, double value)
         return thresholdChanges;
     }
 
+    RateOfChange::Clock::time_point now = RateOfChange::Clock::now();
     for (auto& threshold : sensor->thresholds)
     {
+        double value = reading;
+        if (threshold.level == Level::RATEOFCHANGE)
+        {
+            // Compare the slope rather than the reading itself
+            value = sensor->rateOfChange.slope(threshold.rateWindow, now);
+            if (std::isnan(value))
+            {
+                continue;
+            }
+        }
+
         // Use "Schmitt trigger" logic to avoid threshold trigger spam,
         // if value is noisy while hovering very close to a threshold.
         // When a threshold is crossed, indicate true immediately,
@@ -268,21 +412,29 @@ static std::vector<ChangeParam> checkThresholds(Sensor* sensor, double value)
         {
             if (value >= threshold.value)
             {
-                thresholdChanges.emplace_back(threshold, true, value);
-                if (++cHiTrue < assertLogCount)
+                if (threshold.debounce(true))
                 {
-                    std::cerr << "Sensor " << sensor->name << " high threshold "
-                              << threshold.value << " assert: value " << value
-                              << " raw data " << sensor->rawValue << "\n";
+                    thresholdChanges.emplace_back(threshold, true, value);
+                    if (++cHiTrue < assertLogCount)
+                    {
+                        std::cerr << "Sensor " << sensor->name
+                                  << " high threshold " << threshold.value
+                                  << " assert: value " << value << " raw data "
+                                  << sensor->rawValue << "\n";
+                    }
                 }
             }
             else if (value < (threshold.value - threshold.hysteresis))
             {
-                thresholdChanges.emplace_back(threshold, false, value);
-                ++cHiFalse;
+                if (threshold.debounce(false))
+                {
+                    thresholdChanges.emplace_back(threshold, false, value);
+                    ++cHiFalse;
+                }
             }
             else
             {
+                threshold.resetDebounce();
                 ++cHiMidstate;
             }
         }
@@ -290,22 +442,29 @@ static std::vector<ChangeParam> checkThresholds(Sensor* sensor, double value)
         {
             if (value <= threshold.value)
             {
-                thresholdChanges.emplace_back(threshold, true, value);
-                if (++cLoTrue < assertLogCount)
+                if (threshold.debounce(true))
                 {
-                    std::cerr
-                        << "Sensor " << sensor->name << " low threshold "
-                        << threshold.value << " assert: value " << sensor->value
-                        << " raw data " << sensor->rawValue << "\n";
+                    thresholdChanges.emplace_back(threshold, true, value);
+                    if (++cLoTrue < assertLogCount)
+                    {
+                        std::cerr << "Sensor " << sensor->name
+                                  << " low threshold " << threshold.value
+                                  << " assert: value " << value << " raw data "
+                                  << sensor->rawValue << "\n";
+                    }
                 }
             }
             else if (value > (threshold.value + threshold.hysteresis))
             {
-                thresholdChanges.emplace_back(threshold, false, value);
-                ++cLoFalse;
+                if (threshold.debounce(false))
+                {
+                    thresholdChanges.emplace_back(threshold, false, value);
+                    ++cLoFalse;
+                }
             }
             else
             {
+                threshold.resetDebounce();
                 ++cLoMidstate;
             }
         }
@@ -332,6 +491,57 @@ static std::vector<ChangeParam> checkThresholds(Sensor* sensor, double value)
     return thresholdChanges;
 }
 
+void RateOfChange::addWindow(std::chrono::milliseconds window)
+{
+    maxWindow = std::max(maxWindow, window);
+}
+
+void RateOfChange::addSample(double value, Clock::time_point now)
+{
+    if (!enabled() || !std::isfinite(value))
+    {
+        return;
+    }
+    while (!samples.empty() && samples.front().first < now - maxWindow)
+    {
+        samples.pop_front();
+    }
+    samples.emplace_back(now, value);
+}
+
+double RateOfChange::slope(std::chrono::milliseconds window,
+                           Clock::time_point now) const
+{
+    // Fit against seconds relative to the newest reading to keep the sums
+    // small
+    size_t count = 0;
+    double sumT = 0.0;
+    double sumV = 0.0;
+    double sumTT = 0.0;
+    double sumTV = 0.0;
+    for (const auto& [time, value] : samples)
+    {
+        if (time < now - window)
+        {
+            continue;
+        }
+        double t = std::chrono::duration<double>(time - now).count();
+        count++;
+        sumT += t;
+        sumV += value;
+        sumTT += t * t;
+        sumTV += t * value;
+    }
+
+    double n = static_cast<double>(count);
+    double denominator = (n * sumTT) - (sumT * sumT);
+    if (count < 2 || denominator <= 0.0)
+    {
+        return std::numeric_limits<double>::quiet_NaN();
+    }
+    return ((n * sumTV) - (sumT * sumV)) / denominator;
+}
+
 void ThresholdTimer::startTimer(const std::weak_ptr<Sensor>& weakSensor,
                                 const Threshold& threshold, bool assert,
                                 double assertValue)
@@ -442,9 +652,47 @@ void checkThresholdsPowerDelay(const std::weak_ptr<Sensor>& weakSensor,
     }
 }
 
-void assertThresholds(Sensor* sensor, double assertValue,
-                      thresholds::Level level, thresholds::Direction direction,
-                      bool assert)
+static bool isLatched(Sensor* sensor, thresholds::Level level,
+                      thresholds::Direction direction)
+{
+    for (const auto& threshold : sensor->thresholds)
+    {
+        if (threshold.level == level && threshold.direction == direction)
+        {
+            return threshold.latched;
+        }
+    }
+    return false;
+}
+
+static void logThresholdAlarm(Sensor* sensor, double assertValue,
+                              thresholds::Level level,
+                              thresholds::Direction direction, bool assert)
+{
+    if (!sensor->thresholdLogEntries)
+    {
+        return;
+    }
+    if (!assert)
+    {
+        sensor->thresholdLogEntries->deassertAlarm(level, direction);
+        return;
+    }
+    double thresholdValue = std::numeric_limits<double>::quiet_NaN();
+    for (const auto& threshold : sensor->thresholds)
+    {
+        if (threshold.level == level && threshold.direction == direction)
+        {
+            thresholdValue = threshold.value;
+        }
+    }
+    sensor->thresholdLogEntries->assertAlarm(level, direction, assertValue,
+                                             thresholdValue);
+}
+
+static void setThresholdAlarm(Sensor* sensor, double assertValue,
+                              thresholds::Level level,
+                              thresholds::Direction direction, bool assert)
 {
     std::shared_ptr<sdbusplus::asio::dbus_interface> interface =
         sensor->getThresholdInterface(level);
@@ -463,6 +711,8 @@ void assertThresholds(Sensor* sensor, double assertValue,
     }
     if (interface->set_property<bool, true>(property, assert))
     {
+        sensor->thresholdEvents.record(level, direction, assert, assertValue);
+        logThresholdAlarm(sensor, assertValue, level, direction, assert);
         try
         {
             // msg.get_path() is interface->get_object_path()
@@ -481,6 +731,98 @@ void assertThresholds(Sensor* sensor, double assertValue,
     }
 }
 
+void assertThresholds(Sensor* sensor, double assertValue,
+                      thresholds::Level level, thresholds::Direction direction,
+                      bool assert)
+{
+    if (!assert && isLatched(sensor, level, direction))
+    {
+        return; // held until clearLatchedAlarms()
+    }
+    setThresholdAlarm(sensor, assertValue, level, direction, assert);
+}
+
+bool Threshold::cleared(double value) const
+{
+    if (std::isnan(value))
+    {
+        return false;
+    }
+    if (direction == Direction::HIGH)
+    {
+        return value < this->value - hysteresis;
+    }
+    if (direction == Direction::LOW)
+    {
+        return value > this->value + hysteresis;
+    }
+    return false;
+}
+
+void clearLatchedAlarms(Sensor* sensor)
+{
+    RateOfChange::Clock::time_point now = RateOfChange::Clock::now();
+    for (const auto& threshold : sensor->thresholds)
+    {
+        if (!threshold.latched)
+        {
+            continue;
+        }
+        double value = sensor->value;
+        if (threshold.level == Level::RATEOFCHANGE)
+        {
+            value = sensor->rateOfChange.slope(threshold.rateWindow, now);
+        }
+        // Alarms whose condition is still present stay latched
+        if (!threshold.cleared(value))
+        {
+            continue;
+        }
+        setThresholdAlarm(sensor, sensor->value, threshold.level,
+                          threshold.direction, false);
+    }
+}
+
+void EventLog::record(Level level, Direction direction, bool asserted,
+                      double value)
+{
+    auto now = std::chrono::duration_cast<std::chrono::milliseconds>(
+        std::chrono::system_clock::now().time_since_epoch());
+    record(Event{static_cast<uint64_t>(now.count()), level, direction,
+                 asserted, value});
+}
+
+void EventLog::record(const Event& event)
+{
+    if (events.size() >= capacity)
+    {
+        events.pop_front();
+    }
+    events.push_back(event);
+}
+
+std::vector<EventLog::Entry> EventLog::entries() const
+{
+    std::vector<Entry> result;
+    result.reserve(events.size());
+    for (const Event& event : events)
+    {
+        std::string levelName;
+        for (const ThresholdDefinition& prop : thresProp)
+        {
+            if (prop.level == event.level)
+            {
+                levelName = prop.levelName;
+            }
+        }
+        result.emplace_back(event.timestamp, levelName,
+                            event.direction == Direction::HIGH ? "High"
+                                                               : "Low",
+                            event.asserted, event.value);
+    }
+    return result;
+}
+
 bool parseThresholdsFromAttr(
     std::vector<thresholds::Threshold>& thresholdVector,
     const std::string& inputPath, const double& scaleFactor,
@@ -6,9 +6,13 @@
 #include <boost/asio/steady_timer.hpp>
 #include <nlohmann/json.hpp>
 
+#include <chrono>
+#include <deque>
+#include <limits>
 #include <list>
 #include <memory>
 #include <string>
+#include <tuple>
 #include <utility>
 #include <vector>
 
@@ -22,6 +26,7 @@ enum class Level
     PERFORMANCELOSS,
     SOFTSHUTDOWN,
     HARDSHUTDOWN,
+    RATEOFCHANGE,
     ERROR
 };
 enum class Direction
@@ -44,6 +49,29 @@ struct Threshold
     double value;
     double hysteresis;
     bool writeable;
+    // Whether the entity-manager record has a Hysteresis property, which a
+    // new hysteresis can be persisted to
+    bool hysteresisConfigured = false;
+    // Only used by Level::RATEOFCHANGE, where value and hysteresis are in
+    // units per second and the slope is estimated over this window.
+    std::chrono::milliseconds rateWindow{0};
+    // Number of consecutive readings beyond the threshold needed to assert
+    // it, and within the hysteresis needed to deassert it again.
+    size_t assertSamples = 1;
+    size_t deassertSamples = 1;
+    size_t assertStreak = 0;
+    size_t deassertStreak = 0;
+    // A latched alarm stays asserted until clearLatchedAlarms() is called
+    bool latched = false;
+
+    // Records the outcome of one reading, returns true once it has been
+    // seen often enough in a row to be acted on.
+    bool debounce(bool assert);
+    // Readings inside the hysteresis band break both streaks
+    void resetDebounce();
+    // Whether value is far enough back from the threshold to deassert it,
+    // unknown values never are
+    bool cleared(double value) const;
 
     bool operator==(const Threshold& rhs) const
     {
@@ -52,10 +80,72 @@ struct Threshold
     }
 };
 
+// Keeps the recent readings of a sensor, so that rate-of-change thresholds can
+// estimate how fast it is moving.
+class RateOfChange
+{
+  public:
+    using Clock = std::chrono::steady_clock;
+
+    // Readings are kept for the longest window any threshold asks for
+    void addWindow(std::chrono::milliseconds window);
+    void addSample(double value, Clock::time_point now);
+
+    // Least-squares slope in units per second of the readings taken within
+    // window before now, NaN if there are not enough of them.
+    double slope(std::chrono::milliseconds window, Clock::time_point now) const;
+
+    bool enabled() const
+    {
+        return maxWindow.count() > 0;
+    }
+
+  private:
+    std::chrono::milliseconds maxWindow{0};
+    std::deque<std::pair<Clock::time_point, double>> samples;
+};
+
 void assertThresholds(Sensor* sensor, double assertValue,
                       thresholds::Level level, thresholds::Direction direction,
                       bool assert);
 
+void clearLatchedAlarms(Sensor* sensor);
+
+constexpr const char* eventsInterfaceName =
+    "xyz.openbmc_project.Sensor.ThresholdEvents";
+constexpr size_t eventLogSize = 32;
+
+// The most recent alarm transitions of a sensor
+class EventLog
+{
+  public:
+    struct Event
+    {
+        // Milliseconds since the epoch
+        uint64_t timestamp;
+        Level level;
+        Direction direction;
+        bool asserted;
+        double value;
+    };
+
+    // As returned over D-Bus: timestamp, level name ("Warning", ...),
+    // direction ("High" or "Low"), asserted and the value that caused it
+    using Entry = std::tuple<uint64_t, std::string, std::string, bool, double>;
+
+    explicit EventLog(size_t capacity = eventLogSize) : capacity(capacity) {}
+
+    void record(Level level, Direction direction, bool asserted, double value);
+    void record(const Event& event);
+
+    // Oldest first
+    std::vector<Entry> entries() const;
+
+  private:
+    size_t capacity;
+    std::deque<Event> events;
+};
+
 struct TimerUsed
 {
     bool used;
@@ -136,12 +226,15 @@ struct ThresholdDefinition
     const char* levelName;
 };
 
-constexpr static std::array<thresholds::ThresholdDefinition, 5> thresProp = {
+// Rate-of-change thresholds are selected with "Type": "RateOfChange" in the
+// configuration rather than by severity.
+constexpr static std::array<thresholds::ThresholdDefinition, 6> thresProp = {
     {{Level::WARNING, 0, "Warning"},
      {Level::CRITICAL, 1, "Critical"},
      {Level::PERFORMANCELOSS, 2, "PerformanceLoss"},
      {Level::SOFTSHUTDOWN, 3, "SoftShutdown"},
-     {Level::HARDSHUTDOWN, 4, "HardShutdown"}}};
+     {Level::HARDSHUTDOWN, 4, "HardShutdown"},
+     {Level::RATEOFCHANGE, 5, "RateOfChange"}}};
 
 std::string getInterface(Level level);
 
@@ -150,6 +243,12 @@ void persistThreshold(const std::string& path, const std::string& baseInterface,
                       std::shared_ptr<sdbusplus::asio::connection>& conn,
                       size_t thresholdCount, const std::string& label);
 
+void persistHysteresis(const std::string& path,
+                       const std::string& baseInterface,
+                       const thresholds::Threshold& threshold,
+                       std::shared_ptr<sdbusplus::asio::connection>& conn,
+                       size_t thresholdCount, const std::string& label);
+
 void updateThresholds(Sensor* sensor);
 // returns false if a critical threshold has been crossed, true otherwise
 bool checkThresholds(Sensor* sensor);
@@ -66,6 +66,9 @@ static bool chassisStatusOn = false;
 static std::unique_ptr<sdbusplus::bus::match_t> powerMatch = nullptr;
 static std::unique_ptr<sdbusplus::bus::match_t> postMatch = nullptr;
 static std::unique_ptr<sdbusplus::bus::match_t> chassisMatch = nullptr;
+// Every reactor of the process that wants to hear about power state changes
+static std::vector<std::function<void(PowerState type, bool state)>>
+    hostStatusCallbacks;
 
 /**
  * return the contents of a file
@@ -448,9 +451,16 @@ static void
         chassis::interface, chassis::property);
 }
 
-void setupPowerMatchCallback(
-    const std::shared_ptr<sdbusplus::asio::connection>& conn,
-    std::function<void(PowerState type, bool state)>&& hostStatusCallback)
+static void hostStatusChanged(PowerState type, bool state)
+{
+    for (const auto& callback : hostStatusCallbacks)
+    {
+        callback(type, state);
+    }
+}
+
+static void setupPowerMatches(
+    const std::shared_ptr<sdbusplus::asio::connection>& conn)
 {
     static boost::asio::steady_timer timer(conn->get_io_context());
     static boost::asio::steady_timer timerChassisOn(conn->get_io_context());
@@ -466,7 +476,7 @@ void setupPowerMatchCallback(
         "type='signal',interface='" + std::string(properties::interface) +
             "',path='" + std::string(power::path) + "',arg0='" +
             std::string(power::interface) + "'",
-        [hostStatusCallback](sdbusplus::message_t& message) {
+        [](sdbusplus::message_t& message) {
             std::string objectName;
             boost::container::flat_map<std::string, std::variant<std::string>>
                 values;
@@ -480,25 +490,24 @@ void setupPowerMatchCallback(
                 {
                     timer.cancel();
                     powerStatusOn = false;
-                    hostStatusCallback(PowerState::on, powerStatusOn);
+                    hostStatusChanged(PowerState::on, powerStatusOn);
                     return;
                 }
                 // on comes too quickly
                 timer.expires_after(std::chrono::seconds(10));
-                timer.async_wait(
-                    [hostStatusCallback](boost::system::error_code ec) {
-                        if (ec == boost::asio::error::operation_aborted)
-                        {
-                            return;
-                        }
-                        if (ec)
-                        {
-                            std::cerr << "Timer error " << ec.message() << "\n";
-                            return;
-                        }
-                        powerStatusOn = true;
-                        hostStatusCallback(PowerState::on, powerStatusOn);
-                    });
+                timer.async_wait([](boost::system::error_code ec) {
+                    if (ec == boost::asio::error::operation_aborted)
+                    {
+                        return;
+                    }
+                    if (ec)
+                    {
+                        std::cerr << "Timer error " << ec.message() << "\n";
+                        return;
+                    }
+                    powerStatusOn = true;
+                    hostStatusChanged(PowerState::on, powerStatusOn);
+                });
             }
         });
 
@@ -507,7 +516,7 @@ void setupPowerMatchCallback(
         "type='signal',interface='" + std::string(properties::interface) +
             "',path='" + std::string(post::path) + "',arg0='" +
             std::string(post::interface) + "'",
-        [hostStatusCallback](sdbusplus::message_t& message) {
+        [](sdbusplus::message_t& message) {
             std::string objectName;
             boost::container::flat_map<std::string, std::variant<std::string>>
                 values;
@@ -520,7 +529,7 @@ void setupPowerMatchCallback(
                     (value != "Inactive") &&
                     (value != "xyz.openbmc_project.State.OperatingSystem."
                               "Status.OSStatus.Inactive");
-                hostStatusCallback(PowerState::biosPost, biosHasPost);
+                hostStatusChanged(PowerState::biosPost, biosHasPost);
             }
         });
 
@@ -529,8 +538,7 @@ void setupPowerMatchCallback(
         "type='signal',interface='" + std::string(properties::interface) +
             "',path='" + std::string(chassis::path) + "',arg0='" +
             std::string(chassis::interface) + "'",
-        [hostStatusCallback = std::move(hostStatusCallback)](
-            sdbusplus::message_t& message) {
+        [](sdbusplus::message_t& message) {
             std::string objectName;
             boost::container::flat_map<std::string, std::variant<std::string>>
                 values;
@@ -544,13 +552,12 @@ void setupPowerMatchCallback(
                 {
                     timerChassisOn.cancel();
                     chassisStatusOn = false;
-                    hostStatusCallback(PowerState::chassisOn, chassisStatusOn);
+                    hostStatusChanged(PowerState::chassisOn, chassisStatusOn);
                     return;
                 }
                 // on comes too quickly
                 timerChassisOn.expires_after(std::chrono::seconds(10));
-                timerChassisOn.async_wait([hostStatusCallback](
-                                              boost::system::error_code ec) {
+                timerChassisOn.async_wait([](boost::system::error_code ec) {
                     if (ec == boost::asio::error::operation_aborted)
                     {
                         return;
@@ -561,7 +568,7 @@ void setupPowerMatchCallback(
                         return;
                     }
                     chassisStatusOn = true;
-                    hostStatusCallback(PowerState::chassisOn, chassisStatusOn);
+                    hostStatusChanged(PowerState::chassisOn, chassisStatusOn);
                 });
             }
         });
@@ -570,9 +577,17 @@ void setupPowerMatchCallback(
     getChassisStatus(conn);
 }
 
+void setupPowerMatchCallback(
+    const std::shared_ptr<sdbusplus::asio::connection>& conn,
+    std::function<void(PowerState type, bool state)>&& hostStatusCallback)
+{
+    hostStatusCallbacks.emplace_back(std::move(hostStatusCallback));
+    setupPowerMatches(conn);
+}
+
 void setupPowerMatch(const std::shared_ptr<sdbusplus::asio::connection>& conn)
 {
-    setupPowerMatchCallback(conn, [](PowerState, bool) {});
+    setupPowerMatches(conn);
 }
 
 // replaces limits if MinReading and MaxReading are found.
@@ -17,6 +17,7 @@
 #include "ADCSensor.hpp"
 
 #include "SensorPaths.hpp"
+#include "Simulation.hpp"
 #include "Thresholds.hpp"
 #include "Utils.hpp"
 #include "sensor.hpp"
@@ -55,23 +56,28 @@ ADCSensor::ADCSensor(
     std::vector<thresholds::Threshold>&& thresholdsIn, const double scaleFactor,
     const float pollRate, PowerState readState,
     const std::string& sensorConfiguration,
-    std::optional<BridgeGpio>&& bridgeGpio) :
+    std::optional<BridgeGpio>&& bridgeGpio,
+    const std::shared_ptr<simulation::Source>& simulated) :
     Sensor(escapeName(sensorName), std::move(thresholdsIn), sensorConfiguration,
            "ADC", false, false, maxVoltageReading / scaleFactor,
            minVoltageReading / scaleFactor, conn, readState),
     objServer(objectServer), inputDev(io), waitTimer(io), path(path),
     scaleFactor(scaleFactor),
     sensorPollMs(static_cast<unsigned int>(pollRate * 1000)),
-    bridgeGpio(std::move(bridgeGpio)), thresholdTimer(io)
+    bridgeGpio(std::move(bridgeGpio)), thresholdTimer(io),
+    simulated(simulated)
 {
-    // NOLINTNEXTLINE(cppcoreguidelines-pro-type-vararg)
-    int fd = open(path.c_str(), O_RDONLY);
-    if (fd < 0)
+    if (!simulated)
     {
-        std::cerr << "unable to open acd device \n";
-    }
+        // NOLINTNEXTLINE(cppcoreguidelines-pro-type-vararg)
+        int fd = open(path.c_str(), O_RDONLY);
+        if (fd < 0)
+        {
+            std::cerr << "unable to open acd device \n";
+        }
 
-    inputDev.assign(fd);
+        inputDev.assign(fd);
+    }
 
     sensorInterface = objectServer.add_interface(
         "/xyz/openbmc_project/sensors/voltage/" + name,
@@ -104,6 +110,21 @@ ADCSensor::~ADCSensor()
 
 void ADCSensor::setupRead()
 {
+    if (simulated)
+    {
+        std::optional<double> reading = simulated->read();
+        if (reading)
+        {
+            updateValue(*reading);
+        }
+        else
+        {
+            incrementError();
+        }
+        restartRead();
+        return;
+    }
+
     std::shared_ptr<boost::asio::streambuf> buffer =
         std::make_shared<boost::asio::streambuf>();
 
@@ -160,8 +181,6 @@ void ADCSensor::setupRead()
 
 void ADCSensor::handleResponse(const boost::system::error_code& err)
 {
-    std::weak_ptr<ADCSensor> weakRef = weak_from_this();
-
     if (err == boost::system::errc::bad_file_descriptor)
     {
         return; // we're being destroyed
@@ -206,6 +225,12 @@ void ADCSensor::handleResponse(const boost::system::error_code& err)
         return; // we're no longer valid
     }
     inputDev.assign(fd);
+    restartRead();
+}
+
+void ADCSensor::restartRead()
+{
+    std::weak_ptr<ADCSensor> weakRef = weak_from_this();
     waitTimer.expires_after(std::chrono::milliseconds(sensorPollMs));
     waitTimer.async_wait([weakRef](const boost::system::error_code& ec) {
         std::shared_ptr<ADCSensor> self = weakRef.lock();
@@ -1,5 +1,6 @@
 #pragma once
 
+#include "Simulation.hpp"
 #include "Thresholds.hpp"
 #include "sensor.hpp"
 
@@ -73,7 +74,8 @@ class ADCSensor : public Sensor, public std::enable_shared_from_this<ADCSensor>
               std::vector<thresholds::Threshold>&& thresholds,
               double scaleFactor, float pollRate, PowerState readState,
               const std::string& sensorConfiguration,
-              std::optional<BridgeGpio>&& bridgeGpio);
+              std::optional<BridgeGpio>&& bridgeGpio,
+              const std::shared_ptr<simulation::Source>& simulated = nullptr);
     ~ADCSensor() override;
     void setupRead();
 
@@ -87,6 +89,9 @@ class ADCSensor : public Sensor, public std::enable_shared_from_this<ADCSensor>
     unsigned int sensorPollMs;
     std::optional<BridgeGpio> bridgeGpio;
     thresholds::ThresholdTimer thresholdTimer;
+    // Readings come from here instead of sysfs when simulating
+    std::shared_ptr<simulation::Source> simulated;
     void handleResponse(const boost::system::error_code& err);
+    void restartRead();
     void checkThresholds() override;
 };
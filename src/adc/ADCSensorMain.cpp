@@ -15,6 +15,8 @@
 */
 
 #include "ADCSensor.hpp"
+#include "Reactor.hpp"
+#include "Simulation.hpp"
 #include "Thresholds.hpp"
 #include "Utils.hpp"
 #include "VariantVisitors.hpp"
@@ -50,6 +52,9 @@
 #include <variant>
 #include <vector>
 
+namespace adcsensor
+{
+
 static constexpr bool debug = false;
 static constexpr float pollRateDefault = 0.5;
 static constexpr float gpioBridgeSetupTimeDefault = 0.02;
@@ -85,6 +90,35 @@ bool isAdc(const fs::path& parentPath)
     return name == "iio_hwmon";
 }
 
+// Stand-ins for the inN_input files of the configured channels, as there is no
+// sysfs to find them in when simulating
+static std::vector<fs::path>
+    simulatedPaths(const ManagedObjectType& sensorConfigurations)
+{
+    std::vector<fs::path> paths;
+    for (const auto& [path, cfgData] : sensorConfigurations)
+    {
+        for (const char* type : sensorTypes)
+        {
+            auto sensorBase = cfgData.find(configInterfaceName(type));
+            if (sensorBase == cfgData.end())
+            {
+                continue;
+            }
+            auto findIndex = sensorBase->second.find("Index");
+            if (findIndex == sensorBase->second.end())
+            {
+                continue;
+            }
+            unsigned int index =
+                std::visit(VariantToUnsignedIntVisitor(), findIndex->second);
+            paths.emplace_back(fs::path("/sys/class/hwmon/simulated") /
+                               ("in" + std::to_string(index + 1) + "_input"));
+        }
+    }
+    return paths;
+}
+
 void createSensors(
     boost::asio::io_context& io, sdbusplus::asio::object_server& objectServer,
     boost::container::flat_map<std::string, std::shared_ptr<ADCSensor>>&
@@ -99,9 +133,14 @@ void createSensors(
         [&io, &objectServer, &sensors, &dbusConnection, sensorsChanged,
          updateType](const ManagedObjectType& sensorConfigurations) {
             bool firstScan = sensorsChanged == nullptr;
+            bool simulating = simulation::enabled();
             std::vector<fs::path> paths;
-            if (!findFiles(fs::path("/sys/class/hwmon"), R"(in\d+_input)",
-                           paths))
+            if (simulating)
+            {
+                paths = simulatedPaths(sensorConfigurations);
+            }
+            else if (!findFiles(fs::path("/sys/class/hwmon"),
+                                R"(in\d+_input)", paths))
             {
                 std::cerr << "No adc sensors in system\n";
                 return;
@@ -111,7 +150,7 @@ void createSensors(
             // configuration
             for (auto& path : paths)
             {
-                if (!isAdc(path.parent_path()))
+                if (!simulating && !isAdc(path.parent_path()))
                 {
                     continue;
                 }
@@ -263,13 +302,26 @@ void createSensors(
                     getPollRate(baseConfiguration->second, pollRateDefault);
                 PowerState readState = getPowerState(baseConfiguration->second);
 
+                std::shared_ptr<simulation::Source> source;
+                if (simulating)
+                {
+                    // Rescans were filtered above
+                    source = simulation::sourceFor(sensorName, sensors,
+                                                   nullptr);
+                    if (!source)
+                    {
+                        continue;
+                    }
+                }
+
                 auto& sensor = sensors[sensorName];
                 sensor = nullptr;
 
+                // There is no bridge to switch on for a simulated reading
                 std::optional<BridgeGpio> bridgeGpio;
                 for (const auto& [key, cfgMap] : *sensorData)
                 {
-                    if (key.find("BridgeGpio") != std::string::npos)
+                    if (!source && key.find("BridgeGpio") != std::string::npos)
                     {
                         auto findName = cfgMap.find("Name");
                         if (findName != cfgMap.end())
@@ -308,7 +360,8 @@ void createSensors(
                 sensor = std::make_shared<ADCSensor>(
                     path.string(), objectServer, dbusConnection, io, sensorName,
                     std::move(sensorThresholds), scaleFactor, pollRate,
-                    readState, *interfacePath, std::move(bridgeGpio));
+                    readState, *interfacePath, std::move(bridgeGpio), source);
+                sensor->loadConfigurationRecords(*sensorData);
                 sensor->setupRead();
             }
         });
@@ -317,16 +370,16 @@ void createSensors(
         std::vector<std::string>{sensorTypes.begin(), sensorTypes.end()});
 }
 
-int main()
+static void start(boost::asio::io_context& io,
+                  std::shared_ptr<sdbusplus::asio::connection>& systemBus,
+                  sdbusplus::asio::object_server& objectServer)
 {
-    boost::asio::io_context io;
-    auto systemBus = std::make_shared<sdbusplus::asio::connection>(io);
-    sdbusplus::asio::object_server objectServer(systemBus, true);
-    objectServer.add_manager("/xyz/openbmc_project/sensors");
+    reactor::addManager(objectServer, "/xyz/openbmc_project/sensors");
 
     systemBus->request_name("xyz.openbmc_project.ADCSensor");
-    boost::container::flat_map<std::string, std::shared_ptr<ADCSensor>> sensors;
-    auto sensorsChanged =
+    static boost::container::flat_map<std::string, std::shared_ptr<ADCSensor>>
+        sensors;
+    static auto sensorsChanged =
         std::make_shared<boost::container::flat_set<std::string>>();
 
     boost::asio::post(io, [&]() {
@@ -334,8 +387,8 @@ int main()
                       UpdateType::init);
     });
 
-    boost::asio::steady_timer filterTimer(io);
-    std::function<void(sdbusplus::message_t&)> eventHandler =
+    static boost::asio::steady_timer filterTimer(io);
+    static std::function<void(sdbusplus::message_t&)> eventHandler =
         [&](sdbusplus::message_t& message) {
             if (message.is_method_error())
             {
@@ -362,8 +415,8 @@ int main()
             });
         };
 
-    boost::asio::steady_timer cpuFilterTimer(io);
-    std::function<void(sdbusplus::message_t&)> cpuPresenceHandler =
+    static boost::asio::steady_timer cpuFilterTimer(io);
+    static std::function<void(sdbusplus::message_t&)> cpuPresenceHandler =
         [&](sdbusplus::message_t& message) {
             std::string path = message.get_path();
             boost::to_lower(path);
@@ -413,7 +466,7 @@ int main()
             });
         };
 
-    std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
+    static std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
         setupPropertiesChangedMatches(*systemBus, sensorTypes, eventHandler);
     matches.emplace_back(std::make_unique<sdbusplus::bus::match_t>(
         static_cast<sdbusplus::bus_t&>(*systemBus),
@@ -423,5 +476,8 @@ int main()
         cpuPresenceHandler));
 
     setupManufacturingModeMatch(*systemBus);
-    io.run();
 }
+
+static const reactor::Registration registration("adc", start);
+
+} // namespace adcsensor
@@ -0,0 +1,315 @@
+#include "DerivedSensor.hpp"
+
+#include "Expression.hpp"
+#include "SensorPaths.hpp"
+#include "Thresholds.hpp"
+#include "Utils.hpp"
+#include "VariantVisitors.hpp"
+#include "sensor.hpp"
+
+#include <sdbusplus/asio/connection.hpp>
+#include <sdbusplus/asio/object_server.hpp>
+#include <sdbusplus/bus.hpp>
+#include <sdbusplus/bus/match.hpp>
+#include <sdbusplus/exception.hpp>
+#include <sdbusplus/message.hpp>
+#include <sdbusplus/message/native_types.hpp>
+
+#include <algorithm>
+#include <array>
+#include <cstddef>
+#include <cstdint>
+#include <iostream>
+#include <limits>
+#include <memory>
+#include <optional>
+#include <stdexcept>
+#include <string>
+#include <utility>
+#include <variant>
+#include <vector>
+
+static constexpr const char* sensorsRoot = "/xyz/openbmc_project/sensors";
+
+using ValueVariant = std::variant<double, int64_t>;
+
+DerivedSensor::DerivedSensor(
+    const std::string& objectType, sdbusplus::asio::object_server& objectServer,
+    std::shared_ptr<sdbusplus::asio::connection>& conn,
+    const std::string& sensorName, const std::string& sensorUnits,
+    std::vector<thresholds::Threshold>&& thresholdsIn,
+    const std::string& sensorConfiguration, double maxReading,
+    double minReading, const PowerState& powerState,
+    derived::Expression&& expression) :
+    Sensor(escapeName(sensorName), std::move(thresholdsIn), sensorConfiguration,
+           objectType, false, false, maxReading, minReading, conn, powerState),
+    objServer(objectServer), expression(std::move(expression)),
+    readings(this->expression.inputs.size(),
+             std::numeric_limits<double>::quiet_NaN())
+{
+    std::string dbusPath = sensor_paths::getPathForUnits(sensorUnits);
+    if (dbusPath.empty())
+    {
+        throw std::runtime_error("Units not in allow list");
+    }
+    objectPath = std::string(sensorsRoot) + "/" + dbusPath + "/" + name;
+
+    sensorInterface =
+        objectServer.add_interface(objectPath, sensorValueInterface);
+
+    for (const auto& threshold : thresholds)
+    {
+        std::string interface = thresholds::getInterface(threshold.level);
+        thresholdInterfaces[static_cast<size_t>(threshold.level)] =
+            objectServer.add_interface(objectPath, interface);
+    }
+
+    association =
+        objectServer.add_interface(objectPath, association::interface);
+    createAssociation(association, configurationPath);
+
+    std::string unit = sensorUnits;
+    if (!unit.starts_with("xyz."))
+    {
+        unit = "xyz.openbmc_project.Sensor.Value.Unit." + unit;
+    }
+    setInitialProperties(unit);
+}
+
+DerivedSensor::~DerivedSensor()
+{
+    objServer.remove_interface(association);
+    for (const auto& iface : thresholdInterfaces)
+    {
+        objServer.remove_interface(iface);
+    }
+    objServer.remove_interface(sensorInterface);
+}
+
+void DerivedSensor::checkThresholds()
+{
+    thresholds::checkThresholds(this);
+}
+
+void DerivedSensor::setupMatches()
+{
+    std::weak_ptr<DerivedSensor> weakRef = weak_from_this();
+
+    matches.emplace_back(
+        static_cast<sdbusplus::bus_t&>(*dbusConnection),
+        "type='signal',member='PropertiesChanged',interface='" +
+            std::string(properties::interface) + "',path_namespace='" +
+            sensorsRoot + "',arg0='" + sensorValueInterface + "'",
+        [weakRef](sdbusplus::message_t& message) {
+            auto self = weakRef.lock();
+            if (!self)
+            {
+                return;
+            }
+            if (!self->isInput(message.get_path()))
+            {
+                return;
+            }
+            std::string objectName;
+            SensorBaseConfigMap values;
+            try
+            {
+                message.read(objectName, values);
+            }
+            catch (const sdbusplus::exception_t& e)
+            {
+                std::cerr << "Error reading PropertiesChanged: " << e.what()
+                          << "\n";
+                return;
+            }
+            auto findValue = values.find("Value");
+            if (findValue == values.end())
+            {
+                return;
+            }
+            if (self->updateInput(
+                    message.get_path(),
+                    std::visit(VariantToDoubleVisitor(), findValue->second)))
+            {
+                self->updateReading();
+            }
+        });
+
+    // An input created after this sensor announces its first reading here
+    matches.emplace_back(
+        static_cast<sdbusplus::bus_t&>(*dbusConnection),
+        "type='signal',member='InterfacesAdded',arg0path='" +
+            std::string(sensorsRoot) + "/'",
+        [weakRef](sdbusplus::message_t& message) {
+            auto self = weakRef.lock();
+            if (!self)
+            {
+                return;
+            }
+            sdbusplus::message::object_path path;
+            SensorData interfaces;
+            try
+            {
+                message.read(path, interfaces);
+            }
+            catch (const sdbusplus::exception_t& e)
+            {
+                std::cerr << "Error reading InterfacesAdded: " << e.what()
+                          << "\n";
+                return;
+            }
+            if (!self->isInput(path.str))
+            {
+                return;
+            }
+            auto findInterface = interfaces.find(sensorValueInterface);
+            if (findInterface == interfaces.end())
+            {
+                return;
+            }
+            auto findValue = findInterface->second.find("Value");
+            if (findValue == findInterface->second.end())
+            {
+                return;
+            }
+            if (self->updateInput(
+                    path.str,
+                    std::visit(VariantToDoubleVisitor(), findValue->second)))
+            {
+                self->updateReading();
+            }
+        });
+
+    // An input that goes away no longer has a reading
+    matches.emplace_back(
+        static_cast<sdbusplus::bus_t&>(*dbusConnection),
+        "type='signal',member='InterfacesRemoved',arg0path='" +
+            std::string(sensorsRoot) + "/'",
+        [weakRef](sdbusplus::message_t& message) {
+            auto self = weakRef.lock();
+            if (!self)
+            {
+                return;
+            }
+            sdbusplus::message::object_path path;
+            std::vector<std::string> interfaces;
+            try
+            {
+                message.read(path, interfaces);
+            }
+            catch (const sdbusplus::exception_t& e)
+            {
+                std::cerr << "Error reading InterfacesRemoved: " << e.what()
+                          << "\n";
+                return;
+            }
+            if (std::ranges::find(interfaces, sensorValueInterface) ==
+                interfaces.end())
+            {
+                return;
+            }
+            if (self->updateInput(path.str,
+                                  std::numeric_limits<double>::quiet_NaN()))
+            {
+                self->updateReading();
+            }
+        });
+
+    getInitialReadings();
+}
+
+void DerivedSensor::getInitialReadings()
+{
+    std::weak_ptr<DerivedSensor> weakRef = weak_from_this();
+    dbusConnection->async_method_call(
+        [weakRef](boost::system::error_code ec, const GetSubTreeType& subtree) {
+            if (ec)
+            {
+                std::cerr << "Error contacting mapper\n";
+                return;
+            }
+            auto self = weakRef.lock();
+            if (!self)
+            {
+                return;
+            }
+            for (const auto& [path, objDict] : subtree)
+            {
+                if (objDict.empty() || !self->isInput(path))
+                {
+                    continue;
+                }
+                // lambda capture requires a proper variable (not a
+                // structured binding)
+                const std::string& cbPath = path;
+                self->dbusConnection->async_method_call(
+                    [weakRef, cbPath](boost::system::error_code ec,
+                                      const ValueVariant& value) {
+                        if (ec)
+                        {
+                            std::cerr << "Error getting value from " << cbPath
+                                      << "\n";
+                            return;
+                        }
+                        auto self = weakRef.lock();
+                        if (!self)
+                        {
+                            return;
+                        }
+                        double reading =
+                            std::visit(VariantToDoubleVisitor(), value);
+                        self->updateInput(cbPath, reading);
+                        self->updateReading();
+                    },
+                    objDict[0].first, cbPath, properties::interface,
+                    properties::get, sensorValueInterface, "Value");
+            }
+        },
+        mapper::busName, mapper::path, mapper::interface, mapper::subtree,
+        sensorsRoot, 0, std::array<const char*, 1>{sensorValueInterface});
+}
+
+// Inputs are given by name, or by "<type>/<name>" where the name alone is
+// ambiguous
+static bool pathMatches(const std::string& path, const derived::Input& input)
+{
+    return path.ends_with("/" + input.name);
+}
+
+bool DerivedSensor::isInput(const std::string& path) const
+{
+    return path != objectPath &&
+           std::ranges::any_of(expression.inputs,
+                               [&path](const derived::Input& input) {
+                                   return pathMatches(path, input);
+                               });
+}
+
+bool DerivedSensor::updateInput(const std::string& path, double value)
+{
+    if (!isInput(path))
+    {
+        return false;
+    }
+    for (size_t ii = 0; ii < expression.inputs.size(); ii++)
+    {
+        if (pathMatches(path, expression.inputs[ii]))
+        {
+            readings[ii] = value;
+        }
+    }
+    return true;
+}
+
+void DerivedSensor::updateReading()
+{
+    std::optional<double> reading = expression.evaluate(readings);
+    if (!reading)
+    {
+        // A NaN value alone would leave the sensor available
+        updateValue(std::numeric_limits<double>::quiet_NaN());
+        markAvailable(false);
+        return;
+    }
+    updateValue(*reading);
+}
@@ -0,0 +1,47 @@
+#pragma once
+
+#include "Expression.hpp"
+#include "Thresholds.hpp"
+#include "sensor.hpp"
+
+#include <sdbusplus/asio/connection.hpp>
+#include <sdbusplus/asio/object_server.hpp>
+#include <sdbusplus/bus/match.hpp>
+
+#include <memory>
+#include <string>
+#include <vector>
+
+class DerivedSensor :
+    public Sensor,
+    public std::enable_shared_from_this<DerivedSensor>
+{
+  public:
+    DerivedSensor(const std::string& objectType,
+                  sdbusplus::asio::object_server& objectServer,
+                  std::shared_ptr<sdbusplus::asio::connection>& conn,
+                  const std::string& sensorName, const std::string& sensorUnits,
+                  std::vector<thresholds::Threshold>&& thresholdsIn,
+                  const std::string& sensorConfiguration, double maxReading,
+                  double minReading, const PowerState& powerState,
+                  derived::Expression&& expression);
+    ~DerivedSensor() override;
+
+    // Call this immediately after calling the constructor
+    void setupMatches();
+
+  private:
+    sdbusplus::asio::object_server& objServer;
+    std::string objectPath;
+    derived::Expression expression;
+    // Latest reading of each input, NaN until one is seen
+    std::vector<double> readings;
+    std::vector<sdbusplus::bus::match_t> matches;
+
+    void checkThresholds() override;
+    void getInitialReadings();
+    bool isInput(const std::string& path) const;
+    // Returns false if the sensor at path is not an input
+    bool updateInput(const std::string& path, double value);
+    void updateReading();
+};
@@ -0,0 +1,226 @@
+#include "DerivedSensor.hpp"
+#include "Expression.hpp"
+#include "Reactor.hpp"
+#include "Thresholds.hpp"
+#include "Utils.hpp"
+#include "VariantVisitors.hpp"
+
+#include <boost/asio/error.hpp>
+#include <boost/asio/io_context.hpp>
+#include <boost/asio/post.hpp>
+#include <boost/asio/steady_timer.hpp>
+#include <boost/container/flat_map.hpp>
+#include <boost/container/flat_set.hpp>
+#include <sdbusplus/asio/connection.hpp>
+#include <sdbusplus/asio/object_server.hpp>
+#include <sdbusplus/bus/match.hpp>
+#include <sdbusplus/message.hpp>
+#include <sdbusplus/message/native_types.hpp>
+
+#include <array>
+#include <chrono>
+#include <cmath>
+#include <functional>
+#include <iostream>
+#include <memory>
+#include <optional>
+#include <stdexcept>
+#include <string>
+#include <utility>
+#include <variant>
+#include <vector>
+
+// DerivedSensor publishes a reading computed from the readings of other
+// sensors, such as the total input power of all PSUs or the difference
+// between inlet and outlet temperature, so that it can carry thresholds of its
+// own. See the "derived sensors" section of the README for the configuration.
+
+namespace derivedsensor
+{
+
+static constexpr const char* sensorType = "DerivedSensor";
+
+static void createSensors(
+    sdbusplus::asio::object_server& objectServer,
+    boost::container::flat_map<std::string, std::shared_ptr<DerivedSensor>>&
+        sensors,
+    std::shared_ptr<sdbusplus::asio::connection>& dbusConnection,
+    const std::shared_ptr<boost::container::flat_set<std::string>>&
+        sensorsChanged)
+{
+    auto getter = std::make_shared<GetSensorConfiguration>(
+        dbusConnection,
+        [&objectServer, &sensors, &dbusConnection,
+         sensorsChanged](const ManagedObjectType& sensorConfigurations) {
+            bool firstScan = (sensorsChanged == nullptr);
+
+            for (const auto& [path, sensorData] : sensorConfigurations)
+            {
+                const std::string& interfacePath = path.str;
+
+                auto sensorBase =
+                    sensorData.find(configInterfaceName(sensorType));
+                if (sensorBase == sensorData.end())
+                {
+                    continue;
+                }
+                const SensorBaseConfigMap& baseConfigMap = sensorBase->second;
+
+                auto nameFound = baseConfigMap.find("Name");
+                auto unitsFound = baseConfigMap.find("Units");
+                if (nameFound == baseConfigMap.end() ||
+                    unitsFound == baseConfigMap.end())
+                {
+                    std::cerr << "Name or Units not found for "
+                              << interfacePath << "\n";
+                    continue;
+                }
+                std::string sensorName =
+                    std::visit(VariantToStringVisitor(), nameFound->second);
+                std::string sensorUnits =
+                    std::visit(VariantToStringVisitor(), unitsFound->second);
+
+                // on rescans, only update sensors we were signaled by
+                auto findSensor = sensors.find(sensorName);
+                if (!firstScan && findSensor != sensors.end())
+                {
+                    bool found = false;
+                    for (auto it = sensorsChanged->begin();
+                         it != sensorsChanged->end(); it++)
+                    {
+                        if (it->ends_with(findSensor->second->name))
+                        {
+                            sensorsChanged->erase(it);
+                            findSensor->second = nullptr;
+                            found = true;
+                            break;
+                        }
+                    }
+                    if (!found)
+                    {
+                        continue;
+                    }
+                }
+
+                std::optional<derived::Expression> expression =
+                    derived::parseExpression(sensorData,
+                                             configInterfaceName(sensorType));
+                if (!expression)
+                {
+                    std::cerr << "Invalid expression for " << sensorName
+                              << "\n";
+                    continue;
+                }
+
+                // The range bounds the hysteresis of the thresholds, so it
+                // has to be given
+                auto minFound = baseConfigMap.find("MinValue");
+                auto maxFound = baseConfigMap.find("MaxValue");
+                if (minFound == baseConfigMap.end() ||
+                    maxFound == baseConfigMap.end())
+                {
+                    std::cerr << "MinValue or MaxValue not found for "
+                              << sensorName << "\n";
+                    continue;
+                }
+                std::pair<double, double> limits = {
+                    std::visit(VariantToDoubleVisitor(), minFound->second),
+                    std::visit(VariantToDoubleVisitor(), maxFound->second)};
+                if (!std::isfinite(limits.first) ||
+                    !std::isfinite(limits.second) ||
+                    limits.first >= limits.second)
+                {
+                    std::cerr << "Invalid MinValue or MaxValue for "
+                              << sensorName << "\n";
+                    continue;
+                }
+
+                std::vector<thresholds::Threshold> sensorThresholds;
+                if (!parseThresholdsFromConfig(sensorData, sensorThresholds))
+                {
+                    std::cerr << "error populating thresholds for "
+                              << sensorName << "\n";
+                }
+
+                PowerState readState = getPowerState(baseConfigMap);
+
+                auto& sensorEntry = sensors[sensorName];
+                sensorEntry = nullptr;
+
+                try
+                {
+                    sensorEntry = std::make_shared<DerivedSensor>(
+                        sensorType, objectServer, dbusConnection, sensorName,
+                        sensorUnits, std::move(sensorThresholds),
+                        interfacePath, limits.second, limits.first, readState,
+                        std::move(*expression));
+                }
+                catch (const std::runtime_error& e)
+                {
+                    std::cerr << "Unable to create " << sensorName << ": "
+                              << e.what() << "\n";
+                    sensors.erase(sensorName);
+                    continue;
+                }
+                sensorEntry->loadConfigurationRecords(sensorData);
+                sensorEntry->setupMatches();
+            }
+        });
+
+    getter->getConfiguration(std::vector<std::string>{sensorType});
+}
+
+static void start(boost::asio::io_context& io,
+                  std::shared_ptr<sdbusplus::asio::connection>& systemBus,
+                  sdbusplus::asio::object_server& objectServer)
+{
+    reactor::addManager(objectServer, "/xyz/openbmc_project/sensors");
+    systemBus->request_name("xyz.openbmc_project.DerivedSensor");
+
+    static boost::container::flat_map<std::string,
+                                      std::shared_ptr<DerivedSensor>>
+        sensors;
+    static auto sensorsChanged =
+        std::make_shared<boost::container::flat_set<std::string>>();
+
+    boost::asio::post(io, [&objectServer, &systemBus]() {
+        createSensors(objectServer, sensors, systemBus, nullptr);
+    });
+
+    static boost::asio::steady_timer filterTimer(io);
+    static std::function<void(sdbusplus::message_t&)> eventHandler =
+        [&objectServer, &systemBus](sdbusplus::message_t& message) {
+            if (message.is_method_error())
+            {
+                std::cerr << "callback method error\n";
+                return;
+            }
+            sensorsChanged->insert(message.get_path());
+            // this implicitly cancels the timer
+            filterTimer.expires_after(std::chrono::seconds(1));
+
+            filterTimer.async_wait([&objectServer, &systemBus](
+                                       const boost::system::error_code& ec) {
+                if (ec == boost::asio::error::operation_aborted)
+                {
+                    /* we were canceled*/
+                    return;
+                }
+                if (ec)
+                {
+                    std::cerr << "timer error\n";
+                    return;
+                }
+                createSensors(objectServer, sensors, systemBus,
+                              sensorsChanged);
+            });
+        };
+
+    static std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
+        setupPropertiesChangedMatches(
+            *systemBus, std::to_array<const char*>({sensorType}), eventHandler);
+}
+
+static const reactor::Registration registration("derived", start);
+
+} // namespace derivedsensor
@@ -0,0 +1,265 @@
+#include "Expression.hpp"
+
+#include "SensorPaths.hpp"
+#include "Utils.hpp"
+#include "VariantVisitors.hpp"
+
+#include <algorithm>
+#include <cmath>
+#include <cstddef>
+#include <exception>
+#include <iostream>
+#include <map>
+#include <numeric>
+#include <optional>
+#include <span>
+#include <stdexcept>
+#include <string>
+#include <utility>
+#include <variant>
+#include <vector>
+
+namespace derived
+{
+
+std::optional<double> Expression::evaluate(std::span<const double> values) const
+{
+    std::vector<double> terms;
+    terms.reserve(values.size());
+    for (size_t ii = 0; ii < values.size() && ii < inputs.size(); ii++)
+    {
+        double value = values[ii];
+        if (std::isnan(value))
+        {
+            if (nanHandling == NaNHandling::propagate)
+            {
+                return std::nullopt;
+            }
+            if (nanHandling == NaNHandling::ignore)
+            {
+                // A difference is meaningless without both of its sides
+                if (operation == Operation::difference)
+                {
+                    return std::nullopt;
+                }
+                continue;
+            }
+            value = 0.0;
+        }
+        if (operation == Operation::linearCombination)
+        {
+            value *= inputs[ii].coefficient;
+        }
+        terms.emplace_back(value);
+    }
+    if (terms.empty())
+    {
+        return std::nullopt;
+    }
+
+    double sum = std::accumulate(terms.begin(), terms.end(), 0.0);
+    switch (operation)
+    {
+        case Operation::sum:
+            return sum;
+        case Operation::minimum:
+            return std::ranges::min(terms);
+        case Operation::maximum:
+            return std::ranges::max(terms);
+        case Operation::average:
+            return sum / static_cast<double>(terms.size());
+        case Operation::difference:
+            if (terms.size() != 2)
+            {
+                return std::nullopt;
+            }
+            return terms[0] - terms[1];
+        case Operation::linearCombination:
+            return sum + offset;
+    }
+    return std::nullopt;
+}
+
+std::optional<Operation> parseOperation(const std::string& name)
+{
+    static const std::map<std::string, Operation> operations = {
+        {"Sum", Operation::sum},
+        {"Minimum", Operation::minimum},
+        {"Maximum", Operation::maximum},
+        {"Average", Operation::average},
+        {"Difference", Operation::difference},
+        {"LinearCombination", Operation::linearCombination}};
+
+    auto it = operations.find(name);
+    if (it == operations.end())
+    {
+        return std::nullopt;
+    }
+    return it->second;
+}
+
+std::optional<NaNHandling> parseNaNHandling(const std::string& name)
+{
+    if (name == "Propagate")
+    {
+        return NaNHandling::propagate;
+    }
+    if (name == "Ignore")
+    {
+        return NaNHandling::ignore;
+    }
+    if (name == "Zero")
+    {
+        return NaNHandling::zero;
+    }
+    return std::nullopt;
+}
+
+static bool parseInputRecords(const SensorData& sensorData,
+                              const std::string& configInterface,
+                              std::vector<Input>& inputs)
+{
+    const std::string recordPrefix = configInterface + ".Inputs";
+
+    // entity-manager numbers the records in the order they are listed, which
+    // matters for a difference
+    std::map<size_t, Input> records;
+    for (const auto& [interface, cfg] : sensorData)
+    {
+        if (!interface.starts_with(recordPrefix))
+        {
+            continue;
+        }
+
+        size_t index = 0;
+        try
+        {
+            index = std::stoul(interface.substr(recordPrefix.size()));
+        }
+        catch (const std::exception&)
+        {
+            std::cerr << "Invalid input record " << interface << "\n";
+            return false;
+        }
+
+        auto sensorFind = cfg.find("Sensor");
+        if (sensorFind == cfg.end())
+        {
+            std::cerr << "Input record " << interface
+                      << " is missing Sensor\n";
+            return false;
+        }
+        Input input;
+        input.name = sensor_paths::escapePathForDbus(
+            std::visit(VariantToStringVisitor(), sensorFind->second));
+
+        auto coefficientFind = cfg.find("Coefficient");
+        if (coefficientFind != cfg.end())
+        {
+            input.coefficient =
+                std::visit(VariantToDoubleVisitor(), coefficientFind->second);
+        }
+        records.emplace(index, std::move(input));
+    }
+
+    for (auto& record : records)
+    {
+        inputs.emplace_back(std::move(record.second));
+    }
+    return true;
+}
+
+std::optional<Expression> parseExpression(const SensorData& sensorData,
+                                          const std::string& configInterface)
+{
+    auto baseFind = sensorData.find(configInterface);
+    if (baseFind == sensorData.end())
+    {
+        return std::nullopt;
+    }
+    const SensorBaseConfigMap& cfg = baseFind->second;
+
+    Expression expression;
+    try
+    {
+        auto operationFind = cfg.find("Operation");
+        if (operationFind == cfg.end())
+        {
+            std::cerr << "Operation not found\n";
+            return std::nullopt;
+        }
+        std::string operationName =
+            std::visit(VariantToStringVisitor(), operationFind->second);
+        std::optional<Operation> operation = parseOperation(operationName);
+        if (!operation)
+        {
+            std::cerr << "Unknown operation " << operationName << "\n";
+            return std::nullopt;
+        }
+        expression.operation = *operation;
+
+        auto nanFind = cfg.find("NaNHandling");
+        if (nanFind != cfg.end())
+        {
+            std::string nanName =
+                std::visit(VariantToStringVisitor(), nanFind->second);
+            std::optional<NaNHandling> nanHandling = parseNaNHandling(nanName);
+            if (!nanHandling)
+            {
+                std::cerr << "Unknown NaNHandling " << nanName << "\n";
+                return std::nullopt;
+            }
+            expression.nanHandling = *nanHandling;
+        }
+
+        auto offsetFind = cfg.find("Offset");
+        if (offsetFind != cfg.end())
+        {
+            expression.offset =
+                std::visit(VariantToDoubleVisitor(), offsetFind->second);
+        }
+
+        auto sensorsFind = cfg.find("Sensors");
+        if (sensorsFind != cfg.end())
+        {
+            const auto* names =
+                std::get_if<std::vector<std::string>>(&sensorsFind->second);
+            if (names == nullptr)
+            {
+                std::cerr << "Sensors must be a list of sensor names\n";
+                return std::nullopt;
+            }
+            for (const std::string& name : *names)
+            {
+                expression.inputs.emplace_back(
+                    Input{sensor_paths::escapePathForDbus(name), 1.0});
+            }
+        }
+
+        if (!parseInputRecords(sensorData, configInterface, expression.inputs))
+        {
+            return std::nullopt;
+        }
+    }
+    catch (const std::invalid_argument& e)
+    {
+        std::cerr << "Invalid derived sensor configuration: " << e.what()
+                  << "\n";
+        return std::nullopt;
+    }
+
+    if (expression.inputs.empty())
+    {
+        std::cerr << "Derived sensor has no inputs\n";
+        return std::nullopt;
+    }
+    if (expression.operation == Operation::difference &&
+        expression.inputs.size() != 2)
+    {
+        std::cerr << "Difference needs exactly two inputs\n";
+        return std::nullopt;
+    }
+    return expression;
+}
+
+} // namespace derived
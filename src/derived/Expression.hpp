@@ -0,0 +1,62 @@
+#pragma once
+
+#include "Utils.hpp"
+
+#include <optional>
+#include <span>
+#include <string>
+#include <vector>
+
+namespace derived
+{
+
+enum class Operation
+{
+    sum,
+    minimum,
+    maximum,
+    average,
+    difference,
+    linearCombination
+};
+
+// What to do with an input that has no reading
+enum class NaNHandling
+{
+    // There is no result either
+    propagate,
+    // The input is left out of the calculation
+    ignore,
+    // The input counts as zero
+    zero
+};
+
+struct Input
+{
+    // Escaped name of the source sensor, the last element of its path
+    std::string name;
+    double coefficient = 1.0;
+};
+
+struct Expression
+{
+    Operation operation = Operation::sum;
+    NaNHandling nanHandling = NaNHandling::propagate;
+    std::vector<Input> inputs;
+    // Added to the result of a linear combination
+    double offset = 0.0;
+
+    // values holds the latest reading of each input, in the order of inputs.
+    // Without a result the derived sensor is unavailable.
+    std::optional<double> evaluate(std::span<const double> values) const;
+};
+
+std::optional<Operation> parseOperation(const std::string& name);
+std::optional<NaNHandling> parseNaNHandling(const std::string& name);
+
+// Reads the expression of a DerivedSensor record. The inputs are the names in
+// "Sensors" followed by the "Inputs" records, which may carry a coefficient.
+std::optional<Expression> parseExpression(const SensorData& sensorData,
+                                          const std::string& configInterface);
+
+} // namespace derived
@@ -16,6 +16,7 @@
 
 #include "ExitAirTempSensor.hpp"
 
+#include "Reactor.hpp"
 #include "SensorPaths.hpp"
 #include "Thresholds.hpp"
 #include "Utils.hpp"
@@ -898,6 +899,7 @@ void createSensor(sdbusplus::asio::object_server& objectServer,
                         exitAirSensor = std::make_shared<ExitAirTempSensor>(
                             dbusConnection, name, path.str, objectServer,
                             std::move(sensorThresholds));
+                        exitAirSensor->loadConfigurationRecords(interfaces);
                         exitAirSensor->powerFactorMin =
                             loadVariant<double>(cfg, "PowerFactorMin");
                         exitAirSensor->powerFactorMax =
@@ -919,6 +921,7 @@ void createSensor(sdbusplus::asio::object_server& objectServer,
                         auto sensor = std::make_shared<CFMSensor>(
                             dbusConnection, name, path.str, objectServer,
                             std::move(sensorThresholds), exitAirSensor);
+                        sensor->loadConfigurationRecords(interfaces);
                         loadVariantPathArray(cfg, "Tachs", sensor->tachs);
                         sensor->maxCFM = loadVariant<double>(cfg, "MaxCFM");
 
@@ -946,22 +949,24 @@ void createSensor(sdbusplus::asio::object_server& objectServer,
         std::vector<std::string>(monitorTypes.begin(), monitorTypes.end()));
 }
 
-int main()
+namespace exitairtempsensor
 {
-    boost::asio::io_context io;
-    auto systemBus = std::make_shared<sdbusplus::asio::connection>(io);
-    sdbusplus::asio::object_server objectServer(systemBus, true);
-    objectServer.add_manager("/xyz/openbmc_project/sensors");
+
+static void start(boost::asio::io_context& io,
+                  std::shared_ptr<sdbusplus::asio::connection>& systemBus,
+                  sdbusplus::asio::object_server& objectServer)
+{
+    reactor::addManager(objectServer, "/xyz/openbmc_project/sensors");
     systemBus->request_name("xyz.openbmc_project.ExitAirTempSensor");
-    std::shared_ptr<ExitAirTempSensor> sensor =
+    static std::shared_ptr<ExitAirTempSensor> sensor =
         nullptr; // wait until we find the config
 
     boost::asio::post(io,
                       [&]() { createSensor(objectServer, sensor, systemBus); });
 
-    boost::asio::steady_timer configTimer(io);
+    static boost::asio::steady_timer configTimer(io);
 
-    std::function<void(sdbusplus::message_t&)> eventHandler =
+    static std::function<void(sdbusplus::message_t&)> eventHandler =
         [&](sdbusplus::message_t&) {
             configTimer.expires_after(std::chrono::seconds(1));
             // create a timer because normally multiple properties change
@@ -977,10 +982,12 @@ int main()
                 }
             });
         };
-    std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
+    static std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
         setupPropertiesChangedMatches(*systemBus, monitorTypes, eventHandler);
 
     setupManufacturingModeMatch(*systemBus);
-    io.run();
-    return 0;
 }
+
+static const reactor::Registration registration("exit-air", start);
+
+} // namespace exitairtempsensor
@@ -1,4 +1,5 @@
 #include "ExternalSensor.hpp"
+#include "Reactor.hpp"
 #include "Thresholds.hpp"
 #include "Utils.hpp"
 #include "VariantVisitors.hpp"
@@ -55,6 +56,9 @@
 // https://gerrit.openbmc-project.xyz/c/openbmc/docs/+/41452
 // https://github.com/openbmc/docs/tree/master/designs/
 
+namespace externalsensor
+{
+
 static constexpr bool debug = false;
 
 static const char* sensorType = "ExternalSensor";
@@ -323,6 +327,7 @@ void createSensors(
                     sensorType, objectServer, dbusConnection, sensorName,
                     sensorUnits, std::move(sensorThresholds), interfacePath,
                     maxValue, minValue, timeoutSecs, readState);
+                sensorEntry->loadConfigurationRecords(sensorData);
                 sensorEntry->initWriteHook(
                     [&sensors, &reaperTimer](
                         const std::chrono::steady_clock::time_point& now) {
@@ -340,35 +345,32 @@ void createSensors(
     getter->getConfiguration(std::vector<std::string>{sensorType});
 }
 
-int main()
+static void start(boost::asio::io_context& io,
+                  std::shared_ptr<sdbusplus::asio::connection>& systemBus,
+                  sdbusplus::asio::object_server& objectServer)
 {
     if constexpr (debug)
     {
         std::cerr << "ExternalSensor service starting up\n";
     }
 
-    boost::asio::io_context io;
-    auto systemBus = std::make_shared<sdbusplus::asio::connection>(io);
-    sdbusplus::asio::object_server objectServer(systemBus, true);
-
-    objectServer.add_manager("/xyz/openbmc_project/sensors");
+    reactor::addManager(objectServer, "/xyz/openbmc_project/sensors");
     systemBus->request_name("xyz.openbmc_project.ExternalSensor");
 
-    boost::container::flat_map<std::string, std::shared_ptr<ExternalSensor>>
+    static boost::container::flat_map<std::string,
+                                      std::shared_ptr<ExternalSensor>>
         sensors;
-    auto sensorsChanged =
+    static auto sensorsChanged =
         std::make_shared<boost::container::flat_set<std::string>>();
-    boost::asio::steady_timer reaperTimer(io);
+    static boost::asio::steady_timer reaperTimer(io);
 
-    boost::asio::post(io, [&objectServer, &sensors, &systemBus,
-                           &reaperTimer]() {
+    boost::asio::post(io, [&objectServer, &systemBus]() {
         createSensors(objectServer, sensors, systemBus, nullptr, reaperTimer);
     });
 
-    boost::asio::steady_timer filterTimer(io);
-    std::function<void(sdbusplus::message_t&)> eventHandler =
-        [&objectServer, &sensors, &systemBus, &sensorsChanged, &filterTimer,
-         &reaperTimer](sdbusplus::message_t& message) mutable {
+    static boost::asio::steady_timer filterTimer(io);
+    static std::function<void(sdbusplus::message_t&)> eventHandler =
+        [&objectServer, &systemBus](sdbusplus::message_t& message) mutable {
             if (message.is_method_error())
             {
                 std::cerr << "callback method error\n";
@@ -387,8 +389,8 @@ int main()
             filterTimer.expires_after(std::chrono::seconds(1));
 
             filterTimer.async_wait(
-                [&objectServer, &sensors, &systemBus, &sensorsChanged,
-                 &reaperTimer](const boost::system::error_code& ec) mutable {
+                [&objectServer,
+                 &systemBus](const boost::system::error_code& ec) mutable {
                     if (ec != boost::system::errc::success)
                     {
                         if (ec != boost::asio::error::operation_aborted)
@@ -404,7 +406,7 @@ int main()
                 });
         };
 
-    std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
+    static std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
         setupPropertiesChangedMatches(
             *systemBus, std::to_array<const char*>({sensorType}), eventHandler);
 
@@ -412,6 +414,8 @@ int main()
     {
         std::cerr << "ExternalSensor service entering main loop\n";
     }
-
-    io.run();
 }
+
+static const reactor::Registration registration("external", start);
+
+} // namespace externalsensor
@@ -16,6 +16,8 @@
 
 #include "PresenceGpio.hpp"
 #include "PwmSensor.hpp"
+#include "Reactor.hpp"
+#include "Simulation.hpp"
 #include "TachSensor.hpp"
 #include "Thresholds.hpp"
 #include "Utils.hpp"
@@ -34,6 +36,7 @@
 #include <sdbusplus/bus/match.hpp>
 #include <sdbusplus/message.hpp>
 
+#include <algorithm>
 #include <array>
 #include <chrono>
 #include <cstddef>
@@ -53,6 +56,9 @@
 #include <variant>
 #include <vector>
 
+namespace fansensor
+{
+
 namespace fs = std::filesystem;
 
 // The following two structures need to be consistent
@@ -269,6 +275,81 @@ void createRedundancySensor(
         "org.freedesktop.DBus.ObjectManager", "GetManagedObjects");
 }
 
+// Without hardware there is no sysfs to match the configuration against, so
+// every configured fan is created and plays its simulation scenario.
+static void createSimulatedSensors(
+    boost::asio::io_context& io, sdbusplus::asio::object_server& objectServer,
+    boost::container::flat_map<std::string, std::shared_ptr<TachSensor>>&
+        tachSensors,
+    std::shared_ptr<sdbusplus::asio::connection>& dbusConnection,
+    const std::shared_ptr<boost::container::flat_set<std::string>>&
+        sensorsChanged,
+    const ManagedObjectType& sensorConfigurations)
+{
+    for (const auto& [path, cfgData] : sensorConfigurations)
+    {
+        for (size_t fanType = 0; fanType < sensorTypes.size(); fanType++)
+        {
+            auto baseConfiguration =
+                cfgData.find(configInterfaceName(sensorTypes[fanType]));
+            if (baseConfiguration == cfgData.end())
+            {
+                continue;
+            }
+            auto findSensorName = baseConfiguration->second.find("Name");
+            if (findSensorName == baseConfiguration->second.end())
+            {
+                std::cerr << "could not determine configuration name for "
+                          << path.str << "\n";
+                continue;
+            }
+            std::string sensorName =
+                std::get<std::string>(findSensorName->second);
+
+            std::shared_ptr<simulation::Source> source =
+                simulation::sourceFor(sensorName, tachSensors, sensorsChanged);
+            if (!source)
+            {
+                continue;
+            }
+
+            std::vector<thresholds::Threshold> sensorThresholds;
+            if (!parseThresholdsFromConfig(cfgData, sensorThresholds))
+            {
+                std::cerr << "error populating thresholds for " << sensorName
+                          << "\n";
+            }
+
+            std::optional<RedundancySensor>* redundancy = nullptr;
+            if (fanType == FanTypes::aspeed)
+            {
+                redundancy = &systemRedundancy;
+            }
+
+            PowerState powerState = getPowerState(baseConfiguration->second);
+
+            constexpr double defaultMaxReading = 25000;
+            constexpr double defaultMinReading = 0;
+            std::pair<double, double> limits =
+                std::make_pair(defaultMinReading, defaultMaxReading);
+            findLimits(limits, &(*baseConfiguration));
+
+            std::shared_ptr<PresenceGpio> presenceGpio(nullptr);
+            auto& tachSensor = tachSensors[sensorName];
+            tachSensor = nullptr;
+            tachSensor = std::make_shared<TachSensor>(
+                "simulated", sensorTypes[fanType], objectServer,
+                dbusConnection, presenceGpio, redundancy, io, sensorName,
+                std::move(sensorThresholds), path.str, limits, powerState,
+                std::nullopt, source);
+            tachSensor->loadConfigurationRecords(cfgData);
+            tachSensor->setupRead();
+        }
+    }
+
+    createRedundancySensor(tachSensors, dbusConnection, objectServer);
+}
+
 void createSensors(
     boost::asio::io_context& io, sdbusplus::asio::object_server& objectServer,
     boost::container::flat_map<std::string, std::shared_ptr<TachSensor>>&
@@ -290,6 +371,14 @@ void createSensors(
                                                  sensorsChanged](
                                                     const ManagedObjectType&
                                                         sensorConfigurations) {
+        if (simulation::enabled())
+        {
+            createSimulatedSensors(io, objectServer, tachSensors,
+                                   dbusConnection, sensorsChanged,
+                                   sensorConfigurations);
+            return;
+        }
+
         bool firstScan = sensorsChanged == nullptr;
         std::vector<fs::path> paths;
         if (!findFiles(fs::path("/sys/class/hwmon"), R"(fan\d+_input)", paths))
@@ -622,6 +711,7 @@ void createSensors(
                 presenceGpio, redundancy, io, sensorName,
                 std::move(sensorThresholds), *interfacePath, limits, powerState,
                 led);
+            tachSensor->loadConfigurationRecords(*sensorData);
             tachSensor->setupRead();
 
             if (!pwmPath.empty() && fs::exists(pwmPath) &&
@@ -640,23 +730,21 @@ void createSensors(
         retries);
 }
 
-int main()
+static void start(boost::asio::io_context& io,
+                  std::shared_ptr<sdbusplus::asio::connection>& systemBus,
+                  sdbusplus::asio::object_server& objectServer)
 {
-    boost::asio::io_context io;
-    auto systemBus = std::make_shared<sdbusplus::asio::connection>(io);
-    sdbusplus::asio::object_server objectServer(systemBus, true);
-
-    objectServer.add_manager("/xyz/openbmc_project/sensors");
-    objectServer.add_manager("/xyz/openbmc_project/control");
-    objectServer.add_manager("/xyz/openbmc_project/inventory");
+    reactor::addManager(objectServer, "/xyz/openbmc_project/sensors");
+    reactor::addManager(objectServer, "/xyz/openbmc_project/control");
+    reactor::addManager(objectServer, "/xyz/openbmc_project/inventory");
     systemBus->request_name("xyz.openbmc_project.FanSensor");
-    boost::container::flat_map<std::string, std::shared_ptr<TachSensor>>
+    static boost::container::flat_map<std::string, std::shared_ptr<TachSensor>>
         tachSensors;
-    boost::container::flat_map<std::string, std::unique_ptr<PwmSensor>>
+    static boost::container::flat_map<std::string, std::unique_ptr<PwmSensor>>
         pwmSensors;
-    boost::container::flat_map<std::string, std::weak_ptr<PresenceGpio>>
+    static boost::container::flat_map<std::string, std::weak_ptr<PresenceGpio>>
         presenceGpios;
-    auto sensorsChanged =
+    static auto sensorsChanged =
         std::make_shared<boost::container::flat_set<std::string>>();
 
     boost::asio::post(io, [&]() {
@@ -664,8 +752,8 @@ int main()
                       systemBus, nullptr);
     });
 
-    boost::asio::steady_timer filterTimer(io);
-    std::function<void(sdbusplus::message_t&)> eventHandler =
+    static boost::asio::steady_timer filterTimer(io);
+    static std::function<void(sdbusplus::message_t&)> eventHandler =
         [&](sdbusplus::message_t& message) {
             if (message.is_method_error())
             {
@@ -692,12 +780,12 @@ int main()
             });
         };
 
-    std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
+    static std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
         setupPropertiesChangedMatches(*systemBus, sensorTypes, eventHandler);
 
     // redundancy sensor
     std::function<void(sdbusplus::message_t&)> redundancyHandler =
-        [&tachSensors, &systemBus, &objectServer](sdbusplus::message_t&) {
+        [&systemBus, &objectServer](sdbusplus::message_t&) {
             createRedundancySensor(tachSensors, systemBus, objectServer);
         };
     auto match = std::make_unique<sdbusplus::bus::match_t>(
@@ -709,6 +797,8 @@ int main()
     matches.emplace_back(std::move(match));
 
     setupManufacturingModeMatch(*systemBus);
-    io.run();
-    return 0;
 }
+
+static const reactor::Registration registration("fan", start);
+
+} // namespace fansensor
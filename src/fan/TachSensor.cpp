@@ -18,6 +18,7 @@
 
 #include "PresenceGpio.hpp"
 #include "SensorPaths.hpp"
+#include "Simulation.hpp"
 #include "Thresholds.hpp"
 #include "Utils.hpp"
 #include "sensor.hpp"
@@ -53,14 +54,19 @@ TachSensor::TachSensor(
     std::vector<thresholds::Threshold>&& thresholdsIn,
     const std::string& sensorConfiguration,
     const std::pair<double, double>& limits, const PowerState& powerState,
-    const std::optional<std::string>& ledIn) :
+    const std::optional<std::string>& ledIn,
+    const std::shared_ptr<simulation::Source>& simulated) :
     Sensor(escapeName(fanName), std::move(thresholdsIn), sensorConfiguration,
            objectType, false, false, limits.second, limits.first, conn,
            powerState),
     objServer(objectServer), redundancy(redundancy), presence(presenceGpio),
-    inputDev(io, path, boost::asio::random_access_file::read_only),
-    waitTimer(io), path(path), led(ledIn)
+    inputDev(io), waitTimer(io), path(path), led(ledIn), simulated(simulated)
 {
+    if (!simulated)
+    {
+        inputDev.open(path, boost::asio::random_access_file::read_only);
+    }
+
     sensorInterface = objectServer.add_interface(
         "/xyz/openbmc_project/sensors/fan_tach/" + name,
         "xyz.openbmc_project.Sensor.Value");
@@ -115,6 +121,22 @@ TachSensor::~TachSensor()
 
 void TachSensor::setupRead()
 {
+    if (simulated)
+    {
+        std::optional<double> reading = simulated->read();
+        if (reading)
+        {
+            updateValue(*reading);
+            restartRead(pwmPollMs);
+        }
+        else
+        {
+            incrementError();
+            restartRead(sensorFailedPollTimeMs);
+        }
+        return;
+    }
+
     std::weak_ptr<TachSensor> weakRef = weak_from_this();
     inputDev.async_read_some_at(
         0, boost::asio::buffer(readBuf),
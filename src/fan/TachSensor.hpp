@@ -1,6 +1,7 @@
 #pragma once
 
 #include "PresenceGpio.hpp"
+#include "Simulation.hpp"
 #include "Thresholds.hpp"
 #include "sensor.hpp"
 
@@ -70,7 +71,8 @@ class TachSensor :
                const std::string& sensorConfiguration,
                const std::pair<double, double>& limits,
                const PowerState& powerState,
-               const std::optional<std::string>& led);
+               const std::optional<std::string>& led,
+               const std::shared_ptr<simulation::Source>& simulated = nullptr);
     ~TachSensor() override;
     void setupRead();
 
@@ -88,6 +90,8 @@ class TachSensor :
     std::string path;
     std::optional<std::string> led;
     bool ledState = false;
+    // Readings come from here instead of sysfs when simulating
+    std::shared_ptr<simulation::Source> simulated;
 
     void handleResponse(const boost::system::error_code& err, size_t bytesRead);
     void restartRead(size_t pollTime);
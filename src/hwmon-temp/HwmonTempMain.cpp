@@ -16,7 +16,9 @@
 
 #include "DeviceMgmt.hpp"
 #include "HwmonTempSensor.hpp"
+#include "Reactor.hpp"
 #include "SensorPaths.hpp"
+#include "Simulation.hpp"
 #include "Thresholds.hpp"
 #include "Utils.hpp"
 
@@ -51,6 +53,9 @@
 #include <variant>
 #include <vector>
 
+namespace hwmontempsensor
+{
+
 static constexpr float pollRateDefault = 0.5;
 
 static constexpr double maxValuePressure = 120000;      // Pascals
@@ -264,6 +269,58 @@ static SensorConfigMap
     return configMap;
 }
 
+// Without hardware there is no sysfs to match the configuration against, so
+// every configured sensor is created and plays its simulation scenario.
+static void createSimulatedSensors(
+    boost::asio::io_context& io, sdbusplus::asio::object_server& objectServer,
+    boost::container::flat_map<std::string, std::shared_ptr<HwmonTempSensor>>&
+        sensors,
+    std::shared_ptr<sdbusplus::asio::connection>& dbusConnection,
+    const std::shared_ptr<boost::container::flat_set<std::string>>&
+        sensorsChanged,
+    const SensorConfigMap& configMap)
+{
+    for (const auto& [key, sensorConfig] : configMap)
+    {
+        std::string sensorType = sensorConfig.interface.substr(
+            sensorConfig.interface.find_last_of('.') + 1);
+        float pollRate = getPollRate(sensorConfig.config, pollRateDefault);
+        PowerState readState = getPowerState(sensorConfig.config);
+
+        // "Name" stands for temp1_input, "Name1" for temp2_input and so on
+        for (size_t ii = 0; ii < sensorConfig.name.size(); ii++)
+        {
+            const std::string& sensorName = sensorConfig.name[ii];
+
+            std::shared_ptr<simulation::Source> source =
+                simulation::sourceFor(sensorName, sensors, sensorsChanged);
+            if (!source)
+            {
+                continue;
+            }
+
+            int index = static_cast<int>(ii) + 1;
+            std::vector<thresholds::Threshold> sensorThresholds;
+            if (!parseThresholdsFromConfig(sensorConfig.sensorData,
+                                           sensorThresholds, nullptr, &index))
+            {
+                std::cerr << "error populating thresholds for " << sensorName
+                          << " index " << index << "\n";
+            }
+
+            auto& sensor = sensors[sensorName];
+            sensor = nullptr;
+            sensor = std::make_shared<HwmonTempSensor>(
+                "simulated", sensorType, objectServer, dbusConnection, io,
+                sensorName, std::move(sensorThresholds),
+                getSensorParameters(fs::path()), pollRate,
+                sensorConfig.sensorPath, readState, nullptr, source);
+            sensor->loadConfigurationRecords(sensorConfig.sensorData);
+            sensor->setupRead();
+        }
+    }
+}
+
 void createSensors(
     boost::asio::io_context& io, sdbusplus::asio::object_server& objectServer,
     boost::container::flat_map<std::string, std::shared_ptr<HwmonTempSensor>>&
@@ -282,6 +339,14 @@ void createSensors(
             SensorConfigMap configMap =
                 buildSensorConfigMap(sensorConfigurations);
 
+            if (simulation::enabled())
+            {
+                createSimulatedSensors(io, objectServer, sensors,
+                                       dbusConnection, sensorsChanged,
+                                       configMap);
+                return;
+            }
+
             auto devices =
                 instantiateDevices(sensorConfigurations, sensors, sensorTypes);
 
@@ -455,6 +520,7 @@ void createSensors(
                             dbusConnection, io, sensorName,
                             std::move(sensorThresholds), thisSensorParameters,
                             pollRate, interfacePath, readState, i2cDev);
+                        sensor->loadConfigurationRecords(sensorData);
                         sensor->setupRead();
                     }
                 }
@@ -517,6 +583,7 @@ void createSensors(
                                 dbusConnection, io, sensorName,
                                 std::move(thresholds), thisSensorParameters,
                                 pollRate, interfacePath, readState, i2cDev);
+                            sensor->loadConfigurationRecords(sensorData);
                             sensor->setupRead();
                         }
                     }
@@ -580,7 +647,18 @@ static void powerStateChanged(
     boost::asio::io_context& io, sdbusplus::asio::object_server& objectServer,
     std::shared_ptr<sdbusplus::asio::connection>& dbusConnection)
 {
-    if (newState)
+    if (newState && simulation::enabled())
+    {
+        for (auto& [path, sensor] : sensors)
+        {
+            if (sensor != nullptr && sensor->readState == type &&
+                !sensor->isActive())
+            {
+                sensor->activate("simulated", nullptr);
+            }
+        }
+    }
+    else if (newState)
     {
         createSensors(io, objectServer, sensors, dbusConnection, nullptr, true);
     }
@@ -596,20 +674,20 @@ static void powerStateChanged(
     }
 }
 
-int main()
+static void start(boost::asio::io_context& io,
+                  std::shared_ptr<sdbusplus::asio::connection>& systemBus,
+                  sdbusplus::asio::object_server& objectServer)
 {
-    boost::asio::io_context io;
-    auto systemBus = std::make_shared<sdbusplus::asio::connection>(io);
-    sdbusplus::asio::object_server objectServer(systemBus, true);
-    objectServer.add_manager("/xyz/openbmc_project/sensors");
+    reactor::addManager(objectServer, "/xyz/openbmc_project/sensors");
     systemBus->request_name("xyz.openbmc_project.HwmonTempSensor");
 
-    boost::container::flat_map<std::string, std::shared_ptr<HwmonTempSensor>>
+    static boost::container::flat_map<std::string,
+                                      std::shared_ptr<HwmonTempSensor>>
         sensors;
-    auto sensorsChanged =
+    static auto sensorsChanged =
         std::make_shared<boost::container::flat_set<std::string>>();
 
-    auto powerCallBack = [&sensors, &io, &objectServer,
+    auto powerCallBack = [&io, &objectServer,
                           &systemBus](PowerState type, bool state) {
         powerStateChanged(type, state, sensors, io, objectServer, systemBus);
     };
@@ -619,8 +697,8 @@ int main()
         createSensors(io, objectServer, sensors, systemBus, nullptr, false);
     });
 
-    boost::asio::steady_timer filterTimer(io);
-    std::function<void(sdbusplus::message_t&)> eventHandler =
+    static boost::asio::steady_timer filterTimer(io);
+    static std::function<void(sdbusplus::message_t&)> eventHandler =
         [&](sdbusplus::message_t& message) {
             if (message.is_method_error())
             {
@@ -647,7 +725,7 @@ int main()
             });
         };
 
-    std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
+    static std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
         setupPropertiesChangedMatches(*systemBus, sensorTypes, eventHandler);
     setupManufacturingModeMatch(*systemBus);
 
@@ -657,11 +735,11 @@ int main()
         static_cast<sdbusplus::bus_t&>(*systemBus),
         "type='signal',member='InterfacesRemoved',arg0path='" +
             std::string(inventoryPath) + "/'",
-        [&sensors](sdbusplus::message_t& msg) {
-            interfaceRemoved(msg, sensors);
-        });
+        [](sdbusplus::message_t& msg) { interfaceRemoved(msg, sensors); });
 
     matches.emplace_back(std::move(ifaceRemovedMatch));
-
-    io.run();
 }
+
+static const reactor::Registration registration("hwmon-temp", start);
+
+} // namespace hwmontempsensor
@@ -17,6 +17,7 @@
 #include "HwmonTempSensor.hpp"
 
 #include "DeviceMgmt.hpp"
+#include "Simulation.hpp"
 #include "Thresholds.hpp"
 #include "Utils.hpp"
 #include "sensor.hpp"
@@ -35,6 +36,7 @@
 #include <iostream>
 #include <limits>
 #include <memory>
+#include <optional>
 #include <string>
 #include <system_error>
 #include <utility>
@@ -57,17 +59,24 @@ HwmonTempSensor::HwmonTempSensor(
     std::vector<thresholds::Threshold>&& thresholdsIn,
     const struct SensorParams& thisSensorParameters, const float pollRate,
     const std::string& sensorConfiguration, const PowerState powerState,
-    const std::shared_ptr<I2CDevice>& i2cDevice) :
+    const std::shared_ptr<I2CDevice>& i2cDevice,
+    const std::shared_ptr<simulation::Source>& simulated) :
     Sensor(boost::replace_all_copy(sensorName, " ", "_"),
            std::move(thresholdsIn), sensorConfiguration, objectType, false,
            false, thisSensorParameters.maxValue, thisSensorParameters.minValue,
            conn, powerState),
     i2cDevice(i2cDevice), objServer(objectServer),
-    inputDev(io, path, boost::asio::random_access_file::read_only),
-    waitTimer(io), path(path), offsetValue(thisSensorParameters.offsetValue),
+    inputDev(io), waitTimer(io), path(path),
+    offsetValue(thisSensorParameters.offsetValue),
     scaleValue(thisSensorParameters.scaleValue),
-    sensorPollMs(static_cast<unsigned int>(pollRate * 1000))
+    sensorPollMs(static_cast<unsigned int>(pollRate * 1000)),
+    simulated(simulated)
 {
+    if (!simulated)
+    {
+        inputDev.open(path, boost::asio::random_access_file::read_only);
+    }
+
     sensorInterface = objectServer.add_interface(
         "/xyz/openbmc_project/sensors/" + thisSensorParameters.typeName + "/" +
             name,
@@ -91,6 +100,10 @@ HwmonTempSensor::HwmonTempSensor(
 
 bool HwmonTempSensor::isActive()
 {
+    if (simulated)
+    {
+        return !path.empty();
+    }
     return inputDev.is_open();
 }
 
@@ -99,7 +112,10 @@ void HwmonTempSensor::activate(const std::string& newPath,
 {
     path = newPath;
     i2cDevice = newI2CDevice;
-    inputDev.open(path, boost::asio::random_access_file::read_only);
+    if (!simulated)
+    {
+        inputDev.open(path, boost::asio::random_access_file::read_only);
+    }
     markAvailable(true);
     setupRead();
 }
@@ -136,6 +152,21 @@ void HwmonTempSensor::setupRead()
         return;
     }
 
+    if (simulated)
+    {
+        std::optional<double> reading = simulated->read();
+        if (reading)
+        {
+            updateValue(*reading);
+        }
+        else
+        {
+            incrementError();
+        }
+        restartRead();
+        return;
+    }
+
     std::weak_ptr<HwmonTempSensor> weakRef = weak_from_this();
     inputDev.async_read_some_at(
         0, boost::asio::buffer(readBuf),
@@ -1,12 +1,14 @@
 #pragma once
 
 #include "DeviceMgmt.hpp"
+#include "Simulation.hpp"
 #include "Thresholds.hpp"
 #include "sensor.hpp"
 
 #include <boost/asio/random_access_file.hpp>
 #include <sdbusplus/asio/object_server.hpp>
 
+#include <memory>
 #include <string>
 #include <vector>
 
@@ -33,7 +35,9 @@ class HwmonTempSensor :
                     const struct SensorParams& thisSensorParameters,
                     float pollRate, const std::string& sensorConfiguration,
                     PowerState powerState,
-                    const std::shared_ptr<I2CDevice>& i2cDevice);
+                    const std::shared_ptr<I2CDevice>& i2cDevice,
+                    const std::shared_ptr<simulation::Source>& simulated =
+                        nullptr);
     ~HwmonTempSensor() override;
     void setupRead();
     void activate(const std::string& newPath,
@@ -58,6 +62,8 @@ class HwmonTempSensor :
     double offsetValue;
     double scaleValue;
     unsigned int sensorPollMs;
+    // Readings come from here instead of sysfs when simulating
+    std::shared_ptr<simulation::Source> simulated;
 
     void handleResponse(const boost::system::error_code& err, size_t bytesRead);
     void restartRead();
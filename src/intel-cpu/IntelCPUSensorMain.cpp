@@ -15,6 +15,7 @@
 */
 
 #include "IntelCPUSensor.hpp"
+#include "Reactor.hpp"
 #include "Thresholds.hpp"
 #include "Utils.hpp"
 #include "VariantVisitors.hpp"
@@ -67,6 +68,10 @@ static constexpr bool debug = false;
 
 boost::container::flat_map<std::string, std::shared_ptr<IntelCPUSensor>>
     gCpuSensors;
+
+namespace intelcpusensor
+{
+
 boost::container::flat_map<std::string,
                            std::shared_ptr<sdbusplus::asio::dbus_interface>>
     inventoryIfaces;
@@ -398,6 +403,7 @@ bool createSensors(boost::asio::io_context& io,
                 inputPathStr, sensorType, objectServer, dbusConnection, io,
                 sensorName, std::move(sensorThresholds), *interfacePath, cpuId,
                 show, dtsOffset);
+            sensorPtr->loadConfigurationRecords(*sensorData);
             sensorPtr->setupRead();
             createdSensors.insert(sensorName);
             if (debug)
@@ -798,18 +804,17 @@ bool getCpuConfig(const std::shared_ptr<sdbusplus::asio::connection>& systemBus,
     return false;
 }
 
-int main()
+static void start(boost::asio::io_context& io,
+                  std::shared_ptr<sdbusplus::asio::connection>& systemBus,
+                  sdbusplus::asio::object_server& objectServer)
 {
-    boost::asio::io_context io;
-    auto systemBus = std::make_shared<sdbusplus::asio::connection>(io);
-    boost::container::flat_set<CPUConfig> cpuConfigs;
+    static boost::container::flat_set<CPUConfig> cpuConfigs;
 
-    sdbusplus::asio::object_server objectServer(systemBus, true);
-    objectServer.add_manager("/xyz/openbmc_project/sensors");
-    boost::asio::steady_timer pingTimer(io);
-    boost::asio::steady_timer creationTimer(io);
-    boost::asio::steady_timer filterTimer(io);
-    ManagedObjectType sensorConfigs;
+    reactor::addManager(objectServer, "/xyz/openbmc_project/sensors");
+    static boost::asio::steady_timer pingTimer(io);
+    static boost::asio::steady_timer creationTimer(io);
+    static boost::asio::steady_timer filterTimer(io);
+    static ManagedObjectType sensorConfigs;
 
     filterTimer.expires_after(std::chrono::seconds(1));
     filterTimer.async_wait([&](const boost::system::error_code& ec) {
@@ -825,7 +830,7 @@ int main()
         }
     });
 
-    std::function<void(sdbusplus::message_t&)> eventHandler =
+    static std::function<void(sdbusplus::message_t&)> eventHandler =
         [&](sdbusplus::message_t& message) {
             if (message.is_method_error())
             {
@@ -855,12 +860,14 @@ int main()
             });
         };
 
-    std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
+    static std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
         setupPropertiesChangedMatches(*systemBus, sensorTypes, eventHandler);
 
     systemBus->request_name("xyz.openbmc_project.IntelCPUSensor");
 
     setupManufacturingModeMatch(*systemBus);
-    io.run();
-    return 0;
 }
+
+static const reactor::Registration registration("intel-cpu", start);
+
+} // namespace intelcpusensor
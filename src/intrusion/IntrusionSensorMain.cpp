@@ -15,6 +15,7 @@
 */
 
 #include "ChassisIntrusionSensor.hpp"
+#include "Reactor.hpp"
 #include "Utils.hpp"
 
 #include <boost/asio/error.hpp>
@@ -46,6 +47,9 @@
 #include <variant>
 #include <vector>
 
+namespace intrusionsensor
+{
+
 static constexpr bool debug = false;
 
 static constexpr const char* sensorType = "ChassisIntrusionSensor";
@@ -472,26 +476,22 @@ static bool initializeLanStatus(
     return true;
 }
 
-int main()
+static void start(boost::asio::io_context& io,
+                  std::shared_ptr<sdbusplus::asio::connection>& systemBus,
+                  sdbusplus::asio::object_server& objServer)
 {
-    std::shared_ptr<ChassisIntrusionSensor> intrusionSensor;
-
-    // setup connection to dbus
-    boost::asio::io_context io;
-    auto systemBus = std::make_shared<sdbusplus::asio::connection>(io);
+    static std::shared_ptr<ChassisIntrusionSensor> intrusionSensor;
 
-    // setup object server, define interface
+    // define interface
     systemBus->request_name("xyz.openbmc_project.IntrusionSensor");
 
-    sdbusplus::asio::object_server objServer(systemBus, true);
-
-    objServer.add_manager("/xyz/openbmc_project/Chassis");
+    reactor::addManager(objServer, "/xyz/openbmc_project/Chassis");
 
     createSensorsFromConfig(io, objServer, systemBus, intrusionSensor);
 
     // callback to handle configuration change
-    boost::asio::steady_timer filterTimer(io);
-    std::function<void(sdbusplus::message_t&)> eventHandler =
+    static boost::asio::steady_timer filterTimer(io);
+    static std::function<void(sdbusplus::message_t&)> eventHandler =
         [&](sdbusplus::message_t& message) {
             if (message.is_method_error())
             {
@@ -512,7 +512,7 @@ int main()
             });
         };
 
-    std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
+    static std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
         setupPropertiesChangedMatches(
             *systemBus, std::to_array<const char*>({sensorType}), eventHandler);
 
@@ -541,8 +541,8 @@ int main()
                 getNicNameInfo(systemBus);
             });
     }
+}
 
-    io.run();
+static const reactor::Registration registration("intrusion", start);
 
-    return 0;
-}
+} // namespace intrusionsensor
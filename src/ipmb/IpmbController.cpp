@@ -0,0 +1,252 @@
+#include "IpmbController.hpp"
+
+#include "IpmbSDRSensor.hpp"
+#include "Utils.hpp"
+#include "VariantVisitors.hpp"
+#include "sensor.hpp"
+
+#include <boost/asio/steady_timer.hpp>
+#include <boost/container/flat_map.hpp>
+#include <boost/system/error_code.hpp>
+#include <sdbusplus/asio/connection.hpp>
+#include <sdbusplus/asio/object_server.hpp>
+
+#include <algorithm>
+#include <chrono>
+#include <cstddef>
+#include <cstdint>
+#include <functional>
+#include <iostream>
+#include <memory>
+#include <string>
+#include <tuple>
+#include <utility>
+#include <variant>
+#include <vector>
+
+static constexpr const char* controllerPathPrefix =
+    "/xyz/openbmc_project/ipmb/controller/";
+
+// Gives a busy controller a moment before the request is sent again
+static constexpr std::chrono::milliseconds retryDelay{100};
+
+IpmbRequestTiming requestTimingFromConfig(const SensorBaseConfigMap& cfg)
+{
+    IpmbRequestTiming timing;
+    auto findTimeout = cfg.find("RequestTimeout");
+    if (findTimeout != cfg.end())
+    {
+        double timeout =
+            std::visit(VariantToDoubleVisitor(), findTimeout->second);
+        if (timeout > 0)
+        {
+            timing.timeout = std::chrono::milliseconds(
+                static_cast<int64_t>(timeout * 1000));
+        }
+        else
+        {
+            std::cerr << "Ignoring RequestTimeout " << timeout << "\n";
+        }
+    }
+    auto findRetries = cfg.find("RequestRetries");
+    if (findRetries != cfg.end())
+    {
+        timing.retries =
+            std::visit(VariantToUnsignedIntVisitor(), findRetries->second);
+    }
+    return timing;
+}
+
+bool IpmbControllerHealth::requestAllowed(Clock::time_point now)
+{
+    if (reachable)
+    {
+        return true;
+    }
+    if (now < nextProbe)
+    {
+        return false;
+    }
+    // Only this request probes the controller until the backoff expires again
+    nextProbe = now + backoff;
+    return true;
+}
+
+bool IpmbControllerHealth::requestAnswered()
+{
+    failures = 0;
+    if (reachable)
+    {
+        return false;
+    }
+    reachable = true;
+    backoff = std::chrono::milliseconds(0);
+    return true;
+}
+
+bool IpmbControllerHealth::requestUnanswered(Clock::time_point now)
+{
+    failures++;
+    if (reachable)
+    {
+        if (failures < failureThreshold)
+        {
+            return false;
+        }
+        reachable = false;
+        backoff = minBackoff;
+        nextProbe = now + backoff;
+        return true;
+    }
+    backoff = std::min(backoff * 2, maxBackoff);
+    nextProbe = now + backoff;
+    return false;
+}
+
+IpmbController::IpmbController(sdbusplus::asio::object_server& objectServer,
+                               uint8_t bus, uint8_t commandAddress) :
+    objectServer(objectServer)
+{
+    std::string dbusPath = controllerPathPrefix + std::string("bus") +
+                           std::to_string(bus) + "_" +
+                           std::to_string(commandAddress);
+
+    operationalInterface =
+        objectServer.add_interface(dbusPath, operationalInterfaceName);
+    operationalInterface->register_property("Functional", health.reachable);
+    operationalInterface->initialize();
+
+    availableInterface =
+        objectServer.add_interface(dbusPath, availableInterfaceName);
+    availableInterface->register_property("Available", health.reachable);
+    availableInterface->initialize();
+}
+
+IpmbController::~IpmbController()
+{
+    objectServer.remove_interface(operationalInterface);
+    objectServer.remove_interface(availableInterface);
+}
+
+bool IpmbController::requestAllowed()
+{
+    return health.requestAllowed(IpmbControllerHealth::Clock::now());
+}
+
+void IpmbController::requestAnswered()
+{
+    if (health.requestAnswered())
+    {
+        publish();
+        notify();
+    }
+}
+
+void IpmbController::requestUnanswered()
+{
+    if (health.requestUnanswered(IpmbControllerHealth::Clock::now()))
+    {
+        publish();
+        notify();
+    }
+}
+
+void IpmbController::addListener(Listener&& listener)
+{
+    listeners.emplace_back(std::move(listener));
+}
+
+void IpmbController::notify()
+{
+    auto listenerIt = listeners.begin();
+    while (listenerIt != listeners.end())
+    {
+        if ((*listenerIt)(health.reachable))
+        {
+            listenerIt++;
+        }
+        else
+        {
+            listenerIt = listeners.erase(listenerIt);
+        }
+    }
+}
+
+void IpmbController::publish()
+{
+    operationalInterface->set_property("Functional", health.reachable);
+    availableInterface->set_property("Available", health.reachable);
+}
+
+std::shared_ptr<IpmbController>
+    getIpmbController(sdbusplus::asio::object_server& objectServer,
+                      uint8_t bus, uint8_t commandAddress)
+{
+    static boost::container::flat_map<std::pair<uint8_t, uint8_t>,
+                                      std::weak_ptr<IpmbController>>
+        controllers;
+
+    std::weak_ptr<IpmbController>& weakController =
+        controllers[{bus, commandAddress}];
+    std::shared_ptr<IpmbController> controller = weakController.lock();
+    if (!controller)
+    {
+        controller = std::make_shared<IpmbController>(objectServer, bus,
+                                                      commandAddress);
+        weakController = controller;
+    }
+    return controller;
+}
+
+void sendControllerRequest(
+    const std::shared_ptr<sdbusplus::asio::connection>& conn,
+    const std::shared_ptr<IpmbController>& controller,
+    const IpmbRequestTiming& timing, uint8_t commandAddress, uint8_t netfn,
+    uint8_t lun, uint8_t command, const std::vector<uint8_t>& commandData,
+    std::function<void(const boost::system::error_code&,
+                       const IpmbMethodType&)>&& handler)
+{
+    conn->async_method_call_timed(
+        [conn, controller, timing, commandAddress, netfn, lun, command,
+         commandData, handler{std::move(handler)}](
+            const boost::system::error_code& ec,
+            const IpmbMethodType& response) mutable {
+            // A completion code is still an answer, only a failed transfer
+            // or an expired timeout isn't
+            if (!ec && std::get<0>(response) == 0)
+            {
+                controller->requestAnswered();
+                handler(ec, response);
+                return;
+            }
+            if (timing.retries > 0)
+            {
+                IpmbRequestTiming retryTiming = timing;
+                retryTiming.retries--;
+                auto timer = std::make_shared<boost::asio::steady_timer>(
+                    conn->get_io_context());
+                timer->expires_after(retryDelay);
+                timer->async_wait(
+                    [timer, conn, controller, retryTiming, commandAddress,
+                     netfn, lun, command, commandData,
+                     handler{std::move(handler)}](
+                        const boost::system::error_code&) mutable {
+                        sendControllerRequest(
+                            conn, controller, retryTiming, commandAddress,
+                            netfn, lun, command, commandData,
+                            std::move(handler));
+                    });
+                return;
+            }
+            controller->requestUnanswered();
+            handler(ec, response);
+        },
+        "xyz.openbmc_project.Ipmi.Channel.Ipmb",
+        "/xyz/openbmc_project/Ipmi/Channel/Ipmb", "org.openbmc.Ipmb",
+        "sendRequest",
+        static_cast<uint64_t>(
+            std::chrono::duration_cast<std::chrono::microseconds>(
+                timing.timeout)
+                .count()),
+        commandAddress, netfn, lun, command, commandData);
+}
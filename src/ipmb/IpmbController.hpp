@@ -0,0 +1,101 @@
+#pragma once
+
+#include "IpmbSDRSensor.hpp"
+#include "Utils.hpp"
+
+#include <boost/system/error_code.hpp>
+#include <sdbusplus/asio/connection.hpp>
+#include <sdbusplus/asio/object_server.hpp>
+
+#include <chrono>
+#include <cstddef>
+#include <cstdint>
+#include <functional>
+#include <memory>
+#include <vector>
+
+// How long a request may go unanswered, and how many more times it is sent
+// when it does, given by "RequestTimeout" in seconds and "RequestRetries"
+struct IpmbRequestTiming
+{
+    std::chrono::milliseconds timeout{2000};
+    size_t retries = 1;
+};
+
+IpmbRequestTiming requestTimingFromConfig(const SensorBaseConfigMap& cfg);
+
+// Whether a satellite controller answers the requests of its sensors. Once
+// enough of them in a row went unanswered it is unreachable, and a request is
+// only let through now and then to probe it, at exponentially growing
+// intervals, until one is answered again.
+struct IpmbControllerHealth
+{
+    using Clock = std::chrono::steady_clock;
+
+    static constexpr size_t failureThreshold = 3;
+    static constexpr std::chrono::milliseconds minBackoff{1000};
+    static constexpr std::chrono::milliseconds maxBackoff{60000};
+
+    bool reachable = true;
+    size_t failures = 0;
+    std::chrono::milliseconds backoff{0};
+    Clock::time_point nextProbe;
+
+    bool requestAllowed(Clock::time_point now);
+    // Each returns true when the controller became reachable, or unreachable
+    bool requestAnswered();
+    bool requestUnanswered(Clock::time_point now);
+};
+
+// A satellite controller, identified by its IPMB bus and command address, and
+// shared by the sensors read through it
+class IpmbController
+{
+  public:
+    // Called when the controller becomes reachable or unreachable, until it
+    // returns false
+    using Listener = std::function<bool(bool reachable)>;
+
+    IpmbController(sdbusplus::asio::object_server& objectServer, uint8_t bus,
+                   uint8_t commandAddress);
+    ~IpmbController();
+
+    IpmbController(const IpmbController&) = delete;
+    IpmbController& operator=(const IpmbController&) = delete;
+
+    bool reachable() const
+    {
+        return health.reachable;
+    }
+
+    bool requestAllowed();
+    void requestAnswered();
+    void requestUnanswered();
+    void addListener(Listener&& listener);
+
+  private:
+    sdbusplus::asio::object_server& objectServer;
+    IpmbControllerHealth health;
+    std::vector<Listener> listeners;
+    std::shared_ptr<sdbusplus::asio::dbus_interface> operationalInterface;
+    std::shared_ptr<sdbusplus::asio::dbus_interface> availableInterface;
+
+    void notify();
+    void publish();
+};
+
+// The controller is kept for as long as a sensor holds on to it
+std::shared_ptr<IpmbController>
+    getIpmbController(sdbusplus::asio::object_server& objectServer,
+                      uint8_t bus, uint8_t commandAddress);
+
+// Sends a request to the controller through the IPMB bridge, again after a
+// short pause up to the retries of the timing while it goes unanswered, and
+// tells the controller whether it was answered before calling the handler
+void sendControllerRequest(
+    const std::shared_ptr<sdbusplus::asio::connection>& conn,
+    const std::shared_ptr<IpmbController>& controller,
+    const IpmbRequestTiming& timing, uint8_t commandAddress, uint8_t netfn,
+    uint8_t lun, uint8_t command, const std::vector<uint8_t>& commandData,
+    std::function<void(const boost::system::error_code&,
+                       const IpmbMethodType&)>&& handler);
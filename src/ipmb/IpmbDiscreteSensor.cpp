@@ -0,0 +1,199 @@
+#include "IpmbDiscreteSensor.hpp"
+
+#include "IpmbController.hpp"
+#include "IpmbSDRSensor.hpp"
+#include "IpmbSensor.hpp"
+#include "Utils.hpp"
+#include "sensor.hpp"
+
+#include <boost/asio/error.hpp>
+#include <boost/asio/io_context.hpp>
+#include <sdbusplus/asio/connection.hpp>
+#include <sdbusplus/asio/object_server.hpp>
+
+#include <chrono>
+#include <cstdint>
+#include <iostream>
+#include <memory>
+#include <optional>
+#include <string>
+#include <tuple>
+#include <vector>
+
+static constexpr uint8_t lun = 0;
+
+static constexpr const char* discretePathPrefix =
+    "/xyz/openbmc_project/sensors/discrete/";
+
+IpmbDiscreteSensor::IpmbDiscreteSensor(
+    std::shared_ptr<sdbusplus::asio::connection>& conn,
+    boost::asio::io_context& io, const std::string& sensorName,
+    const std::string& sensorConfiguration,
+    sdbusplus::asio::object_server& objectServer, const SensorInfo& info,
+    uint8_t commandAddress, const float pollRate, PowerState readState) :
+    name(escapeName(sensorName)), configurationPath(sensorConfiguration),
+    commandAddress(commandAddress), sensorNumber(info.sensorNumber),
+    sensorType(info.sensorType), readingType(info.readingType),
+    readingMask(info.readingMask),
+    eventOnly(info.recordType == SDRType::sdrType03), dbusConnection(conn),
+    objectServer(objectServer), waitTimer(io),
+    sensorPollMs(static_cast<int>(pollRate * 1000)), readState(readState)
+{
+    std::string dbusPath = discretePathPrefix + name;
+    ipmi::sensor::DiscreteStatus status =
+        ipmi::sensor::discreteStatus(sensorType, readingType, state);
+
+    discreteInterface =
+        objectServer.add_interface(dbusPath, discreteInterfaceName);
+    discreteInterface->register_property("SensorType", sensorType);
+    discreteInterface->register_property("EventReadingType", readingType);
+    discreteInterface->register_property("ReadingMask", readingMask);
+    discreteInterface->register_property("State", state);
+    if (!discreteInterface->initialize())
+    {
+        std::cerr << "error initializing discrete sensor interface for "
+                  << name << "\n";
+    }
+
+    itemInterface =
+        objectServer.add_interface(dbusPath, inventoryItemInterfaceName);
+    itemInterface->register_property("PrettyName", sensorName);
+    itemInterface->register_property("Present", status.present);
+    if (!itemInterface->initialize())
+    {
+        std::cerr << "error initializing inventory item interface for " << name
+                  << "\n";
+    }
+
+    operationalInterface =
+        objectServer.add_interface(dbusPath, operationalInterfaceName);
+    operationalInterface->register_property("Functional", status.functional);
+    operationalInterface->initialize();
+
+    // Event-only sensors are never read, so they are available from the start
+    availableInterface =
+        objectServer.add_interface(dbusPath, availableInterfaceName);
+    availableInterface->register_property("Available", eventOnly);
+    availableInterface->initialize();
+
+    association = objectServer.add_interface(dbusPath, association::interface);
+    createAssociation(association, configurationPath);
+}
+
+IpmbDiscreteSensor::~IpmbDiscreteSensor()
+{
+    waitTimer.cancel();
+    objectServer.remove_interface(discreteInterface);
+    objectServer.remove_interface(itemInterface);
+    objectServer.remove_interface(operationalInterface);
+    objectServer.remove_interface(availableInterface);
+    objectServer.remove_interface(association);
+}
+
+void IpmbDiscreteSensor::init()
+{
+    controller = getIpmbController(objectServer, ipmbBusIndex, commandAddress);
+    controller->addListener([weakRef{weak_from_this()}](bool reachable) {
+        std::shared_ptr<IpmbDiscreteSensor> self = weakRef.lock();
+        if (!self)
+        {
+            return false;
+        }
+        self->controllerReachable(reachable);
+        return true;
+    });
+    if (!eventOnly)
+    {
+        read();
+    }
+}
+
+void IpmbDiscreteSensor::updateState(uint16_t newState)
+{
+    if (readingMask != 0)
+    {
+        newState &= readingMask;
+    }
+    if (newState == state)
+    {
+        return;
+    }
+    state = newState;
+    discreteInterface->set_property("State", state);
+
+    ipmi::sensor::DiscreteStatus status =
+        ipmi::sensor::discreteStatus(sensorType, readingType, state);
+    itemInterface->set_property("Present", status.present);
+    operationalInterface->set_property("Functional", status.functional);
+}
+
+void IpmbDiscreteSensor::markAvailable(bool available)
+{
+    availableInterface->set_property("Available", available);
+}
+
+void IpmbDiscreteSensor::read()
+{
+    waitTimer.expires_after(std::chrono::milliseconds(sensorPollMs));
+    waitTimer.async_wait(
+        [weakRef{weak_from_this()}](const boost::system::error_code& ec) {
+            if (ec == boost::asio::error::operation_aborted)
+            {
+                return; // we're being canceled
+            }
+            std::shared_ptr<IpmbDiscreteSensor> self = weakRef.lock();
+            if (!self)
+            {
+                return;
+            }
+            self->sendIpmbRequest();
+        });
+}
+
+void IpmbDiscreteSensor::sendIpmbRequest()
+{
+    if (!readingStateGood(readState))
+    {
+        markAvailable(false);
+        read();
+        return;
+    }
+    if (!controller->requestAllowed())
+    {
+        read();
+        return;
+    }
+    sendControllerRequest(
+        dbusConnection, controller, timing, commandAddress,
+        ipmi::sensor::netFn, lun, ipmi::sensor::getSensorReading,
+        std::vector<uint8_t>{sensorNumber},
+        [weakRef{weak_from_this()}](const boost::system::error_code& ec,
+                                    const IpmbMethodType& response) {
+            std::shared_ptr<IpmbDiscreteSensor> self = weakRef.lock();
+            if (!self)
+            {
+                return;
+            }
+            std::optional<uint16_t> newState;
+            if (!ec && std::get<0>(response) == 0)
+            {
+                newState = ipmi::sensor::discreteState(std::get<5>(response));
+            }
+            self->markAvailable(newState.has_value());
+            if (newState)
+            {
+                self->updateState(*newState);
+            }
+            self->read();
+        });
+}
+
+void IpmbDiscreteSensor::controllerReachable(bool reachable)
+{
+    // Polled sensors are available again with their next reading, event-only
+    // ones have none to wait for
+    if (!reachable || eventOnly)
+    {
+        markAvailable(reachable);
+    }
+}
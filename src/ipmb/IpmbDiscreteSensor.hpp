@@ -0,0 +1,70 @@
+#pragma once
+
+#include "IpmbController.hpp"
+#include "IpmbSDRSensor.hpp"
+#include "Utils.hpp"
+
+#include <boost/asio/io_context.hpp>
+#include <boost/asio/steady_timer.hpp>
+#include <sdbusplus/asio/connection.hpp>
+#include <sdbusplus/asio/object_server.hpp>
+
+#include <cstdint>
+#include <memory>
+#include <string>
+
+constexpr const char* inventoryItemInterfaceName =
+    "xyz.openbmc_project.Inventory.Item";
+constexpr const char* discreteInterfaceName =
+    "xyz.openbmc_project.Ipmb.DiscreteSensor";
+
+// A sensor of a remote SDR that reads states rather than a value, or that only
+// sends events. Its state holds the asserted offsets of its event/reading type
+// as bits, bit 0 for offset 0, and is published as is, along with the presence
+// and health of the device it watches, and associated with the inventory item
+// of the device's configuration.
+struct IpmbDiscreteSensor :
+    public std::enable_shared_from_this<IpmbDiscreteSensor>
+{
+    IpmbDiscreteSensor(std::shared_ptr<sdbusplus::asio::connection>& conn,
+                       boost::asio::io_context& io,
+                       const std::string& sensorName,
+                       const std::string& sensorConfiguration,
+                       sdbusplus::asio::object_server& objectServer,
+                       const SensorInfo& info, uint8_t commandAddress,
+                       float pollRate, PowerState readState);
+    ~IpmbDiscreteSensor();
+
+    void init();
+    void updateState(uint16_t newState);
+    void markAvailable(bool available);
+
+    std::string name;
+    std::string configurationPath;
+    uint8_t commandAddress = 0;
+    uint8_t ipmbBusIndex = 0;
+    uint8_t sensorNumber = 0;
+    uint8_t sensorType = 0;
+    uint8_t readingType = 0;
+    uint16_t readingMask = 0;
+    bool eventOnly = false;
+    uint16_t state = 0;
+    IpmbRequestTiming timing;
+
+  private:
+    std::shared_ptr<sdbusplus::asio::connection> dbusConnection;
+    sdbusplus::asio::object_server& objectServer;
+    std::shared_ptr<sdbusplus::asio::dbus_interface> discreteInterface;
+    std::shared_ptr<sdbusplus::asio::dbus_interface> itemInterface;
+    std::shared_ptr<sdbusplus::asio::dbus_interface> operationalInterface;
+    std::shared_ptr<sdbusplus::asio::dbus_interface> availableInterface;
+    std::shared_ptr<sdbusplus::asio::dbus_interface> association;
+    boost::asio::steady_timer waitTimer;
+    std::shared_ptr<IpmbController> controller;
+    int sensorPollMs;
+    PowerState readState;
+
+    void read();
+    void sendIpmbRequest();
+    void controllerReachable(bool reachable);
+};
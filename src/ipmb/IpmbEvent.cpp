@@ -0,0 +1,177 @@
+#include "IpmbEvent.hpp"
+
+#include "IpmbDiscreteSensor.hpp"
+#include "IpmbSDRSensor.hpp"
+#include "IpmbSensor.hpp"
+#include "Thresholds.hpp"
+
+#include <boost/container/flat_map.hpp>
+#include <phosphor-logging/lg2.hpp>
+#include <sdbusplus/asio/object_server.hpp>
+
+#include <algorithm>
+#include <array>
+#include <cstdint>
+#include <iostream>
+#include <memory>
+#include <optional>
+#include <string>
+#include <utility>
+#include <vector>
+
+static constexpr const char* eventsPath = "/xyz/openbmc_project/ipmb/events";
+
+std::optional<PlatformEvent>
+    PlatformEvent::parse(const std::vector<uint8_t>& data)
+{
+    if (data.size() < ipmi::event::messageLength ||
+        data[ipmi::event::evmRevByte] != ipmi::event::evmRev)
+    {
+        return std::nullopt;
+    }
+    PlatformEvent event;
+    event.sensorType = data[ipmi::event::sensorTypeByte];
+    event.sensorNumber = data[ipmi::event::sensorNumberByte];
+    uint8_t eventType = data[ipmi::event::eventTypeByte];
+    event.eventType = eventType & ipmi::event::eventTypeMask;
+    event.assertion = (eventType & ipmi::event::deassertionBit) == 0;
+    uint8_t eventData1 = data[ipmi::event::eventData1Byte];
+    event.offset = eventData1 & ipmi::event::offsetMask;
+    if (event.eventType == sdr::readingTypeThreshold &&
+        (eventData1 & ipmi::event::eventData2Mask) ==
+            ipmi::event::eventData2Trigger)
+    {
+        event.triggerReading = data[ipmi::event::eventData2Byte];
+    }
+    return event;
+}
+
+std::optional<std::pair<thresholds::Level, thresholds::Direction>>
+    PlatformEvent::threshold() const
+{
+    // Each threshold has an offset for going low, then one for going high
+    static constexpr std::array<
+        std::pair<thresholds::Level, thresholds::Direction>, 6>
+        offsetThresholds = {{
+            {thresholds::Level::WARNING, thresholds::Direction::LOW},
+            {thresholds::Level::CRITICAL, thresholds::Direction::LOW},
+            {thresholds::Level::HARDSHUTDOWN, thresholds::Direction::LOW},
+            {thresholds::Level::WARNING, thresholds::Direction::HIGH},
+            {thresholds::Level::CRITICAL, thresholds::Direction::HIGH},
+            {thresholds::Level::HARDSHUTDOWN, thresholds::Direction::HIGH},
+        }};
+    size_t index = offset / 2;
+    if (eventType != sdr::readingTypeThreshold ||
+        index >= offsetThresholds.size())
+    {
+        return std::nullopt;
+    }
+    return offsetThresholds[index];
+}
+
+IpmbEventReceiver::IpmbEventReceiver(
+    sdbusplus::asio::object_server& objectServer,
+    boost::container::flat_map<std::string, std::shared_ptr<IpmbSensor>>&
+        sensors,
+    boost::container::flat_map<std::string,
+                               std::shared_ptr<IpmbDiscreteSensor>>&
+        discreteSensors) :
+    objectServer(objectServer), sensors(sensors),
+    discreteSensors(discreteSensors)
+{
+    eventsInterface =
+        objectServer.add_interface(eventsPath, ipmbEventsInterfaceName);
+    eventsInterface->register_method(
+        "PlatformEvent", [this](uint8_t bus, uint8_t address,
+                                const std::vector<uint8_t>& data) {
+            receive(bus, address, data);
+        });
+    if (!eventsInterface->initialize())
+    {
+        std::cerr << "error initializing IPMB events interface\n";
+    }
+}
+
+IpmbEventReceiver::~IpmbEventReceiver()
+{
+    objectServer.remove_interface(eventsInterface);
+}
+
+void IpmbEventReceiver::receive(uint8_t bus, uint8_t address,
+                                const std::vector<uint8_t>& data)
+{
+    std::optional<PlatformEvent> event = PlatformEvent::parse(data);
+    if (!event)
+    {
+        lg2::error("Invalid IPMB platform event on bus {BUS} from {ADDRESS}",
+                   "BUS", bus, "ADDRESS", lg2::hex, address);
+        return;
+    }
+
+    lg2::info("IPMB platform event on bus {BUS} from {ADDRESS} for sensor "
+              "{SENSOR}: type {TYPE}, offset {OFFSET}, {ASSERTION}",
+              "BUS", bus, "ADDRESS", lg2::hex, address, "SENSOR",
+              event->sensorNumber, "TYPE", lg2::hex, event->eventType,
+              "OFFSET", event->offset, "ASSERTION",
+              event->assertion ? "asserted" : "deasserted");
+
+    // Sensor numbers are only unique within the SDR of one controller
+    for (const auto& [name, sensor] : sensors)
+    {
+        if (sensor->type == IpmbType::sdrSensor &&
+            sensor->ipmbBusIndex == bus && sensor->commandAddress == address &&
+            sensor->deviceAddress == event->sensorNumber)
+        {
+            applyThresholdEvent(*sensor, *event);
+            return;
+        }
+    }
+    for (const auto& [name, sensor] : discreteSensors)
+    {
+        if (sensor->ipmbBusIndex == bus && sensor->commandAddress == address &&
+            sensor->sensorNumber == event->sensorNumber)
+        {
+            uint16_t offsetBit = 1U << event->offset;
+            sensor->updateState(event->assertion ? sensor->state | offsetBit
+                                                 : sensor->state & ~offsetBit);
+            return;
+        }
+    }
+    lg2::info("No sensor for IPMB platform event on bus {BUS} from {ADDRESS} "
+              "for sensor {SENSOR}",
+              "BUS", bus, "ADDRESS", lg2::hex, address, "SENSOR",
+              event->sensorNumber);
+}
+
+void IpmbEventReceiver::applyThresholdEvent(IpmbSensor& sensor,
+                                            const PlatformEvent& event)
+{
+    std::optional<std::pair<thresholds::Level, thresholds::Direction>>
+        crossed = event.threshold();
+    if (!crossed)
+    {
+        return;
+    }
+    const auto& [level, direction] = *crossed;
+    auto threshold = std::ranges::find_if(
+        sensor.thresholds, [&](const thresholds::Threshold& candidate) {
+            return candidate.level == level &&
+                   candidate.direction == direction;
+        });
+    if (threshold == sensor.thresholds.end())
+    {
+        return;
+    }
+
+    // The reading that triggered the event, converted like polled ones, or
+    // else the threshold it crossed, as the last reading may not be known
+    double value = threshold->value;
+    if (event.triggerReading && sensor.sdrConversion)
+    {
+        value = IpmbSDRDevice::sensorReading(*sensor.sdrConversion,
+                                             *event.triggerReading);
+        value = (value * sensor.scaleVal) + sensor.offsetVal;
+    }
+    thresholds::assertThresholds(&sensor, value, level, direction,
+                                 event.assertion);
+}
@@ -0,0 +1,94 @@
+#pragma once
+
+#include "IpmbDiscreteSensor.hpp"
+#include "IpmbSensor.hpp"
+#include "Thresholds.hpp"
+
+#include <boost/container/flat_map.hpp>
+#include <sdbusplus/asio/object_server.hpp>
+
+#include <cstddef>
+#include <cstdint>
+#include <memory>
+#include <optional>
+#include <string>
+#include <utility>
+#include <vector>
+
+constexpr const char* ipmbEventsInterfaceName =
+    "xyz.openbmc_project.Ipmb.Events";
+
+namespace ipmi::event
+{
+// Platform Event Message request bytes
+constexpr uint8_t evmRevByte = 0;
+constexpr uint8_t sensorTypeByte = 1;
+constexpr uint8_t sensorNumberByte = 2;
+constexpr uint8_t eventTypeByte = 3;
+constexpr uint8_t eventData1Byte = 4;
+constexpr uint8_t eventData2Byte = 5;
+constexpr uint8_t eventData3Byte = 6;
+constexpr size_t messageLength = 7;
+
+constexpr uint8_t evmRev = 0x04;
+constexpr uint8_t deassertionBit = 0x80;
+constexpr uint8_t eventTypeMask = 0x7f;
+constexpr uint8_t offsetMask = 0x0f;
+// Event data 2 holds the reading that triggered a threshold event
+constexpr uint8_t eventData2Mask = 0xc0;
+constexpr uint8_t eventData2Trigger = 0x40;
+} // namespace ipmi::event
+
+// A Platform Event Message that a satellite controller sent about one of its
+// sensors
+struct PlatformEvent
+{
+    uint8_t sensorType = 0;
+    uint8_t sensorNumber = 0;
+    uint8_t eventType = 0;
+    bool assertion = true;
+    uint8_t offset = 0;
+    std::optional<uint8_t> triggerReading;
+
+    static std::optional<PlatformEvent> parse(const std::vector<uint8_t>& data);
+
+    // The threshold that a threshold event is about
+    std::optional<std::pair<thresholds::Level, thresholds::Direction>>
+        threshold() const;
+};
+
+// Receives the Platform Event Messages of satellite controllers, and applies
+// them to the sensors of the SDR they belong to right away. The stock IPMB
+// bridge hands these to the IPMI host handler instead, so it has to be changed
+// to call PlatformEvent for them, with the address of the controller that sent
+// them.
+class IpmbEventReceiver
+{
+  public:
+    IpmbEventReceiver(
+        sdbusplus::asio::object_server& objectServer,
+        boost::container::flat_map<std::string, std::shared_ptr<IpmbSensor>>&
+            sensors,
+        boost::container::flat_map<std::string,
+                                   std::shared_ptr<IpmbDiscreteSensor>>&
+            discreteSensors);
+    ~IpmbEventReceiver();
+
+    IpmbEventReceiver(const IpmbEventReceiver&) = delete;
+    IpmbEventReceiver& operator=(const IpmbEventReceiver&) = delete;
+
+    void receive(uint8_t bus, uint8_t address,
+                 const std::vector<uint8_t>& data);
+
+  private:
+    sdbusplus::asio::object_server& objectServer;
+    boost::container::flat_map<std::string, std::shared_ptr<IpmbSensor>>&
+        sensors;
+    boost::container::flat_map<std::string,
+                               std::shared_ptr<IpmbDiscreteSensor>>&
+        discreteSensors;
+    std::shared_ptr<sdbusplus::asio::dbus_interface> eventsInterface;
+
+    static void applyThresholdEvent(IpmbSensor& sensor,
+                                    const PlatformEvent& event);
+};
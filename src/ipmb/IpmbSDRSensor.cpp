@@ -1,13 +1,16 @@
 #include "IpmbSDRSensor.hpp"
 
+#include <boost/asio/error.hpp>
 #include <sdbusplus/asio/connection.hpp>
 
+#include <algorithm>
 #include <cmath>
 #include <cstddef>
 #include <cstdint>
 #include <functional>
 #include <iostream>
 #include <memory>
+#include <optional>
 #include <string>
 #include <utility>
 #include <vector>
@@ -20,8 +23,10 @@ static constexpr uint8_t lun = 0;
 
 IpmbSDRDevice::IpmbSDRDevice(
     std::shared_ptr<sdbusplus::asio::connection>& dbusConnection,
-    uint8_t cmdAddr) :
-    commandAddress(cmdAddr << 2), hostIndex(cmdAddr + 1), conn(dbusConnection)
+    uint8_t cmdAddr, const std::string& configPath) :
+    commandAddress(cmdAddr << 2), hostIndex(cmdAddr + 1),
+    configPath(configPath), conn(dbusConnection),
+    refreshTimer(dbusConnection->get_io_context())
 {}
 
 bool validateStatus(boost::system::error_code ec,
@@ -43,7 +48,7 @@ bool validateStatus(boost::system::error_code ec,
 }
 
 /* This function will store the record count of the SDR sensors for each IPMB
- * bus */
+ * bus, and walk the records again when the repository has changed */
 void IpmbSDRDevice::getSDRRepositoryInfo()
 {
     std::weak_ptr<IpmbSDRDevice> weakRef = weak_from_this();
@@ -56,6 +61,7 @@ void IpmbSDRDevice::getSDRRepositoryInfo()
             {
                 return;
             }
+            self->scheduleRefresh();
 
             auto status = std::bind_front(validateStatus, ec, response);
             if (!status(self->hostIndex))
@@ -63,10 +69,9 @@ void IpmbSDRDevice::getSDRRepositoryInfo()
                 return;
             }
 
-            const std::vector<uint8_t>& data = std::get<5>(response);
-            const size_t sdrInfoDataSize = 14;
-
-            if (data.size() < sdrInfoDataSize)
+            std::optional<SDRRepositoryInfo> info =
+                parseRepositoryInfo(std::get<5>(response));
+            if (!info)
             {
                 std::cerr
                     << " IPMB Get SDR Repository Info data is empty for host "
@@ -74,28 +79,102 @@ void IpmbSDRDevice::getSDRRepositoryInfo()
                 return;
             }
 
-            constexpr uint8_t recordCountLSB = 1;
-            constexpr uint8_t recordCountMSB = 2;
-
-            uint16_t recordCount = (data[recordCountMSB] << 8) |
-                                   data[recordCountLSB];
-
-            self->reserveSDRRepository(recordCount);
+            if (info == self->walkedInfo)
+            {
+                return;
+            }
+            self->startWalk(*info);
         },
         ipmbService, ipmbDbusPath, ipmbInterface, ipmbMethod, commandAddress,
         sdr::netfnStorageReq, lun, sdr::cmdStorageGetSdrInfo, sdrCommandData);
 }
 
+std::optional<SDRRepositoryInfo>
+    IpmbSDRDevice::parseRepositoryInfo(const std::vector<uint8_t>& data)
+{
+    const size_t sdrInfoDataSize = 14;
+    if (data.size() < sdrInfoDataSize)
+    {
+        return std::nullopt;
+    }
+
+    SDRRepositoryInfo info;
+    info.recordCount = (data[sdr::infoRecordCountMSB] << 8) |
+                       data[sdr::infoRecordCountLSB];
+
+    // The timestamps are sent least significant byte first
+    for (size_t ii = 4; ii > 0; ii--)
+    {
+        info.additionTimestamp = (info.additionTimestamp << 8) |
+                                 data[sdr::infoAdditionTimestamp + ii - 1];
+        info.eraseTimestamp = (info.eraseTimestamp << 8) |
+                              data[sdr::infoEraseTimestamp + ii - 1];
+    }
+    return info;
+}
+
+void IpmbSDRDevice::scheduleRefresh()
+{
+    std::weak_ptr<IpmbSDRDevice> weakRef = weak_from_this();
+
+    refreshTimer.expires_after(sdr::refreshInterval);
+    refreshTimer.async_wait([weakRef](const boost::system::error_code& ec) {
+        if (ec == boost::asio::error::operation_aborted)
+        {
+            return; // we're being canceled
+        }
+        auto self = weakRef.lock();
+        if (!self)
+        {
+            return;
+        }
+        self->getSDRRepositoryInfo();
+    });
+}
+
+/* This function will discard the records read so far and read them all
+ * again */
+void IpmbSDRDevice::startWalk(const SDRRepositoryInfo& info)
+{
+    walk++;
+    walkInfo = info;
+    sdrData.clear();
+    validRecordCount = 1;
+    iCnt = 0;
+    nextRecordIDLSB = 0;
+    nextRecordIDMSB = 0;
+    sensorRecord[hostIndex - 1].clear();
+    sensorValRecord[hostIndex - 1].clear();
+
+    if (info.recordCount == 0)
+    {
+        completeWalk();
+        return;
+    }
+    reserveSDRRepository(info.recordCount);
+}
+
+void IpmbSDRDevice::completeWalk()
+{
+    nextRecordIDLSB = 0;
+    nextRecordIDMSB = 0;
+    walkedInfo = walkInfo;
+    if (onUpdate)
+    {
+        onUpdate(*this);
+    }
+}
+
 /* This function will store the reserve ID for each IPMB bus index */
 void IpmbSDRDevice::reserveSDRRepository(uint16_t recordCount)
 {
     std::weak_ptr<IpmbSDRDevice> weakRef = weak_from_this();
 
     conn->async_method_call(
-        [weakRef, recordCount](boost::system::error_code ec,
-                               const IpmbMethodType& response) {
+        [weakRef, recordCount, walk{walk}](boost::system::error_code ec,
+                                           const IpmbMethodType& response) {
             auto self = weakRef.lock();
-            if (!self)
+            if (!self || self->walk != walk)
             {
                 return;
             }
@@ -138,10 +217,10 @@ void IpmbSDRDevice::getSDRSensorData(uint16_t recordCount, uint8_t resrvIDLSB,
                                         loopCount,       sdr::perCountByte};
 
     conn->async_method_call(
-        [weakRef, recordCount, resrvIDLSB, resrvIDMSB](
+        [weakRef, recordCount, resrvIDLSB, resrvIDMSB, walk{walk}](
             boost::system::error_code ec, const IpmbMethodType& response) {
             auto self = weakRef.lock();
-            if (!self)
+            if (!self || self->walk != walk)
             {
                 return;
             }
@@ -175,8 +254,10 @@ void IpmbSDRDevice::handleSDRData(const std::vector<uint8_t>& data,
 {
     sdrData.insert(sdrData.end(), data.begin(), data.end());
 
-    /* dataLength represents the size of data for SDR types */
-    uint8_t dataLength = sdrData[sdr::dataLengthByte] + sdr::dataLengthByte + 1;
+    /* dataLength represents the size of data for SDR types, including the
+     * next record ID ahead of each 16 bytes of the record */
+    size_t dataLength =
+        sdr::recordIndex(sdrData[sdr::dataLengthByte] + sdr::headerSize) + 1;
 
     /*  If sdrData size is less than dataLength, it will call getSDRSensorData
      *  function recursively till all the data is received.
@@ -204,8 +285,7 @@ void IpmbSDRDevice::handleSDRData(const std::vector<uint8_t>& data,
         {
             /* Once all the sensors are read and recordCount matched, it will
              * return. */
-            nextRecordIDLSB = 0;
-            nextRecordIDMSB = 0;
+            completeWalk();
             return;
         }
         validRecordCount++;
@@ -216,7 +296,7 @@ void IpmbSDRDevice::handleSDRData(const std::vector<uint8_t>& data,
 /* This function will convert the SDR sensor data such as sensor unit, name, ID,
  * type from decimal to readable format */
 void IpmbSDRDevice::checkSDRData(std::vector<uint8_t>& sdrDataBytes,
-                                 uint8_t dataLength) const
+                                 size_t dataLength) const
 {
     if (sdrDataBytes.size() < dataLength)
     {
@@ -224,29 +304,102 @@ void IpmbSDRDevice::checkSDRData(std::vector<uint8_t>& sdrDataBytes,
     }
 
     /* sdrType represents the SDR Type (Byte 5) such as 1, 2, 3 */
-    uint8_t sdrType = sdrDataBytes[sdr::sdrType];
-    if (sdrType != static_cast<uint8_t>(SDRType::sdrType01))
+    switch (static_cast<SDRType>(sdrDataBytes[sdr::sdrType]))
     {
-        return;
+        case SDRType::sdrType01:
+            checkSDRType01Threshold(
+                sdrDataBytes, (hostIndex - 1),
+                recordName(sdrDataBytes, sdrtype01::nameLengthByte));
+            break;
+        case SDRType::sdrType02:
+            checkSDRType02(sdrDataBytes, (hostIndex - 1),
+                           recordName(sdrDataBytes, sdrtype02::nameLengthByte));
+            break;
+        case SDRType::sdrType03:
+            checkSDRType03(sdrDataBytes, (hostIndex - 1),
+                           recordName(sdrDataBytes, sdrtype03::nameLengthByte));
+            break;
+        default:
+            break;
     }
+}
 
-    /*  dataLen represents the data length (Byte 6) for SDR sensor */
-    int dataLen = sdrDataBytes[sdr::dataLengthByte];
+std::string IpmbSDRDevice::recordName(const std::vector<uint8_t>& sdrDataBytes,
+                                      size_t lengthByte)
+{
+    if (lengthByte >= sdrDataBytes.size())
+    {
+        return {};
+    }
 
-    /* iStrLen represents the length of the sensor name for SDR Type 1 */
+    /* strLen represents the length of the sensor name */
     const uint8_t sdrLenBit = 0x1F;
-    int strLen = (sdrDataBytes[sdrtype01::nameLengthByte]) & (sdrLenBit);
+    size_t strLen = sdrDataBytes[lengthByte] & sdrLenBit;
+
+    /* The name may run on past the next record ID ahead of the next 16 bytes
+     * of the record */
+    const size_t responseSize = sdr::perCountByte + 2;
+    std::string tempName;
+    for (size_t ii = lengthByte + 1;
+         ii < sdrDataBytes.size() && tempName.size() < strLen; ii++)
+    {
+        if (ii % responseSize < 2)
+        {
+            continue;
+        }
+        tempName.push_back(static_cast<char>(sdrDataBytes[ii]));
+    }
+    return tempName;
+}
 
-    /* iStrAddr represents the starting byte (Byte 56) for SDR sensor name */
-    int strAddr = dataLen + ((dataLen / (sdr::perCountByte)) * 4) -
-                  (strLen - 1);
+/* Compact and event-only records may stand for several sensors with
+ * consecutive sensor numbers, whose names end in a number or letters counting
+ * up from the offset */
+static void addSharedRecords(SensorInfo& temp, uint8_t sharing,
+                             uint8_t sharingOffset, int busIndex)
+{
+    uint8_t count = std::max(sharing & sdr::shareCountMask, 1);
+    if (count == 1)
+    {
+        sensorRecord[busIndex].emplace_back(std::move(temp));
+        return;
+    }
 
-    /* Below for loop will convert the bytes to string and form a sensor name */
+    uint8_t offset = sharingOffset & sdr::shareOffsetMask;
+    for (uint8_t ii = 0; ii < count; ii++)
+    {
+        SensorInfo shared = temp;
+        shared.sensorNumber = temp.sensorNumber + ii;
 
-    std::string tempName(sdrDataBytes.begin() + strAddr,
-                         sdrDataBytes.begin() + strAddr + strLen);
+        unsigned int modifier = offset + ii;
+        if ((sharing & sdr::shareModifierAlpha) != 0)
+        {
+            // A to Z, then AA to ZZ
+            std::string letters;
+            do
+            {
+                letters.insert(letters.begin(),
+                               static_cast<char>('A' + (modifier % 26)));
+                modifier = modifier / 26;
+            } while (modifier-- > 0);
+            shared.sensorReadName += letters;
+        }
+        else
+        {
+            shared.sensorReadName += std::to_string(modifier);
+        }
+        sensorRecord[busIndex].emplace_back(std::move(shared));
+    }
+}
 
-    checkSDRType01Threshold(sdrDataBytes, (hostIndex - 1), tempName);
+/* M and B are 10 bit two's complement values */
+static int16_t signExtend10(uint16_t value)
+{
+    if ((value & 0x200) != 0)
+    {
+        return static_cast<int16_t>(value) - 0x400;
+    }
+    return static_cast<int16_t>(value);
 }
 
 /* This function will convert the raw value of threshold for each sensor */
@@ -255,6 +408,24 @@ void IpmbSDRDevice::checkSDRType01Threshold(std::vector<uint8_t>& sdrDataBytes,
 {
     const uint8_t bitShiftMsb = 2;
     const uint8_t sdrThresAccess = 0x0C;
+    const uint8_t sdrThresMask = 0x3F;
+
+    struct SensorInfo temp;
+
+    temp.sensorReadName = std::move(tempName);
+    temp.sensorNumber = sdrDataBytes[sdr::sdrSensorNum];
+    temp.sensorType = sdrDataBytes[sdrtype01::sensorTypeByte];
+    temp.readingType = sdrDataBytes[sdrtype01::readingTypeByte];
+
+    /* Discrete sensors only read the state bits of their reading mask */
+    if (temp.isDiscrete())
+    {
+        temp.readingMask = static_cast<uint16_t>(
+            ((sdrDataBytes[sdrtype01::readingMaskMSB] & 0x7F) << 8) |
+            sdrDataBytes[sdrtype01::readingMaskLSB]);
+        sensorRecord[busIndex].emplace_back(std::move(temp));
+        return;
+    }
 
     /* linear represents the sensor's linearization (Byte 27) */
     uint8_t linear = sdrDataBytes[sdrtype01::sdrLinearByte];
@@ -272,17 +443,17 @@ void IpmbSDRDevice::checkSDRType01Threshold(std::vector<uint8_t>& sdrDataBytes,
      * mDataByte    - Byte 28 - 8 bits LSB
      * mTolDataByte - Byte 29 - 2 bits MSB [7-6]
      */
-    uint16_t mData =
+    int16_t mData = signExtend10(
         ((sdrDataBytes[sdrtype01::mTolDataByte] & 0xC0) << bitShiftMsb) |
-        sdrDataBytes[sdrtype01::mDataByte];
+        sdrDataBytes[sdrtype01::mDataByte]);
 
     /* bData        - 10 bits
      * bDataByte    - Byte 30 - 8 bits LSB
      * bAcuDataByte - Byte 31 - 2 bits MSB [7-6]
      */
-    uint16_t bData =
+    int16_t bData = signExtend10(
         ((sdrDataBytes[sdrtype01::bAcuDataByte] & 0xC0) << bitShiftMsb) |
-        sdrDataBytes[sdrtype01::bDataByte];
+        sdrDataBytes[sdrtype01::bDataByte]);
 
     /* rbExpDataByte (Byte 33) represents the exponent value
      *  Bit [3-0] - B Exponent 2's complement signed bit.
@@ -292,6 +463,7 @@ void IpmbSDRDevice::checkSDRType01Threshold(std::vector<uint8_t>& sdrDataBytes,
     if (bExpVal > 7)
     {
         bExpVal = (~bExpVal + 1) & 0xF;
+        bExpVal = -bExpVal;
     }
 
     /* Shifting the data to right by 4, since rExpVal has 4 bits from 4 to 7 in
@@ -317,36 +489,153 @@ void IpmbSDRDevice::checkSDRType01Threshold(std::vector<uint8_t>& sdrDataBytes,
     double bDataVal = bData * pow(10, bExpVal);
     double expVal = pow(10, rExpVal);
 
-    double thresUpCri =
-        sensorValCalculation(mData, bDataVal, expVal,
-                             sdrDataBytes[sdrtype01::upperCriticalThreshold]);
-    double thresLoCri =
-        sensorValCalculation(mData, bDataVal, expVal,
-                             sdrDataBytes[sdrtype01::lowerCriticalThreshold]);
-
-    struct SensorInfo temp;
+    SensorValConversion val = {mData, bDataVal, expVal,
+                               sdrDataBytes[sdrtype01::sdrNegHandle]};
 
-    temp.sensorReadName = std::move(tempName);
     temp.sensorUnit = sdrDataBytes[sdrtype01::sdrUnitType];
 
-    temp.thresUpperCri = thresUpCri;
-    temp.thresLowerCri = thresLoCri;
+    temp.thresUpperNonRec = sensorReading(
+        val, sdrDataBytes[sdrtype01::upperNonRecoverableThreshold]);
+    temp.thresUpperCri = sensorReading(
+        val, sdrDataBytes[sdrtype01::upperCriticalThreshold]);
+    temp.thresUpperNonCri = sensorReading(
+        val, sdrDataBytes[sdrtype01::upperNonCriticalThreshold]);
+    temp.thresLowerNonCri = sensorReading(
+        val, sdrDataBytes[sdrtype01::lowerNonCriticalThreshold]);
+    temp.thresLowerCri = sensorReading(
+        val, sdrDataBytes[sdrtype01::lowerCriticalThreshold]);
+    temp.thresLowerNonRec = sensorReading(
+        val, sdrDataBytes[sdrtype01::lowerNonRecoverableThreshold]);
+
+    /* Hysteresis is a raw difference, so it is scaled without the offset */
+    temp.hysteresisPositive = std::abs(sensorValCalculation(
+        mData, 0, expVal, sdrDataBytes[sdrtype01::positiveHysteresis]));
+    temp.hysteresisNegative = std::abs(sensorValCalculation(
+        mData, 0, expVal, sdrDataBytes[sdrtype01::negativeHysteresis]));
 
-    temp.sensorNumber = sdrDataBytes[sdr::sdrSensorNum];
     temp.sensCap = threshold;
+    if (threshold != sdrtype01::sdrSensNoThres)
+    {
+        temp.readableThresholds = sdrDataBytes[sdrtype01::readingMaskLSB] &
+                                  sdrThresMask;
+    }
 
     sensorRecord[busIndex].emplace_back(std::move(temp));
 
-    SensorValConversion val = {mData, bDataVal, expVal,
-                               sdrDataBytes[sdrtype01::sdrNegHandle]};
-
     sensorValRecord[busIndex][sdrDataBytes[sdr::sdrSensorNum]] = val;
 }
 
+/* Compact records carry no conversion factors, so the sensors are read for
+ * their state bits only */
+void IpmbSDRDevice::checkSDRType02(std::vector<uint8_t>& sdrDataBytes,
+                                   int busIndex, std::string tempName)
+{
+    struct SensorInfo temp;
+
+    temp.recordType = SDRType::sdrType02;
+    temp.sensorReadName = std::move(tempName);
+    temp.sensorNumber = sdrDataBytes[sdr::sdrSensorNum];
+    temp.sensorType = sdrDataBytes[sdrtype02::sensorTypeByte];
+    temp.readingType = sdrDataBytes[sdrtype02::readingTypeByte];
+    temp.readingMask = static_cast<uint16_t>(
+        ((sdrDataBytes[sdrtype02::readingMaskMSB] & 0x7F) << 8) |
+        sdrDataBytes[sdrtype02::readingMaskLSB]);
+
+    addSharedRecords(temp, sdrDataBytes[sdrtype02::recordSharing],
+                     sdrDataBytes[sdrtype02::recordSharingOffset], busIndex);
+}
+
+/* Event-only sensors can't be read, their state only changes with the events
+ * they send */
+void IpmbSDRDevice::checkSDRType03(std::vector<uint8_t>& sdrDataBytes,
+                                   int busIndex, std::string tempName)
+{
+    struct SensorInfo temp;
+
+    temp.recordType = SDRType::sdrType03;
+    temp.sensorReadName = std::move(tempName);
+    temp.sensorNumber = sdrDataBytes[sdr::sdrSensorNum];
+    temp.sensorType = sdrDataBytes[sdrtype03::sensorTypeByte];
+    temp.readingType = sdrDataBytes[sdrtype03::readingTypeByte];
+
+    addSharedRecords(temp, sdrDataBytes[sdrtype03::recordSharing],
+                     sdrDataBytes[sdrtype03::recordSharingOffset], busIndex);
+}
+
 /* This function will calculate the sensor's threshold value */
-double IpmbSDRDevice::sensorValCalculation(uint16_t mValue, double bValue,
+double IpmbSDRDevice::sensorValCalculation(int16_t mValue, double bValue,
                                            double expValue, double value)
 {
     double sensorValue = ((mValue * value) + bValue) * expValue;
     return sensorValue;
 }
+
+double IpmbSDRDevice::sensorReading(const SensorValConversion& conversion,
+                                    uint8_t raw)
+{
+    uint8_t format = conversion.negRead >> sdrtype01::analogFormatShift;
+
+    double value = raw;
+    if (raw > sdrtype01::maxPosReadingMargin)
+    {
+        if (format == sdrtype01::analogTwosComplement)
+        {
+            value = raw - sdrtype01::thermalConst;
+        }
+        else if (format == sdrtype01::analogOnesComplement)
+        {
+            value = raw - (sdrtype01::thermalConst - 1);
+        }
+    }
+    return sensorValCalculation(conversion.mValue, conversion.bValue,
+                                conversion.expoVal, value);
+}
+
+std::pair<double, double>
+    IpmbSDRDevice::readingRange(const SensorValConversion& conversion)
+{
+    uint8_t format = conversion.negRead >> sdrtype01::analogFormatShift;
+
+    uint8_t lowest = 0x00;
+    uint8_t highest = 0xff;
+    if (format == sdrtype01::analogTwosComplement ||
+        format == sdrtype01::analogOnesComplement)
+    {
+        lowest = sdrtype01::twosCompVal;
+        highest = sdrtype01::maxPosReadingMargin;
+    }
+
+    // M may be negative, turning the range around
+    double first = sensorReading(conversion, lowest);
+    double second = sensorReading(conversion, highest);
+    return {std::min(first, second), std::max(first, second)};
+}
+
+std::optional<std::string>
+    IpmbSDRDevice::sensorTypeName(const SensorInfo& info,
+                                  const SensorValConversion& conversion)
+{
+    uint8_t format = conversion.negRead >> sdrtype01::analogFormatShift;
+    if (format == sdrtype01::analogNoReading)
+    {
+        return std::nullopt;
+    }
+    if ((conversion.negRead & sdrtype01::unitPercentage) != 0)
+    {
+        return "utilization";
+    }
+
+    switch (info.sensorUnit)
+    {
+        case sdrtype01::unitDegreesC:
+            return "temperature";
+        case sdrtype01::unitVolts:
+            return "voltage";
+        case sdrtype01::unitAmps:
+            return "current";
+        case sdrtype01::unitWatts:
+            return "power";
+        default:
+            return std::nullopt;
+    }
+}
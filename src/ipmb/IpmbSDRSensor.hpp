@@ -1,7 +1,19 @@
 #pragma once
 
+#include <boost/asio/steady_timer.hpp>
 #include <sensor.hpp>
 
+#include <chrono>
+#include <cstddef>
+#include <cstdint>
+#include <functional>
+#include <map>
+#include <memory>
+#include <optional>
+#include <string>
+#include <utility>
+#include <vector>
+
 using IpmbMethodType =
     std::tuple<int, uint8_t, uint8_t, uint8_t, uint8_t, std::vector<uint8_t>>;
 
@@ -25,10 +37,36 @@ static constexpr uint8_t sdrNxtRecLSB = 0;
 static constexpr uint8_t sdrNxtRecMSB = 1;
 static constexpr uint8_t perCountByte = 16;
 
+// Get SDR Repository Info response bytes
+static constexpr uint8_t infoRecordCountLSB = 1;
+static constexpr uint8_t infoRecordCountMSB = 2;
+static constexpr uint8_t infoAdditionTimestamp = 5;
+static constexpr uint8_t infoEraseTimestamp = 9;
+
+// How often the repository is checked for changes
+static constexpr std::chrono::seconds refreshInterval(60);
+
 // Sensor Record Bytes
 static constexpr uint8_t sdrType = 5;
 static constexpr uint8_t dataLengthByte = 6;
 static constexpr uint8_t sdrSensorNum = 9;
+static constexpr uint8_t headerSize = 5;
+
+// Event/Reading Type Code, IPMI v2.0 Table 42-1
+static constexpr uint8_t readingTypeThreshold = 0x01;
+
+// Record Sharing (Type 02 and Type 03)
+static constexpr uint8_t shareCountMask = 0x0F;
+static constexpr uint8_t shareModifierAlpha = 0x10;
+static constexpr uint8_t shareOffsetMask = 0x7F;
+
+// The next record ID of each Get SDR response is kept ahead of the 16 bytes
+// of the record that follow it, so this is the index of a record byte,
+// counted from 1 as in the specification
+constexpr size_t recordIndex(size_t byte)
+{
+    return byte + 1 + (2 * ((byte - 1) / perCountByte));
+}
 
 } // namespace sdr
 
@@ -40,6 +78,33 @@ static constexpr uint8_t twosCompVal = 128;
 static constexpr double thermalConst = 256;
 
 static constexpr uint8_t sdrSensNoThres = 0;
+static constexpr uint8_t sensorTypeByte = 14;
+static constexpr uint8_t readingTypeByte = 15;
+
+// Readable threshold mask of threshold sensors, discrete reading mask of others
+static constexpr uint8_t readingMaskLSB = 22;
+static constexpr uint8_t readingMaskMSB = 23;
+static constexpr uint8_t thresholdLowerNonCritical = 0x01;
+static constexpr uint8_t thresholdLowerCritical = 0x02;
+static constexpr uint8_t thresholdLowerNonRecoverable = 0x04;
+static constexpr uint8_t thresholdUpperNonCritical = 0x08;
+static constexpr uint8_t thresholdUpperCritical = 0x10;
+static constexpr uint8_t thresholdUpperNonRecoverable = 0x20;
+
+// Sensor Units 1 (sdrNegHandle), analog data format in bits [7-6]
+static constexpr uint8_t analogFormatShift = 6;
+static constexpr uint8_t analogUnsigned = 0;
+static constexpr uint8_t analogOnesComplement = 1;
+static constexpr uint8_t analogTwosComplement = 2;
+static constexpr uint8_t analogNoReading = 3;
+static constexpr uint8_t unitPercentage = 0x01;
+
+// Sensor Units 2 (sdrUnitType), IPMI v2.0 Table 43-15
+static constexpr uint8_t unitDegreesC = 1;
+static constexpr uint8_t unitVolts = 4;
+static constexpr uint8_t unitAmps = 5;
+static constexpr uint8_t unitWatts = 6;
+
 static constexpr uint8_t sensorCapability = 13;
 static constexpr uint8_t sdrNegHandle = 24;
 static constexpr uint8_t sdrUnitType = 25;
@@ -51,12 +116,42 @@ static constexpr uint8_t mTolDataByte = 29;
 static constexpr uint8_t bDataByte = 30;
 static constexpr uint8_t bAcuDataByte = 31;
 static constexpr uint8_t rbExpDataByte = 33;
+static constexpr uint8_t upperNonRecoverableThreshold = 42;
 static constexpr uint8_t upperCriticalThreshold = 43;
+static constexpr uint8_t upperNonCriticalThreshold = 44;
+static constexpr uint8_t lowerNonRecoverableThreshold = 45;
 static constexpr uint8_t lowerCriticalThreshold = 46;
+static constexpr uint8_t lowerNonCriticalThreshold = 47;
+static constexpr uint8_t positiveHysteresis = 48;
+static constexpr uint8_t negativeHysteresis = 49;
 static constexpr uint8_t nameLengthByte = 53;
 
 } // namespace sdrtype01
 
+namespace sdrtype02
+{
+// SDR Type 2 Compact Sensor Record
+static constexpr uint8_t sensorTypeByte = 14;
+static constexpr uint8_t readingTypeByte = 15;
+static constexpr uint8_t readingMaskLSB = 22;
+static constexpr uint8_t readingMaskMSB = 23;
+static constexpr uint8_t recordSharing = 27;
+static constexpr uint8_t recordSharingOffset = 28;
+static constexpr uint8_t nameLengthByte = 35;
+
+} // namespace sdrtype02
+
+namespace sdrtype03
+{
+// SDR Type 3 Event-Only Record
+static constexpr uint8_t sensorTypeByte = 12;
+static constexpr uint8_t readingTypeByte = 13;
+static constexpr uint8_t recordSharing = 14;
+static constexpr uint8_t recordSharingOffset = 15;
+static constexpr uint8_t nameLengthByte = 20;
+
+} // namespace sdrtype03
+
 struct SensorInfo
 {
     std::string sensorReadName;
@@ -65,16 +160,47 @@ struct SensorInfo
     double thresLowerCri = 0;
     uint8_t sensorNumber = 0;
     uint8_t sensCap = 0;
+    SDRType recordType = SDRType::sdrType01;
+    uint8_t sensorType = 0;
+    uint8_t readingType = 0;
+    // The sdrtype01::threshold* bits of the thresholds that can be read
+    uint8_t readableThresholds = 0;
+    double thresUpperNonRec = 0;
+    double thresUpperNonCri = 0;
+    double thresLowerNonCri = 0;
+    double thresLowerNonRec = 0;
+    double hysteresisPositive = 0;
+    double hysteresisNegative = 0;
+    // The states a discrete sensor can read
+    uint16_t readingMask = 0;
+
+    // Only full records of threshold sensors have analog readings
+    bool isDiscrete() const
+    {
+        return recordType != SDRType::sdrType01 ||
+               readingType != sdr::readingTypeThreshold;
+    }
 };
 
 struct SensorValConversion
 {
-    uint16_t mValue = 0;
+    int16_t mValue = 0;
     double bValue = 0;
     double expoVal = 0;
     uint8_t negRead = 0;
 };
 
+// The record count and timestamps of the repository, which change along with
+// its records
+struct SDRRepositoryInfo
+{
+    uint16_t recordCount = 0;
+    uint32_t additionTimestamp = 0;
+    uint32_t eraseTimestamp = 0;
+
+    bool operator==(const SDRRepositoryInfo&) const = default;
+};
+
 inline std::map<int, std::vector<SensorInfo>> sensorRecord;
 inline std::map<int, std::map<uint8_t, SensorValConversion>> sensorValRecord;
 
@@ -82,13 +208,18 @@ class IpmbSDRDevice : public std::enable_shared_from_this<IpmbSDRDevice>
 {
   public:
     IpmbSDRDevice(std::shared_ptr<sdbusplus::asio::connection>& dbusConnection,
-                  uint8_t cmdAddr);
+                  uint8_t cmdAddr, const std::string& configPath = "");
 
     uint8_t commandAddress = 0;
     int hostIndex = 0;
+    std::string configPath;
 
     std::shared_ptr<sdbusplus::asio::connection> conn;
 
+    // Called once the records of the repository have all been read, and again
+    // each time they are read after the repository changed
+    std::function<void(const IpmbSDRDevice&)> onUpdate;
+
     std::vector<uint8_t> sdrData;
     uint16_t validRecordCount = 1;
     uint8_t iCnt = 0;
@@ -97,8 +228,12 @@ class IpmbSDRDevice : public std::enable_shared_from_this<IpmbSDRDevice>
 
     std::vector<uint8_t> sdrCommandData;
 
+    // Reads the repository info, walks the records if they changed since
+    // the last complete walk, and checks again after sdr::refreshInterval
     void getSDRRepositoryInfo();
 
+    void startWalk(const SDRRepositoryInfo& info);
+
     void reserveSDRRepository(uint16_t recordCount);
 
     void getSDRSensorData(uint16_t recordCount, uint8_t resrvIDLSB,
@@ -108,11 +243,48 @@ class IpmbSDRDevice : public std::enable_shared_from_this<IpmbSDRDevice>
                        uint8_t resrvIDLSB, uint8_t resrvIDMSB);
 
     void checkSDRData(std::vector<uint8_t>& sdrDataBytes,
-                      uint8_t dataLength) const;
+                      size_t dataLength) const;
 
     static void checkSDRType01Threshold(std::vector<uint8_t>& sdrDataBytes,
                                         int busIndex, std::string tempName);
 
-    inline static double sensorValCalculation(uint16_t mValue, double bValue,
+    static void checkSDRType02(std::vector<uint8_t>& sdrDataBytes,
+                               int busIndex, std::string tempName);
+
+    static void checkSDRType03(std::vector<uint8_t>& sdrDataBytes,
+                               int busIndex, std::string tempName);
+
+    // The ID string of the record, whose type/length byte is at lengthByte
+    static std::string recordName(const std::vector<uint8_t>& sdrDataBytes,
+                                  size_t lengthByte);
+
+    static std::optional<SDRRepositoryInfo>
+        parseRepositoryInfo(const std::vector<uint8_t>& data);
+
+    // Converts a raw reading or threshold as given by the analog data format
+    static double sensorReading(const SensorValConversion& conversion,
+                                uint8_t raw);
+
+    // The lowest and highest value the sensor can read
+    static std::pair<double, double>
+        readingRange(const SensorValConversion& conversion);
+
+    // The IpmbSensor type name for the units of the record, if it has an
+    // analog reading in units a D-Bus sensor can have
+    static std::optional<std::string>
+        sensorTypeName(const SensorInfo& info,
+                       const SensorValConversion& conversion);
+
+    inline static double sensorValCalculation(int16_t mValue, double bValue,
                                               double expValue, double value);
+
+  private:
+    // Responses to the requests of an abandoned walk are dropped
+    uint32_t walk = 0;
+    std::optional<SDRRepositoryInfo> walkInfo;
+    std::optional<SDRRepositoryInfo> walkedInfo;
+    boost::asio::steady_timer refreshTimer;
+
+    void scheduleRefresh();
+    void completeWalk();
 };
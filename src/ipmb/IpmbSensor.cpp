@@ -16,6 +16,8 @@
 
 #include "IpmbSensor.hpp"
 
+#include "IpmbController.hpp"
+#include "IpmbDiscreteSensor.hpp"
 #include "IpmbSDRSensor.hpp"
 #include "SensorPaths.hpp"
 #include "Thresholds.hpp"
@@ -39,7 +41,9 @@
 #include <cstdint>
 #include <iostream>
 #include <limits>
+#include <map>
 #include <memory>
+#include <optional>
 #include <stdexcept>
 #include <string>
 #include <tuple>
@@ -66,10 +70,11 @@ IpmbSensor::IpmbSensor(
     const std::string& sensorConfiguration,
     sdbusplus::asio::object_server& objectServer,
     std::vector<thresholds::Threshold>&& thresholdData, uint8_t deviceAddress,
-    uint8_t hostSMbusIndex, const float pollRate, std::string& sensorTypeName) :
+    uint8_t hostSMbusIndex, const float pollRate, std::string& sensorTypeName,
+    std::pair<double, double> readingRange) :
     Sensor(escapeName(sensorName), std::move(thresholdData),
-           sensorConfiguration, "IpmbSensor", false, false, ipmbMaxReading,
-           ipmbMinReading, conn, PowerState::on),
+           sensorConfiguration, "IpmbSensor", false, false, readingRange.second,
+           readingRange.first, conn, PowerState::on),
     deviceAddress(deviceAddress), hostSMbusIndex(hostSMbusIndex),
     sensorPollMs(static_cast<int>(pollRate * 1000)), objectServer(objectServer),
     waitTimer(io)
@@ -122,6 +127,16 @@ void IpmbSensor::init()
 {
     loadDefaults();
     setInitialProperties(getSubTypeUnits());
+    controller = getIpmbController(objectServer, ipmbBusIndex, commandAddress);
+    controller->addListener([weakRef{weak_from_this()}](bool reachable) {
+        std::shared_ptr<IpmbSensor> self = weakRef.lock();
+        if (!self)
+        {
+            return false;
+        }
+        self->controllerReachable(reachable);
+        return true;
+    });
     if (initCommand)
     {
         runInitCmd();
@@ -148,60 +163,85 @@ static void initCmdCb(const std::weak_ptr<IpmbSensor>& weakRef,
 
 void IpmbSensor::runInitCmd()
 {
-    if (!initCommand)
+    // Nothing to send before init() found the controller
+    if (!initCommand || !controller)
     {
         return;
     }
-    dbusConnection->async_method_call(
+    sendControllerRequest(
+        dbusConnection, controller, timing, commandAddress, netfn, lun,
+        *initCommand, initData,
         [weakRef{weak_from_this()}](const boost::system::error_code& ec,
                                     const IpmbMethodType& response) {
             initCmdCb(weakRef, ec, response);
-        },
-        "xyz.openbmc_project.Ipmi.Channel.Ipmb",
-        "/xyz/openbmc_project/Ipmi/Channel/Ipmb", "org.openbmc.Ipmb",
-        "sendRequest", commandAddress, netfn, lun, *initCommand, initData);
+        });
 }
 
-void IpmbSensor::loadDefaults()
+void IpmbRequestConfig::update(const IpmbRequestConfig& other)
 {
+    auto replace = [](auto& field, const auto& otherField) {
+        if (otherField)
+        {
+            field = otherField;
+        }
+    };
+    replace(commandAddress, other.commandAddress);
+    replace(netfn, other.netfn);
+    replace(command, other.command);
+    replace(commandData, other.commandData);
+    replace(initCommand, other.initCommand);
+    replace(initData, other.initData);
+    replace(readingFormat, other.readingFormat);
+    replace(minValue, other.minValue);
+    replace(maxValue, other.maxValue);
+}
+
+IpmbRequestConfig IpmbSensor::presetRequest(IpmbType type, IpmbSubType subType,
+                                            uint8_t deviceAddress,
+                                            uint8_t hostSMbusIndex)
+{
+    IpmbRequestConfig preset;
     if (type == IpmbType::meSensor)
     {
-        commandAddress = meAddress;
-        netfn = ipmi::sensor::netFn;
-        command = ipmi::sensor::getSensorReading;
-        commandData = {deviceAddress};
-        readingFormat = ReadingFormat::byte0;
+        preset.commandAddress = meAddress;
+        preset.netfn = ipmi::sensor::netFn;
+        preset.command = ipmi::sensor::getSensorReading;
+        preset.commandData = std::vector<uint8_t>{deviceAddress};
+        preset.readingFormat = ReadingFormat::byte0;
     }
     else if (type == IpmbType::PXE1410CVR)
     {
-        commandAddress = meAddress;
-        netfn = ipmi::me_bridge::netFn;
-        command = ipmi::me_bridge::sendRawPmbus;
-        initCommand = ipmi::me_bridge::sendRawPmbus;
+        preset.commandAddress = meAddress;
+        preset.netfn = ipmi::me_bridge::netFn;
+        preset.command = ipmi::me_bridge::sendRawPmbus;
+        preset.initCommand = ipmi::me_bridge::sendRawPmbus;
         // pmbus read temp
-        commandData = {0x57,          0x01, 0x00, 0x16, hostSMbusIndex,
-                       deviceAddress, 0x00, 0x00, 0x00, 0x00,
-                       0x01,          0x02, 0x8d};
+        preset.commandData = std::vector<uint8_t>{
+            0x57,          0x01, 0x00, 0x16, hostSMbusIndex,
+            deviceAddress, 0x00, 0x00, 0x00, 0x00,
+            0x01,          0x02, 0x8d};
         // goto page 0
-        initData = {0x57,          0x01, 0x00, 0x14, hostSMbusIndex,
-                    deviceAddress, 0x00, 0x00, 0x00, 0x00,
-                    0x02,          0x00, 0x00, 0x00};
-        readingFormat = ReadingFormat::linearElevenBit;
+        preset.initData = std::vector<uint8_t>{
+            0x57,          0x01, 0x00, 0x14, hostSMbusIndex,
+            deviceAddress, 0x00, 0x00, 0x00, 0x00,
+            0x02,          0x00, 0x00, 0x00};
+        preset.readingFormat = ReadingFormat::linearElevenBit;
     }
     else if (type == IpmbType::IR38363VR)
     {
-        commandAddress = meAddress;
-        netfn = ipmi::me_bridge::netFn;
-        command = ipmi::me_bridge::sendRawPmbus;
+        preset.commandAddress = meAddress;
+        preset.netfn = ipmi::me_bridge::netFn;
+        preset.command = ipmi::me_bridge::sendRawPmbus;
         // pmbus read temp
-        commandData = {0x57,          0x01, 0x00, 0x16, hostSMbusIndex,
-                       deviceAddress, 00,   0x00, 0x00, 0x00,
-                       0x01,          0x02, 0x8D};
-        readingFormat = ReadingFormat::elevenBitShift;
+        preset.commandData = std::vector<uint8_t>{
+            0x57,          0x01, 0x00, 0x16, hostSMbusIndex,
+            deviceAddress, 00,   0x00, 0x00, 0x00,
+            0x01,          0x02, 0x8D};
+        preset.readingFormat = ReadingFormat::elevenBitShift;
     }
     else if (type == IpmbType::ADM1278HSC)
     {
-        commandAddress = meAddress;
+        preset.commandAddress = meAddress;
         uint8_t snsNum = 0;
         switch (subType)
         {
@@ -215,18 +255,19 @@ void IpmbSensor::loadDefaults()
                 {
                     snsNum = 0x8c;
                 }
-                netfn = ipmi::me_bridge::netFn;
-                command = ipmi::me_bridge::sendRawPmbus;
-                commandData = {0x57, 0x01, 0x00, 0x86, deviceAddress,
-                               0x00, 0x00, 0x01, 0x02, snsNum};
-                readingFormat = ReadingFormat::elevenBit;
+                preset.netfn = ipmi::me_bridge::netFn;
+                preset.command = ipmi::me_bridge::sendRawPmbus;
+                preset.commandData = std::vector<uint8_t>{
+                    0x57, 0x01, 0x00, 0x86, deviceAddress,
+                    0x00, 0x00, 0x01, 0x02, snsNum};
+                preset.readingFormat = ReadingFormat::elevenBit;
                 break;
             case IpmbSubType::power:
             case IpmbSubType::volt:
-                netfn = ipmi::sensor::netFn;
-                command = ipmi::sensor::getSensorReading;
-                commandData = {deviceAddress};
-                readingFormat = ReadingFormat::byte0;
+                preset.netfn = ipmi::sensor::netFn;
+                preset.command = ipmi::sensor::getSensorReading;
+                preset.commandData = std::vector<uint8_t>{deviceAddress};
+                preset.readingFormat = ReadingFormat::byte0;
                 break;
             default:
                 throw std::runtime_error("Invalid sensor type");
@@ -234,19 +275,21 @@ void IpmbSensor::loadDefaults()
     }
     else if (type == IpmbType::mpsVR)
     {
-        commandAddress = meAddress;
-        netfn = ipmi::me_bridge::netFn;
-        command = ipmi::me_bridge::sendRawPmbus;
-        initCommand = ipmi::me_bridge::sendRawPmbus;
+        preset.commandAddress = meAddress;
+        preset.netfn = ipmi::me_bridge::netFn;
+        preset.command = ipmi::me_bridge::sendRawPmbus;
+        preset.initCommand = ipmi::me_bridge::sendRawPmbus;
         // pmbus read temp
-        commandData = {0x57,          0x01, 0x00, 0x16, hostSMbusIndex,
-                       deviceAddress, 0x00, 0x00, 0x00, 0x00,
-                       0x01,          0x02, 0x8d};
+        preset.commandData = std::vector<uint8_t>{
+            0x57,          0x01, 0x00, 0x16, hostSMbusIndex,
+            deviceAddress, 0x00, 0x00, 0x00, 0x00,
+            0x01,          0x02, 0x8d};
         // goto page 0
-        initData = {0x57,          0x01, 0x00, 0x14, hostSMbusIndex,
-                    deviceAddress, 0x00, 0x00, 0x00, 0x00,
-                    0x02,          0x00, 0x00, 0x00};
-        readingFormat = ReadingFormat::byte3;
+        preset.initData = std::vector<uint8_t>{
+            0x57,          0x01, 0x00, 0x14, hostSMbusIndex,
+            deviceAddress, 0x00, 0x00, 0x00, 0x00,
+            0x02,          0x00, 0x00, 0x00};
+        preset.readingFormat = ReadingFormat::byte3;
     }
     else if (type == IpmbType::SMPro)
     {
@@ -255,26 +298,40 @@ void IpmbSensor::loadDefaults()
         // See the Ampere Family SoC BMC Interface Specification at
         // https://amperecomputing.com/customer-connect/products/altra-family-software---firmware
         // for details of the sensors.
-        commandAddress = 0;
-        netfn = 0x30;
-        command = 0x31;
-        commandData = {0x9e, deviceAddress};
+        preset.commandAddress = 0;
+        preset.netfn = 0x30;
+        preset.command = 0x31;
+        preset.commandData = std::vector<uint8_t>{0x9e, deviceAddress};
         switch (subType)
         {
             case IpmbSubType::temp:
-                readingFormat = ReadingFormat::nineBit;
+                preset.readingFormat = ReadingFormat::nineBit;
                 break;
             case IpmbSubType::power:
-                readingFormat = ReadingFormat::tenBit;
+                preset.readingFormat = ReadingFormat::tenBit;
                 break;
             case IpmbSubType::curr:
             case IpmbSubType::volt:
-                readingFormat = ReadingFormat::fifteenBit;
+                preset.readingFormat = ReadingFormat::fifteenBit;
                 break;
             default:
                 throw std::runtime_error("Invalid sensor type");
         }
     }
+    else if (type == IpmbType::sdrSensor)
+    {
+        // commandAddress is that of the IpmbDevice the record came from
+        preset.netfn = ipmi::sensor::netFn;
+        preset.command = ipmi::sensor::getSensorReading;
+        preset.commandData = std::vector<uint8_t>{deviceAddress};
+        preset.readingFormat = ReadingFormat::byte0;
+    }
+    else if (type == IpmbType::custom)
+    {
+        // Everything else comes from the configuration
+        preset.commandAddress = meAddress;
+        preset.readingFormat = ReadingFormat::byte0;
+    }
     else
     {
         throw std::runtime_error("Invalid sensor type");
@@ -283,8 +340,40 @@ void IpmbSensor::loadDefaults()
     if (subType == IpmbSubType::util)
     {
         // Utilization need to be scaled to percent
-        maxValue = 100;
-        minValue = 0;
+        preset.maxValue = 100;
+        preset.minValue = 0;
+    }
+    return preset;
+}
+
+void IpmbSensor::loadDefaults()
+{
+    IpmbRequestConfig request =
+        presetRequest(type, subType, deviceAddress, hostSMbusIndex);
+    request.update(requestConfig);
+    if (!request.netfn || !request.command)
+    {
+        throw std::runtime_error("No command to read " + name);
+    }
+
+    if (request.commandAddress)
+    {
+        commandAddress = *request.commandAddress;
+    }
+    netfn = *request.netfn;
+    command = *request.command;
+    commandData = request.commandData.value_or(std::vector<uint8_t>{});
+    initCommand = request.initCommand;
+    initData = request.initData.value_or(std::vector<uint8_t>{});
+    readingFormat = request.readingFormat.value_or(ReadingFormat::byte0);
+
+    if (request.minValue)
+    {
+        minValue = *request.minValue;
+    }
+    if (request.maxValue)
+    {
+        maxValue = *request.maxValue;
     }
 }
 
@@ -425,7 +514,11 @@ void IpmbSensor::ipmbRequestCompletionCb(const boost::system::error_code& ec,
     const int& status = std::get<0>(response);
     if (ec || (status != 0))
     {
-        incrementError();
+        // The sensors of an unreachable controller are already unavailable
+        if (controller->reachable())
+        {
+            incrementError();
+        }
         read();
         return;
     }
@@ -455,6 +548,12 @@ void IpmbSensor::ipmbRequestCompletionCb(const boost::system::error_code& ec,
         return;
     }
 
+    if (sdrConversion)
+    {
+        value = IpmbSDRDevice::sensorReading(*sdrConversion,
+                                             static_cast<uint8_t>(value));
+    }
+
     // rawValue only used in debug logging
     // up to 5th byte in data are used to derive value
     size_t end = std::min(sizeof(uint64_t), data.size());
@@ -498,8 +597,16 @@ void IpmbSensor::sendIpmbRequest()
         read();
         return;
     }
-    dbusConnection->async_method_call(
-        [weakRef{weak_from_this()}](boost::system::error_code ec,
+    if (!controller->requestAllowed())
+    {
+        // Another sensor is probing the unreachable controller
+        read();
+        return;
+    }
+    sendControllerRequest(
+        dbusConnection, controller, timing, commandAddress, netfn, lun,
+        command, commandData,
+        [weakRef{weak_from_this()}](const boost::system::error_code& ec,
                                     const IpmbMethodType& response) {
             std::shared_ptr<IpmbSensor> self = weakRef.lock();
             if (!self)
@@ -507,10 +614,17 @@ void IpmbSensor::sendIpmbRequest()
                 return;
             }
             self->ipmbRequestCompletionCb(ec, response);
-        },
-        "xyz.openbmc_project.Ipmi.Channel.Ipmb",
-        "/xyz/openbmc_project/Ipmi/Channel/Ipmb", "org.openbmc.Ipmb",
-        "sendRequest", commandAddress, netfn, lun, command, commandData);
+        });
+}
+
+void IpmbSensor::controllerReachable(bool reachable)
+{
+    // Reachable again, the sensor is available with its next reading
+    if (!reachable)
+    {
+        updateValue(std::numeric_limits<double>::quiet_NaN());
+        markAvailable(false);
+    }
 }
 
 bool IpmbSensor::sensorClassType(const std::string& sensorClass)
@@ -539,6 +653,10 @@ bool IpmbSensor::sensorClassType(const std::string& sensorClass)
     {
         type = IpmbType::SMPro;
     }
+    else if (sensorClass == "Custom")
+    {
+        type = IpmbType::custom;
+    }
     else
     {
         std::cerr << "Invalid class " << sensorClass << "\n";
@@ -586,6 +704,125 @@ void IpmbSensor::parseConfigValues(const SensorBaseConfigMap& entry)
     }
 
     readState = getPowerState(entry);
+    timing = requestTimingFromConfig(entry);
+}
+
+std::optional<ReadingFormat>
+    IpmbSensor::readingFormatFromName(const std::string& formatName)
+{
+    static const std::array<std::pair<const char*, ReadingFormat>, 8>
+        readingFormats = {{
+            {"Byte0", ReadingFormat::byte0},
+            {"Byte3", ReadingFormat::byte3},
+            {"NineBit", ReadingFormat::nineBit},
+            {"TenBit", ReadingFormat::tenBit},
+            {"ElevenBit", ReadingFormat::elevenBit},
+            {"ElevenBitShift", ReadingFormat::elevenBitShift},
+            {"LinearElevenBit", ReadingFormat::linearElevenBit},
+            {"FifteenBit", ReadingFormat::fifteenBit},
+        }};
+    for (const auto& [formatString, format] : readingFormats)
+    {
+        if (formatName == formatString)
+        {
+            return format;
+        }
+    }
+    return std::nullopt;
+}
+
+static std::optional<uint8_t> configByte(const SensorBaseConfigMap& entry,
+                                         const std::string& key)
+{
+    auto find = entry.find(key);
+    if (find == entry.end())
+    {
+        return std::nullopt;
+    }
+    unsigned int value =
+        std::visit(VariantToUnsignedIntVisitor(), find->second);
+    if (value > std::numeric_limits<uint8_t>::max())
+    {
+        throw std::invalid_argument(key + " must be a byte");
+    }
+    return static_cast<uint8_t>(value);
+}
+
+// Byte lists are given as lists of strings, such as "0x57", since those are
+// the only lists in the configuration
+static std::optional<std::vector<uint8_t>>
+    configBytes(const SensorBaseConfigMap& entry, const std::string& key)
+{
+    auto find = entry.find(key);
+    if (find == entry.end())
+    {
+        return std::nullopt;
+    }
+    const auto* strings = std::get_if<std::vector<std::string>>(&find->second);
+    if (strings == nullptr)
+    {
+        throw std::invalid_argument(key + " must be a list of bytes");
+    }
+
+    std::vector<uint8_t> bytes;
+    for (const std::string& str : *strings)
+    {
+        size_t end = 0;
+        unsigned long value = 0;
+        try
+        {
+            value = std::stoul(str, &end, 0);
+        }
+        catch (const std::logic_error&)
+        {
+            end = 0;
+        }
+        if (end == 0 || end != str.size() ||
+            value > std::numeric_limits<uint8_t>::max())
+        {
+            throw std::invalid_argument(key + " has an invalid byte " + str);
+        }
+        bytes.push_back(static_cast<uint8_t>(value));
+    }
+    return bytes;
+}
+
+IpmbRequestConfig IpmbSensor::parseRequestConfig(
+    const SensorBaseConfigMap& entry)
+{
+    IpmbRequestConfig request;
+    request.commandAddress = configByte(entry, "CommandAddress");
+    request.netfn = configByte(entry, "NetFn");
+    request.command = configByte(entry, "Command");
+    request.commandData = configBytes(entry, "CommandData");
+    request.initCommand = configByte(entry, "InitCommand");
+    request.initData = configBytes(entry, "InitData");
+
+    auto findFormat = entry.find("ReadingFormat");
+    if (findFormat != entry.end())
+    {
+        std::string formatName =
+            std::visit(VariantToStringVisitor(), findFormat->second);
+        request.readingFormat = readingFormatFromName(formatName);
+        if (!request.readingFormat)
+        {
+            throw std::invalid_argument("Invalid ReadingFormat " + formatName);
+        }
+    }
+
+    auto findMin = entry.find("MinValue");
+    if (findMin != entry.end())
+    {
+        request.minValue =
+            std::visit(VariantToDoubleVisitor(), findMin->second);
+    }
+    auto findMax = entry.find("MaxValue");
+    if (findMax != entry.end())
+    {
+        request.maxValue =
+            std::visit(VariantToDoubleVisitor(), findMax->second);
+    }
+    return request;
 }
 
 void createSensors(
@@ -626,8 +863,34 @@ void createSensors(
                     uint8_t deviceAddress =
                         loadVariant<uint8_t>(cfg, "Address");
 
-                    std::string sensorClass =
-                        loadVariant<std::string>(cfg, "Class");
+                    // Without a Class the sensor is described by its
+                    // configuration alone
+                    std::string sensorClass = "Custom";
+                    auto findClass = cfg.find("Class");
+                    if (findClass != cfg.end())
+                    {
+                        sensorClass = std::visit(VariantToStringVisitor(),
+                                                 findClass->second);
+                    }
+
+                    IpmbRequestConfig requestConfig;
+                    try
+                    {
+                        requestConfig = IpmbSensor::parseRequestConfig(cfg);
+                    }
+                    catch (const std::invalid_argument& e)
+                    {
+                        std::cerr << "Invalid configuration for " << name
+                                  << ": " << e.what() << "\n";
+                        continue;
+                    }
+                    if (sensorClass == "Custom" &&
+                        (!requestConfig.netfn || !requestConfig.command))
+                    {
+                        std::cerr << name
+                                  << " needs a Class, or a NetFn and Command\n";
+                        continue;
+                    }
 
                     uint8_t hostSMbusIndex = hostSMbusIndexDefault;
                     auto findSmType = cfg.find("HostSMbusIndex");
@@ -663,15 +926,19 @@ void createSensors(
                     sensor = std::make_shared<IpmbSensor>(
                         dbusConnection, io, name, path, objectServer,
                         std::move(sensorThresholds), deviceAddress,
-                        hostSMbusIndex, pollRate, sensorTypeName);
+                        hostSMbusIndex, pollRate, sensorTypeName,
+                        std::make_pair(ipmbMinReading, ipmbMaxReading));
 
                     sensor->parseConfigValues(cfg);
+                    sensor->ipmbBusIndex = ipmbBusIndex;
+                    sensor->requestConfig = std::move(requestConfig);
                     if (!(sensor->sensorClassType(sensorClass)))
                     {
                         continue;
                     }
                     sensor->sensorSubType(sensorTypeName);
                     sensor->init();
+                    sensor->loadConfigurationRecords(interfaces);
                 }
             }
         },
@@ -679,10 +946,168 @@ void createSensors(
         "org.freedesktop.DBus.ObjectManager", "GetManagedObjects");
 }
 
+std::vector<thresholds::Threshold> sdrThresholds(const SensorInfo& info)
+{
+    struct SDRThreshold
+    {
+        uint8_t readable;
+        thresholds::Level level;
+        thresholds::Direction direction;
+        double value;
+    };
+    const std::array<SDRThreshold, 6> sdrThresholdList = {{
+        {sdrtype01::thresholdUpperNonCritical, thresholds::Level::WARNING,
+         thresholds::Direction::HIGH, info.thresUpperNonCri},
+        {sdrtype01::thresholdUpperCritical, thresholds::Level::CRITICAL,
+         thresholds::Direction::HIGH, info.thresUpperCri},
+        {sdrtype01::thresholdUpperNonRecoverable,
+         thresholds::Level::HARDSHUTDOWN, thresholds::Direction::HIGH,
+         info.thresUpperNonRec},
+        {sdrtype01::thresholdLowerNonCritical, thresholds::Level::WARNING,
+         thresholds::Direction::LOW, info.thresLowerNonCri},
+        {sdrtype01::thresholdLowerCritical, thresholds::Level::CRITICAL,
+         thresholds::Direction::LOW, info.thresLowerCri},
+        {sdrtype01::thresholdLowerNonRecoverable,
+         thresholds::Level::HARDSHUTDOWN, thresholds::Direction::LOW,
+         info.thresLowerNonRec},
+    }};
+
+    std::vector<thresholds::Threshold> sensorThresholds;
+    for (const SDRThreshold& threshold : sdrThresholdList)
+    {
+        if ((info.readableThresholds & threshold.readable) == 0)
+        {
+            continue;
+        }
+        // Upper thresholds deassert going down by the positive-going
+        // hysteresis, lower ones going up by the negative-going hysteresis.
+        // They belong to the remote controller, so they can't be changed here.
+        double hysteresis = threshold.direction == thresholds::Direction::HIGH
+                                ? info.hysteresisPositive
+                                : info.hysteresisNegative;
+        sensorThresholds.emplace_back(threshold.level, threshold.direction,
+                                      threshold.value, hysteresis, false);
+    }
+    return sensorThresholds;
+}
+
+void createSDRSensors(
+    boost::asio::io_context& io, sdbusplus::asio::object_server& objectServer,
+    boost::container::flat_map<std::string, std::shared_ptr<IpmbSensor>>&
+        sensors,
+    boost::container::flat_map<std::string,
+                               std::shared_ptr<IpmbDiscreteSensor>>&
+        discreteSensors,
+    std::shared_ptr<sdbusplus::asio::connection>& dbusConnection,
+    const IpmbSDRDevice& device, const SensorBaseConfigMap& cfg)
+{
+    // The sensors are destroyed before they are created again, so that their
+    // objects can be added back at the same paths
+    auto sensorIt = sensors.begin();
+    while (sensorIt != sensors.end())
+    {
+        if ((sensorIt->second->type == IpmbType::sdrSensor) &&
+            (sensorIt->second->configurationPath == device.configPath))
+        {
+            sensorIt = sensors.erase(sensorIt);
+        }
+        else
+        {
+            sensorIt++;
+        }
+    }
+    auto discreteIt = discreteSensors.begin();
+    while (discreteIt != discreteSensors.end())
+    {
+        if (discreteIt->second->configurationPath == device.configPath)
+        {
+            discreteIt = discreteSensors.erase(discreteIt);
+        }
+        else
+        {
+            discreteIt++;
+        }
+    }
+
+    std::string prefix;
+    auto findPrefix = cfg.find("SensorPrefix");
+    if (findPrefix != cfg.end())
+    {
+        prefix = std::visit(VariantToStringVisitor(), findPrefix->second) +
+                 " ";
+    }
+
+    float pollRate = getPollRate(cfg, pollRateDefault);
+
+    int busIndex = device.hostIndex - 1;
+    for (const SensorInfo& info : sensorRecord[busIndex])
+    {
+        std::string name = prefix + info.sensorReadName;
+        if (sensors.contains(name) || discreteSensors.contains(name))
+        {
+            std::cerr << "SDR sensor " << name
+                      << " duplicates an existing sensor\n";
+            continue;
+        }
+
+        if (info.isDiscrete())
+        {
+            auto sensor = std::make_shared<IpmbDiscreteSensor>(
+                dbusConnection, io, name, device.configPath, objectServer,
+                info, device.commandAddress, pollRate, getPowerState(cfg));
+            sensor->ipmbBusIndex = static_cast<uint8_t>(busIndex);
+            sensor->timing = requestTimingFromConfig(cfg);
+            sensor->init();
+            discreteSensors[name] = std::move(sensor);
+            continue;
+        }
+
+        const std::map<uint8_t, SensorValConversion>& conversions =
+            sensorValRecord[busIndex];
+        auto findConversion = conversions.find(info.sensorNumber);
+        if (findConversion == conversions.end())
+        {
+            continue;
+        }
+        const SensorValConversion& conversion = findConversion->second;
+
+        std::optional<std::string> sensorTypeName =
+            IpmbSDRDevice::sensorTypeName(info, conversion);
+        if (!sensorTypeName)
+        {
+            if constexpr (debug)
+            {
+                std::cerr << "Skipping SDR sensor " << name
+                          << " with unsupported units\n";
+            }
+            continue;
+        }
+
+        auto sensor = std::make_shared<IpmbSensor>(
+            dbusConnection, io, name, device.configPath, objectServer,
+            sdrThresholds(info), info.sensorNumber, hostSMbusIndexDefault,
+            pollRate, *sensorTypeName, IpmbSDRDevice::readingRange(conversion));
+
+        sensor->parseConfigValues(cfg);
+        sensor->type = IpmbType::sdrSensor;
+        sensor->commandAddress = device.commandAddress;
+        sensor->ipmbBusIndex = static_cast<uint8_t>(busIndex);
+        sensor->sdrConversion = conversion;
+        sensor->sensorSubType(*sensorTypeName);
+        sensor->init();
+        sensors[name] = std::move(sensor);
+    }
+}
+
 void interfaceRemoved(
     sdbusplus::message_t& message,
     boost::container::flat_map<std::string, std::shared_ptr<IpmbSensor>>&
-        sensors)
+        sensors,
+    boost::container::flat_map<std::string,
+                               std::shared_ptr<IpmbDiscreteSensor>>&
+        discreteSensors,
+    boost::container::flat_map<uint8_t, std::shared_ptr<IpmbSDRDevice>>&
+        sdrDevices)
 {
     if (message.is_method_error())
     {
@@ -701,8 +1126,11 @@ void interfaceRemoved(
     while (sensorIt != sensors.end())
     {
         if ((sensorIt->second->configurationPath == removedPath) &&
-            (std::find(interfaces.begin(), interfaces.end(),
-                       configInterfaceName(sdrInterface)) != interfaces.end()))
+            ((std::find(interfaces.begin(), interfaces.end(),
+                        configInterfaceName(sdrInterface)) !=
+              interfaces.end()) ||
+             (std::find(interfaces.begin(), interfaces.end(),
+                        configInterfaceName(sensorType)) != interfaces.end())))
         {
             sensorIt = sensors.erase(sensorIt);
         }
@@ -711,4 +1139,35 @@ void interfaceRemoved(
             sensorIt++;
         }
     }
+
+    // Stop the devices from creating their sensors again
+    if (std::find(interfaces.begin(), interfaces.end(),
+                  configInterfaceName(sdrInterface)) == interfaces.end())
+    {
+        return;
+    }
+    auto discreteIt = discreteSensors.begin();
+    while (discreteIt != discreteSensors.end())
+    {
+        if (discreteIt->second->configurationPath == removedPath.str)
+        {
+            discreteIt = discreteSensors.erase(discreteIt);
+        }
+        else
+        {
+            discreteIt++;
+        }
+    }
+    auto deviceIt = sdrDevices.begin();
+    while (deviceIt != sdrDevices.end())
+    {
+        if (deviceIt->second->configPath == removedPath.str)
+        {
+            deviceIt = sdrDevices.erase(deviceIt);
+        }
+        else
+        {
+            deviceIt++;
+        }
+    }
 }
@@ -1,4 +1,8 @@
 #pragma once
+#include "IpmbController.hpp"
+#include "IpmbDiscreteSensor.hpp"
+#include "IpmbSDRSensor.hpp"
+
 #include <boost/asio/steady_timer.hpp>
 #include <boost/container/flat_map.hpp>
 #include <sensor.hpp>
@@ -8,6 +12,7 @@
 #include <memory>
 #include <optional>
 #include <string>
+#include <utility>
 #include <vector>
 
 constexpr const char* sensorType = "IpmbSensor";
@@ -21,7 +26,9 @@ enum class IpmbType
     IR38363VR,
     ADM1278HSC,
     mpsVR,
-    SMPro
+    SMPro,
+    sdrSensor,
+    custom
 };
 
 enum class IpmbSubType
@@ -74,6 +81,92 @@ static inline bool isValid(const std::vector<uint8_t>& data)
     return true;
 }
 
+// The state bits of a discrete sensor's 'Get Sensor Reading' response, of
+// which the second byte is optional
+static inline std::optional<uint16_t>
+    discreteState(const std::vector<uint8_t>& data)
+{
+    if (!isValid(data))
+    {
+        return std::nullopt;
+    }
+
+    uint16_t state = data[2];
+    if (data.size() > 3)
+    {
+        state |= (data[3] & 0x7f) << 8;
+    }
+    return state;
+}
+
+// What the state bits of a discrete sensor say about the device it watches
+struct DiscreteStatus
+{
+    bool present = true;
+    bool functional = true;
+};
+
+// Maps the offsets that mean a device is missing or failed, by the generic
+// event/reading type of the sensor or, for sensor specific ones, its type.
+// Offsets that don't bear on either leave the device present and functional.
+static inline DiscreteStatus discreteStatus(uint8_t sensorType,
+                                            uint8_t readingType, uint16_t state)
+{
+    constexpr uint8_t predictiveFailure = 0x04;
+    constexpr uint8_t limit = 0x05;
+    constexpr uint8_t severity = 0x07;
+    constexpr uint8_t presence = 0x08;
+    constexpr uint8_t enabled = 0x09;
+    constexpr uint8_t sensorSpecific = 0x6f;
+
+    constexpr uint8_t processor = 0x07;
+    constexpr uint8_t powerSupply = 0x08;
+    constexpr uint8_t driveSlot = 0x0d;
+
+    auto asserted = [state](uint16_t mask) { return (state & mask) != 0; };
+
+    DiscreteStatus status;
+    switch (readingType)
+    {
+        case predictiveFailure:
+        case limit:
+            status.functional = !asserted(0x0002);
+            break;
+        case severity:
+            // Critical and non-recoverable, from either direction
+            status.functional = !asserted(0x006c);
+            break;
+        case presence:
+            status.present = asserted(0x0002) || !asserted(0x0001);
+            break;
+        case enabled:
+            status.functional = !asserted(0x0001);
+            break;
+        case sensorSpecific:
+            if (sensorType == processor)
+            {
+                // IERR to uncorrectable error, and disabled
+                status.functional = !asserted(0x017f);
+                status.present = asserted(0x0080);
+            }
+            else if (sensorType == powerSupply)
+            {
+                // Failure, input lost or out of range, and configuration error
+                status.functional = !asserted(0x007a);
+                status.present = asserted(0x0001);
+            }
+            else if (sensorType == driveSlot)
+            {
+                status.functional = !asserted(0x0002);
+                status.present = asserted(0x0001);
+            }
+            break;
+        default:
+            break;
+    }
+    return status;
+}
+
 } // namespace sensor
 namespace me_bridge
 {
@@ -85,6 +178,25 @@ constexpr uint8_t sendRawPmbus = 0xd9;
 using IpmbMethodType =
     std::tuple<int, uint8_t, uint8_t, uint8_t, uint8_t, std::vector<uint8_t>>;
 
+// How a sensor is read and its readings converted. The Class of a sensor
+// presets these, and its configuration may give any of them in place of the
+// preset ones.
+struct IpmbRequestConfig
+{
+    std::optional<uint8_t> commandAddress;
+    std::optional<uint8_t> netfn;
+    std::optional<uint8_t> command;
+    std::optional<std::vector<uint8_t>> commandData;
+    std::optional<uint8_t> initCommand;
+    std::optional<std::vector<uint8_t>> initData;
+    std::optional<ReadingFormat> readingFormat;
+    std::optional<double> minValue;
+    std::optional<double> maxValue;
+
+    // Replaces the settings that the other one has
+    void update(const IpmbRequestConfig& other);
+};
+
 struct IpmbSensor :
     public Sensor,
     public std::enable_shared_from_this<IpmbSensor>
@@ -95,7 +207,8 @@ struct IpmbSensor :
                sdbusplus::asio::object_server& objectServer,
                std::vector<thresholds::Threshold>&& thresholdData,
                uint8_t deviceAddress, uint8_t hostSMbusIndex, float pollRate,
-               std::string& sensorTypeName);
+               std::string& sensorTypeName,
+               std::pair<double, double> readingRange);
     ~IpmbSensor() override;
 
     void checkThresholds() override;
@@ -108,6 +221,13 @@ struct IpmbSensor :
                                const std::vector<uint8_t>& data, double& resp,
                                size_t errCount);
     void parseConfigValues(const SensorBaseConfigMap& entry);
+    static IpmbRequestConfig presetRequest(IpmbType type, IpmbSubType subType,
+                                           uint8_t deviceAddress,
+                                           uint8_t hostSMbusIndex);
+    static IpmbRequestConfig
+        parseRequestConfig(const SensorBaseConfigMap& entry);
+    static std::optional<ReadingFormat>
+        readingFormatFromName(const std::string& formatName);
     bool sensorClassType(const std::string& sensorClass);
     void sensorSubType(const std::string& sensorTypeName);
 
@@ -121,10 +241,15 @@ struct IpmbSensor :
     uint8_t deviceAddress = 0;
     uint8_t errorCount = 0;
     uint8_t hostSMbusIndex = 0;
+    uint8_t ipmbBusIndex = 0;
     std::vector<uint8_t> commandData;
     std::optional<uint8_t> initCommand;
     std::vector<uint8_t> initData;
     int sensorPollMs;
+    // The conversion of the raw readings of sensors found in the SDR
+    std::optional<SensorValConversion> sdrConversion;
+    IpmbRequestConfig requestConfig;
+    IpmbRequestTiming timing;
 
     ReadingFormat readingFormat = ReadingFormat::byte0;
 
@@ -132,6 +257,8 @@ struct IpmbSensor :
     void sendIpmbRequest();
     sdbusplus::asio::object_server& objectServer;
     boost::asio::steady_timer waitTimer;
+    std::shared_ptr<IpmbController> controller;
+    void controllerReachable(bool reachable);
     void ipmbRequestCompletionCb(const boost::system::error_code& ec,
                                  const IpmbMethodType& response);
 };
@@ -142,7 +269,26 @@ void createSensors(
         sensors,
     std::shared_ptr<sdbusplus::asio::connection>& dbusConnection);
 
+// The thresholds of a full SDR record that can be read
+std::vector<thresholds::Threshold> sdrThresholds(const SensorInfo& info);
+
+// Replaces the sensors of the IpmbDevice with those of its SDR records
+void createSDRSensors(
+    boost::asio::io_context& io, sdbusplus::asio::object_server& objectServer,
+    boost::container::flat_map<std::string, std::shared_ptr<IpmbSensor>>&
+        sensors,
+    boost::container::flat_map<std::string,
+                               std::shared_ptr<IpmbDiscreteSensor>>&
+        discreteSensors,
+    std::shared_ptr<sdbusplus::asio::connection>& dbusConnection,
+    const IpmbSDRDevice& device, const SensorBaseConfigMap& cfg);
+
 void interfaceRemoved(
     sdbusplus::message_t& message,
     boost::container::flat_map<std::string, std::shared_ptr<IpmbSensor>>&
-        sensors);
+        sensors,
+    boost::container::flat_map<std::string,
+                               std::shared_ptr<IpmbDiscreteSensor>>&
+        discreteSensors,
+    boost::container::flat_map<uint8_t, std::shared_ptr<IpmbSDRDevice>>&
+        sdrDevices);
@@ -1,5 +1,8 @@
+#include "IpmbDiscreteSensor.hpp"
+#include "IpmbEvent.hpp"
 #include "IpmbSDRSensor.hpp"
 #include "IpmbSensor.hpp"
+#include "Reactor.hpp"
 #include "Utils.hpp"
 
 #include <boost/asio/error.hpp>
@@ -21,35 +24,111 @@
 #include <iostream>
 #include <memory>
 #include <string>
+#include <utility>
 #include <variant>
 #include <vector>
 
+namespace ipmbsensor
+{
+
 std::unique_ptr<boost::asio::steady_timer> initCmdTimer;
 boost::container::flat_map<std::string, std::shared_ptr<IpmbSensor>> sensors;
+boost::container::flat_map<std::string, std::shared_ptr<IpmbDiscreteSensor>>
+    discreteSensors;
 boost::container::flat_map<uint8_t, std::shared_ptr<IpmbSDRDevice>> sdrsensor;
-
-void sdrHandler(
-    boost::container::flat_map<uint8_t, std::shared_ptr<IpmbSDRDevice>>
-        sdrsensor,
-    sdbusplus::message_t& message,
-    std::shared_ptr<sdbusplus::asio::connection>& dbusConnection)
+std::unique_ptr<IpmbEventReceiver> eventReceiver;
+
+static void addSDRDevice(
+    boost::asio::io_context& io, sdbusplus::asio::object_server& objectServer,
+    std::shared_ptr<sdbusplus::asio::connection>& dbusConnection,
+    const std::string& path, const SensorBaseConfigMap& cfg,
+    boost::container::flat_map<uint8_t, std::shared_ptr<IpmbSDRDevice>>&
+        devices)
 {
-    std::string objectName;
-    SensorBaseConfigMap values;
-    message.read(objectName, values);
-
-    auto findBus = values.find("Bus");
-    if (findBus == values.end())
+    auto findBus = cfg.find("Bus");
+    if (findBus == cfg.end())
     {
         return;
     }
+    uint8_t busIndex = loadVariant<uint8_t>(cfg, "Bus");
+
+    auto device =
+        std::make_shared<IpmbSDRDevice>(dbusConnection, busIndex, path);
+    device->onUpdate = [&io, &objectServer, &dbusConnection,
+                        cfg](const IpmbSDRDevice& updated) {
+        createSDRSensors(io, objectServer, sensors, discreteSensors,
+                         dbusConnection, updated, cfg);
+    };
+    device->getSDRRepositoryInfo();
+    devices[busIndex] = std::move(device);
+}
+
+void createSDRDevices(
+    boost::asio::io_context& io, sdbusplus::asio::object_server& objectServer,
+    std::shared_ptr<sdbusplus::asio::connection>& dbusConnection)
+{
+    dbusConnection->async_method_call(
+        [&](boost::system::error_code ec, const ManagedObjectType& resp) {
+            if (ec)
+            {
+                std::cerr << "Error contacting entity manager\n";
+                return;
+            }
 
-    uint8_t busIndex = loadVariant<uint8_t>(values, "Bus");
+            boost::container::flat_map<uint8_t, std::shared_ptr<IpmbSDRDevice>>
+                devices;
+            for (const auto& [path, interfaces] : resp)
+            {
+                auto findCfg =
+                    interfaces.find(configInterfaceName(sdrInterface));
+                if (findCfg == interfaces.end())
+                {
+                    continue;
+                }
 
-    auto& sdrsen = sdrsensor[busIndex];
-    sdrsen = nullptr;
-    sdrsen = std::make_shared<IpmbSDRDevice>(dbusConnection, busIndex);
-    sdrsen->getSDRRepositoryInfo();
+                addSDRDevice(io, objectServer, dbusConnection, path.str,
+                             findCfg->second, devices);
+            }
+
+            // Dropping the devices stops them from refreshing their sensors
+            sdrsensor = std::move(devices);
+        },
+        entityManagerName, "/xyz/openbmc_project/inventory",
+        "org.freedesktop.DBus.ObjectManager", "GetManagedObjects");
+}
+
+// Only the device whose configuration changed walks its repository again
+static void updateSDRDevice(
+    boost::asio::io_context& io, sdbusplus::asio::object_server& objectServer,
+    std::shared_ptr<sdbusplus::asio::connection>& dbusConnection,
+    const std::string& path)
+{
+    dbusConnection->async_method_call(
+        [&io, &objectServer, &dbusConnection,
+         path](boost::system::error_code ec, const SensorBaseConfigMap& cfg) {
+            if (ec)
+            {
+                std::cerr << "Error reading configuration of " << path << "\n";
+                return;
+            }
+
+            auto deviceIt = sdrsensor.begin();
+            while (deviceIt != sdrsensor.end())
+            {
+                if (deviceIt->second->configPath == path)
+                {
+                    deviceIt = sdrsensor.erase(deviceIt);
+                }
+                else
+                {
+                    deviceIt++;
+                }
+            }
+            addSDRDevice(io, objectServer, dbusConnection, path, cfg,
+                         sdrsensor);
+        },
+        entityManagerName, path, properties::interface, "GetAll",
+        configInterfaceName(sdrInterface));
 }
 
 void reinitSensors(sdbusplus::message_t& message)
@@ -94,23 +173,26 @@ void reinitSensors(sdbusplus::message_t& message)
     }
 }
 
-int main()
+static void start(boost::asio::io_context& io,
+                  std::shared_ptr<sdbusplus::asio::connection>& systemBus,
+                  sdbusplus::asio::object_server& objectServer)
 {
-    boost::asio::io_context io;
-    auto systemBus = std::make_shared<sdbusplus::asio::connection>(io);
-    sdbusplus::asio::object_server objectServer(systemBus, true);
-    objectServer.add_manager("/xyz/openbmc_project/sensors");
+    reactor::addManager(objectServer, "/xyz/openbmc_project/sensors");
+    reactor::addManager(objectServer, "/xyz/openbmc_project/ipmb");
     systemBus->request_name("xyz.openbmc_project.IpmbSensor");
 
     initCmdTimer = std::make_unique<boost::asio::steady_timer>(io);
+    eventReceiver = std::make_unique<IpmbEventReceiver>(objectServer, sensors,
+                                                        discreteSensors);
 
     boost::asio::post(io, [&]() {
         createSensors(io, objectServer, sensors, systemBus);
+        createSDRDevices(io, objectServer, systemBus);
     });
 
-    boost::asio::steady_timer configTimer(io);
+    static boost::asio::steady_timer configTimer(io);
 
-    std::function<void(sdbusplus::message_t&)> eventHandler =
+    static std::function<void(sdbusplus::message_t&)> eventHandler =
         [&](sdbusplus::message_t&) {
             configTimer.expires_after(std::chrono::seconds(1));
             // create a timer because normally multiple properties change
@@ -127,35 +209,39 @@ int main()
             });
         };
 
-    std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
+    static std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
         setupPropertiesChangedMatches(
             *systemBus, std::to_array<const char*>({sensorType}), eventHandler);
 
-    sdbusplus::bus::match_t powerChangeMatch(
+    static sdbusplus::bus::match_t powerChangeMatch(
         static_cast<sdbusplus::bus_t&>(*systemBus),
         "type='signal',interface='" + std::string(properties::interface) +
             "',path='" + std::string(power::path) + "',arg0='" +
             std::string(power::interface) + "'",
         reinitSensors);
 
-    auto matchSignal = std::make_shared<sdbusplus::bus::match_t>(
+    static auto matchSignal = std::make_shared<sdbusplus::bus::match_t>(
         static_cast<sdbusplus::bus_t&>(*systemBus),
         "type='signal',member='PropertiesChanged',path_namespace='" +
-            std::string(inventoryPath) + "',arg0namespace='" +
+            std::string(inventoryPath) + "',arg0='" +
             configInterfaceName(sdrInterface) + "'",
-        [&systemBus](sdbusplus::message_t& msg) {
-            sdrHandler(sdrsensor, msg, systemBus);
+        [&](sdbusplus::message_t& message) {
+            updateSDRDevice(io, objectServer, systemBus, message.get_path());
         });
 
     // Watch for entity-manager to remove configuration interfaces
     // so the corresponding sensors can be removed.
-    auto ifaceRemovedMatch = std::make_shared<sdbusplus::bus::match_t>(
+    static auto ifaceRemovedMatch = std::make_shared<sdbusplus::bus::match_t>(
         static_cast<sdbusplus::bus_t&>(*systemBus),
         "type='signal',member='InterfacesRemoved',arg0path='" +
             std::string(inventoryPath) + "/'",
-        [](sdbusplus::message_t& msg) { interfaceRemoved(msg, sensors); });
+        [](sdbusplus::message_t& msg) {
+            interfaceRemoved(msg, sensors, discreteSensors, sdrsensor);
+        });
 
     setupManufacturingModeMatch(*systemBus);
-    io.run();
-    return 0;
 }
+
+static const reactor::Registration registration("ipmb", start);
+
+} // namespace ipmbsensor
@@ -21,12 +21,14 @@
 #include <filesystem>
 #include <format>
 #include <functional>
+#include <initializer_list>
 #include <map>
 #include <memory>
 #include <optional>
 #include <set>
 #include <stdexcept>
 #include <string>
+#include <string_view>
 #include <system_error>
 #include <utility>
 #include <variant>
@@ -43,8 +45,10 @@ static constexpr const char* mctpdEndpointControlInterface =
 
 MCTPDDevice::MCTPDDevice(
     const std::shared_ptr<sdbusplus::asio::connection>& connection,
-    const std::string& interface, const std::vector<uint8_t>& physaddr) :
-    connection(connection), interface(interface), physaddr(physaddr)
+    const std::string& interface, const std::vector<uint8_t>& physaddr,
+    std::optional<uint8_t> eid) :
+    connection(connection), interface(interface), physaddr(physaddr),
+    requestedEID(eid)
 {}
 
 void MCTPDDevice::onEndpointInterfacesRemoved(
@@ -116,9 +120,19 @@ void MCTPDDevice::setup(
                 "INVENTORY_PATH", objpath);
         }
     };
-    connection->async_method_call(onSetup, mctpdBusName, mctpdControlPath,
-                                  mctpdControlInterface, "AssignEndpoint",
-                                  interface, physaddr);
+    // mctpd allocates the EIDs behind a bridge itself, from its dynamic range
+    if (requestedEID)
+    {
+        connection->async_method_call(
+            onSetup, mctpdBusName, mctpdControlPath, mctpdControlInterface,
+            "AssignEndpointStatic", interface, physaddr, *requestedEID);
+    }
+    else
+    {
+        connection->async_method_call(onSetup, mctpdBusName, mctpdControlPath,
+                                      mctpdControlInterface, "AssignEndpoint",
+                                      interface, physaddr);
+    }
 }
 
 void MCTPDDevice::endpointRemoved()
@@ -156,9 +170,36 @@ std::string MCTPDDevice::describe() const
         }
         description.append(std::format("{:02x} ]", *it));
     }
+    if (requestedEID)
+    {
+        description.append(std::format(", static EID: {}", *requestedEID));
+    }
     return description;
 }
 
+std::optional<uint8_t> MCTPDDevice::staticEID() const
+{
+    return requestedEID;
+}
+
+std::optional<uint8_t>
+    MCTPDDevice::staticEIDFromConfig(const SensorBaseConfigMap& iface)
+{
+    auto member = iface.find("EID");
+    if (member == iface.end())
+    {
+        return std::nullopt;
+    }
+
+    auto eid = std::visit(VariantToUnsignedIntVisitor(), member->second);
+    if (eid < minimumEID || eid > maximumEID)
+    {
+        throw std::invalid_argument(std::format(
+            "EID must be between {} and {}", minimumEID, maximumEID));
+    }
+    return static_cast<uint8_t>(eid);
+}
+
 std::string MCTPDEndpoint::path(const std::shared_ptr<MCTPEndpoint>& ep)
 {
     return std::format("/xyz/openbmc_project/mctp/{}/{}", ep->network(),
@@ -377,9 +418,11 @@ std::shared_ptr<I2CMCTPDDevice> I2CMCTPDDevice::from(
         throw std::invalid_argument("Bad bus index");
     }
 
+    auto eid = staticEIDFromConfig(iface);
+
     try
     {
-        return std::make_shared<I2CMCTPDDevice>(connection, bus, address);
+        return std::make_shared<I2CMCTPDDevice>(connection, bus, address, eid);
     }
     catch (const MCTPException& ex)
     {
@@ -405,3 +448,191 @@ std::string I2CMCTPDDevice::interfaceFromBus(int bus)
 
     return it->path().filename();
 }
+
+// Throws std::invalid_argument unless the iface is a configuration record of
+// the type, with each of the members
+static void checkConfig(const SensorBaseConfigMap& iface, const char* type,
+                        std::initializer_list<const char*> members)
+{
+    auto mType = iface.find("Type");
+    if (mType == iface.end())
+    {
+        throw std::invalid_argument(
+            "No 'Type' member found for provided configuration object");
+    }
+
+    if (std::visit(VariantToStringVisitor(), mType->second) != type)
+    {
+        throw std::invalid_argument(std::format("Not an {} device", type));
+    }
+
+    for (const char* member : members)
+    {
+        if (!iface.contains(member))
+        {
+            throw std::invalid_argument(
+                std::format("Configuration object violates {} schema", type));
+        }
+    }
+}
+
+template <typename T>
+static T configNumber(const SensorBaseConfigMap& iface, const char* member)
+{
+    auto value = std::visit(VariantToStringVisitor(), iface.at(member));
+    std::string_view digits(value);
+    int base = 10;
+    if (digits.starts_with("0x") || digits.starts_with("0X"))
+    {
+        digits.remove_prefix(2);
+        base = 16;
+    }
+    T number{};
+    auto [ptr, ec] = std::from_chars(digits.data(),
+                                     digits.data() + digits.size(), number,
+                                     base);
+    if (ec != std::errc{} || ptr != digits.data() + digits.size())
+    {
+        throw std::invalid_argument(std::format("Bad {}", member));
+    }
+    return number;
+}
+
+std::optional<SensorBaseConfigMap>
+    I3CMCTPDDevice::match(const SensorData& config)
+{
+    auto iface = config.find(configInterfaceName(configType));
+    if (iface == config.end())
+    {
+        return std::nullopt;
+    }
+    return iface->second;
+}
+
+bool I3CMCTPDDevice::match(const std::set<std::string>& interfaces)
+{
+    return interfaces.contains(configInterfaceName(configType));
+}
+
+std::shared_ptr<I3CMCTPDDevice> I3CMCTPDDevice::from(
+    const std::shared_ptr<sdbusplus::asio::connection>& connection,
+    const SensorBaseConfigMap& iface)
+{
+    checkConfig(iface, configType, {"Bus", "Name", "ProvisionedID"});
+
+    auto bus = configNumber<int>(iface, "Bus");
+    auto pid = configNumber<uint64_t>(iface, "ProvisionedID");
+    if ((pid >> 48) != 0)
+    {
+        throw std::invalid_argument("ProvisionedID is wider than 48 bits");
+    }
+    auto eid = staticEIDFromConfig(iface);
+
+    try
+    {
+        return std::make_shared<I3CMCTPDDevice>(connection, bus, pid, eid);
+    }
+    catch (const MCTPException& ex)
+    {
+        warning(
+            "Failed to create I3CMCTPDDevice at [ bus: {I3C_BUS}, PID: {I3C_PID} ]: {EXCEPTION}",
+            "I3C_BUS", bus, "I3C_PID", lg2::hex, pid, "EXCEPTION", ex);
+        return {};
+    }
+}
+
+std::vector<uint8_t> I3CMCTPDDevice::physaddrFromPID(uint64_t pid)
+{
+    std::vector<uint8_t> physaddr;
+    for (int shift = 40; shift >= 0; shift -= 8)
+    {
+        physaddr.push_back(static_cast<uint8_t>(pid >> shift));
+    }
+    return physaddr;
+}
+
+std::string I3CMCTPDDevice::interfaceFromBus(int bus)
+{
+    std::filesystem::path netdir =
+        std::format("/sys/bus/i3c/devices/i3c-{}/net", bus);
+    std::error_code ec;
+    std::filesystem::directory_iterator it(netdir, ec);
+    if (ec || it == std::filesystem::end(it))
+    {
+        error("No net device associated with I3C bus {I3C_BUS} at {NET_DEVICE}",
+              "I3C_BUS", bus, "NET_DEVICE", netdir);
+        throw MCTPException("Bus is not configured as an MCTP interface");
+    }
+
+    return it->path().filename();
+}
+
+std::optional<SensorBaseConfigMap>
+    PCIeVDMMCTPDDevice::match(const SensorData& config)
+{
+    auto iface = config.find(configInterfaceName(configType));
+    if (iface == config.end())
+    {
+        return std::nullopt;
+    }
+    return iface->second;
+}
+
+bool PCIeVDMMCTPDDevice::match(const std::set<std::string>& interfaces)
+{
+    return interfaces.contains(configInterfaceName(configType));
+}
+
+std::shared_ptr<PCIeVDMMCTPDDevice> PCIeVDMMCTPDDevice::from(
+    const std::shared_ptr<sdbusplus::asio::connection>& connection,
+    const SensorBaseConfigMap& iface)
+{
+    checkConfig(iface, configType,
+                {"Bus", "Device", "Function", "Interface", "Name"});
+
+    auto interface =
+        std::visit(VariantToStringVisitor(), iface.at("Interface"));
+    auto bus = configNumber<uint8_t>(iface, "Bus");
+    auto device = configNumber<uint8_t>(iface, "Device");
+    auto function = configNumber<uint8_t>(iface, "Function");
+    if (device > 0x1f || function > 0x07)
+    {
+        throw std::invalid_argument("Bad PCIe device or function number");
+    }
+    auto eid = staticEIDFromConfig(iface);
+
+    try
+    {
+        return std::make_shared<PCIeVDMMCTPDDevice>(
+            connection, interface, bus, device, function, eid);
+    }
+    catch (const MCTPException& ex)
+    {
+        warning(
+            "Failed to create PCIeVDMMCTPDDevice at [ interface: {NET_DEVICE}, BDF: {PCIE_BUS}:{PCIE_DEVICE}.{PCIE_FUNCTION} ]: {EXCEPTION}",
+            "NET_DEVICE", interface, "PCIE_BUS", bus, "PCIE_DEVICE", device,
+            "PCIE_FUNCTION", function, "EXCEPTION", ex);
+        return {};
+    }
+}
+
+std::vector<uint8_t> PCIeVDMMCTPDDevice::physaddrFromBDF(
+    uint8_t bus, uint8_t device, uint8_t function)
+{
+    return {bus, static_cast<uint8_t>((device << 3) | function)};
+}
+
+std::string PCIeVDMMCTPDDevice::checkInterface(const std::string& interface)
+{
+    std::filesystem::path netdev =
+        std::filesystem::path("/sys/class/net") / interface;
+    std::error_code ec;
+    if (!std::filesystem::exists(netdev, ec))
+    {
+        error("No net device {NET_DEVICE} for PCIe VDM", "NET_DEVICE",
+              interface);
+        throw MCTPException("Interface is not configured for MCTP");
+    }
+
+    return interface;
+}
@@ -47,6 +47,8 @@ enum class MCTPTransport
 {
     Reserved = 0x00,
     SMBus = 0x01,
+    PCIeVDM = 0x02,
+    I3C = 0x06,
 };
 
 /**
@@ -62,6 +64,10 @@ struct MCTPInterface
     auto operator<=>(const MCTPInterface& r) const = default;
 };
 
+// EIDs 0 and 255 are the null and broadcast EIDs, and 1 to 7 are reserved
+constexpr uint8_t minimumEID = 8;
+constexpr uint8_t maximumEID = 254;
+
 class MCTPDevice;
 
 /**
@@ -171,6 +177,15 @@ class MCTPDevice
      *         address properties.
      */
     virtual std::string describe() const = 0;
+
+    /**
+     * @return The static EID the device's configuration asks for, or
+     *         std::nullopt if mctpd assigns one dynamically
+     */
+    virtual std::optional<uint8_t> staticEID() const
+    {
+        return std::nullopt;
+    }
 };
 
 class MCTPDDevice;
@@ -259,7 +274,8 @@ class MCTPDDevice :
     MCTPDDevice() = delete;
     MCTPDDevice(const std::shared_ptr<sdbusplus::asio::connection>& connection,
                 const std::string& interface,
-                const std::vector<uint8_t>& physaddr);
+                const std::vector<uint8_t>& physaddr,
+                std::optional<uint8_t> eid = std::nullopt);
     MCTPDDevice(const MCTPDDevice& other) = delete;
     MCTPDDevice(MCTPDDevice&& other) = delete;
     ~MCTPDDevice() override = default;
@@ -269,6 +285,15 @@ class MCTPDDevice :
                    added) override;
     void remove() override;
     std::string describe() const override;
+    std::optional<uint8_t> staticEID() const override;
+
+    /**
+     * @brief Parse the EID member shared by the MCTP target configurations.
+     *
+     * @throws std::invalid_argument if the EID is malformed or out of range
+     */
+    static std::optional<uint8_t>
+        staticEIDFromConfig(const SensorBaseConfigMap& iface);
 
   private:
     static void onEndpointInterfacesRemoved(
@@ -278,6 +303,7 @@ class MCTPDDevice :
     std::shared_ptr<sdbusplus::asio::connection> connection;
     const std::string interface;
     const std::vector<uint8_t> physaddr;
+    const std::optional<uint8_t> requestedEID;
     std::shared_ptr<MCTPDEndpoint> endpoint;
     std::unique_ptr<sdbusplus::bus::match_t> removeMatch;
 
@@ -309,8 +335,8 @@ class I2CMCTPDDevice : public MCTPDDevice
     I2CMCTPDDevice() = delete;
     I2CMCTPDDevice(
         const std::shared_ptr<sdbusplus::asio::connection>& connection, int bus,
-        uint8_t physaddr) :
-        MCTPDDevice(connection, interfaceFromBus(bus), {physaddr})
+        uint8_t physaddr, std::optional<uint8_t> eid = std::nullopt) :
+        MCTPDDevice(connection, interfaceFromBus(bus), {physaddr}, eid)
     {}
     ~I2CMCTPDDevice() override = default;
 
@@ -319,3 +345,70 @@ class I2CMCTPDDevice : public MCTPDDevice
 
     static std::string interfaceFromBus(int bus);
 };
+
+/**
+ * @brief An MCTPDDevice on an I3C bus, addressed by the 48-bit Provisioned ID
+ *        the @c mctp-i3c binding uses as its physical address.
+ */
+class I3CMCTPDDevice : public MCTPDDevice
+{
+  public:
+    static std::optional<SensorBaseConfigMap> match(const SensorData& config);
+    static bool match(const std::set<std::string>& interfaces);
+    static std::shared_ptr<I3CMCTPDDevice>
+        from(const std::shared_ptr<sdbusplus::asio::connection>& connection,
+             const SensorBaseConfigMap& iface);
+
+    I3CMCTPDDevice() = delete;
+    I3CMCTPDDevice(
+        const std::shared_ptr<sdbusplus::asio::connection>& connection, int bus,
+        uint64_t pid, std::optional<uint8_t> eid = std::nullopt) :
+        MCTPDDevice(connection, interfaceFromBus(bus), physaddrFromPID(pid),
+                    eid)
+    {}
+    ~I3CMCTPDDevice() override = default;
+
+    // The Provisioned ID, most significant byte first
+    static std::vector<uint8_t> physaddrFromPID(uint64_t pid);
+
+  private:
+    static constexpr const char* configType = "MCTPI3CTarget";
+
+    static std::string interfaceFromBus(int bus);
+};
+
+/**
+ * @brief An MCTPDDevice reached with PCIe Vendor Defined Messages, addressed
+ *        by its PCIe routing ID (bus, device and function).
+ *
+ * PCIe VDM bindings aren't tied to a bus index, so the configuration names the
+ * MCTP network interface directly.
+ */
+class PCIeVDMMCTPDDevice : public MCTPDDevice
+{
+  public:
+    static std::optional<SensorBaseConfigMap> match(const SensorData& config);
+    static bool match(const std::set<std::string>& interfaces);
+    static std::shared_ptr<PCIeVDMMCTPDDevice>
+        from(const std::shared_ptr<sdbusplus::asio::connection>& connection,
+             const SensorBaseConfigMap& iface);
+
+    PCIeVDMMCTPDDevice() = delete;
+    PCIeVDMMCTPDDevice(
+        const std::shared_ptr<sdbusplus::asio::connection>& connection,
+        const std::string& interface, uint8_t bus, uint8_t device,
+        uint8_t function, std::optional<uint8_t> eid = std::nullopt) :
+        MCTPDDevice(connection, checkInterface(interface),
+                    physaddrFromBDF(bus, device, function), eid)
+    {}
+    ~PCIeVDMMCTPDDevice() override = default;
+
+    // The 16-bit routing ID, most significant byte first
+    static std::vector<uint8_t> physaddrFromBDF(uint8_t bus, uint8_t device,
+                                                uint8_t function);
+
+  private:
+    static constexpr const char* configType = "MCTPPCIeTarget";
+
+    static std::string checkInterface(const std::string& interface);
+};
@@ -7,6 +7,9 @@
 #include <boost/system/detail/error_code.hpp>
 #include <phosphor-logging/lg2.hpp>
 
+#include <chrono>
+#include <cmath>
+#include <cstdint>
 #include <cstdlib>
 #include <memory>
 #include <optional>
@@ -17,17 +20,145 @@
 
 PHOSPHOR_LOG2_USING;
 
+std::chrono::seconds RetryPolicy::delay(unsigned int attempts) const
+{
+    if (attempts == 0)
+    {
+        return std::chrono::seconds(0);
+    }
+
+    double seconds = static_cast<double>(initialDelay.count()) *
+                     std::pow(backoffFactor, attempts - 1);
+    if (seconds >= static_cast<double>(maxDelay.count()))
+    {
+        return maxDelay;
+    }
+    return std::chrono::seconds(std::llround(seconds));
+}
+
 void MCTPReactor::deferSetup(const std::shared_ptr<MCTPDevice>& dev)
 {
     debug("Deferring setup for MCTP device at [ {MCTP_DEVICE} ]", "MCTP_DEVICE",
           dev->describe());
 
-    deferred.emplace(dev);
+    // Retried on the next tick, as nothing has failed yet
+    deferred.insert_or_assign(dev, Deferral{.due = Clock::now()});
+}
+
+void MCTPReactor::setupFailed(const std::shared_ptr<MCTPDevice>& dev,
+                              const std::string& reason)
+{
+    std::optional<std::string> item = devices.inventoryFor(dev);
+    if (!item)
+    {
+        // The device was unmanaged while its setup was in flight
+        return;
+    }
+
+    Deferral& deferral = deferred[dev];
+    deferral.pending = false;
+    deferral.attempts++;
+
+    auto policy = retryPolicies.find(*item);
+    RetryPolicy retry =
+        policy == retryPolicies.end() ? RetryPolicy{} : policy->second;
+    if (retry.maxAttempts && deferral.attempts >= *retry.maxAttempts)
+    {
+        deferral.abandoned = true;
+        error(
+            "Abandoned setup for MCTP device at [ {MCTP_DEVICE} ] after {ATTEMPTS} attempts: {ERROR_MESSAGE}",
+            "MCTP_DEVICE", dev->describe(), "ATTEMPTS", deferral.attempts,
+            "ERROR_MESSAGE", reason);
+        return;
+    }
+
+    std::chrono::seconds delay = retry.delay(deferral.attempts);
+    deferral.due = Clock::now() + delay;
+    debug(
+        "Deferring setup for MCTP device at [ {MCTP_DEVICE} ] by {SECONDS} seconds",
+        "MCTP_DEVICE", dev->describe(), "SECONDS", delay.count());
+}
+
+void MCTPReactor::setupCompleted(const std::shared_ptr<MCTPDevice>& dev)
+{
+    deferred.erase(dev);
 }
 
 void MCTPReactor::untrackEndpoint(const std::shared_ptr<MCTPEndpoint>& ep)
 {
-    server.disassociate(MCTPDEndpoint::path(ep));
+    std::string path = MCTPDEndpoint::path(ep);
+    server.disassociate(path);
+    if (endpointHealth.erase(path) == 0)
+    {
+        return;
+    }
+    if (healthServer != nullptr)
+    {
+        healthServer->withdraw(path);
+    }
+}
+
+void MCTPReactor::publishHealth(const std::string& path,
+                                const TrackedHealth& tracked,
+                                Clock::time_point now)
+{
+    if (healthServer == nullptr)
+    {
+        return;
+    }
+
+    EndpointHealth health = tracked.health;
+    if (tracked.degradedSince)
+    {
+        health.degradedTime += std::chrono::duration_cast<std::chrono::seconds>(
+            now - *tracked.degradedSince);
+    }
+    healthServer->publish(path, health);
+}
+
+void MCTPReactor::endpointDegraded(const std::shared_ptr<MCTPEndpoint>& ep)
+{
+    debug("Endpoint entered degraded state: [ {MCTP_ENDPOINT} ]",
+          "MCTP_ENDPOINT", ep->describe());
+
+    auto entry = endpointHealth.find(MCTPDEndpoint::path(ep));
+    if (entry == endpointHealth.end() || !entry->second.health.available)
+    {
+        return;
+    }
+
+    TrackedHealth& tracked = entry->second;
+    Clock::time_point now = Clock::now();
+    tracked.health.available = false;
+    tracked.health.degraded++;
+    tracked.degradedSince = now;
+    tracked.logged = false;
+    publishHealth(entry->first, tracked, now);
+}
+
+void MCTPReactor::endpointAvailable(const std::shared_ptr<MCTPEndpoint>& ep)
+{
+    debug("Endpoint entered available state: [ {MCTP_ENDPOINT} ]",
+          "MCTP_ENDPOINT", ep->describe());
+
+    auto entry = endpointHealth.find(MCTPDEndpoint::path(ep));
+    if (entry == endpointHealth.end() || entry->second.health.available)
+    {
+        return;
+    }
+
+    TrackedHealth& tracked = entry->second;
+    Clock::time_point now = Clock::now();
+    tracked.health.available = true;
+    tracked.health.recovered++;
+    if (tracked.degradedSince)
+    {
+        tracked.health.degradedTime +=
+            std::chrono::duration_cast<std::chrono::seconds>(
+                now - *tracked.degradedSince);
+        tracked.degradedSince.reset();
+    }
+    publishHealth(entry->first, tracked, now);
 }
 
 void MCTPReactor::trackEndpoint(const std::shared_ptr<MCTPEndpoint>& ep)
@@ -37,14 +168,18 @@ void MCTPReactor::trackEndpoint(const std::shared_ptr<MCTPEndpoint>& ep)
 
     ep->subscribe(
         // Degraded
-        [](const std::shared_ptr<MCTPEndpoint>& ep) {
-            debug("Endpoint entered degraded state: [ {MCTP_ENDPOINT} ]",
-                  "MCTP_ENDPOINT", ep->describe());
+        [weak{weak_from_this()}](const std::shared_ptr<MCTPEndpoint>& ep) {
+            if (auto self = weak.lock())
+            {
+                self->endpointDegraded(ep);
+            }
         },
         // Available
-        [](const std::shared_ptr<MCTPEndpoint>& ep) {
-            debug("Endpoint entered available state: [ {MCTP_ENDPOINT} ]",
-                  "MCTP_ENDPOINT", ep->describe());
+        [weak{weak_from_this()}](const std::shared_ptr<MCTPEndpoint>& ep) {
+            if (auto self = weak.lock())
+            {
+                self->endpointAvailable(ep);
+            }
         },
         // Removed
         [weak{weak_from_this()}](const std::shared_ptr<MCTPEndpoint>& ep) {
@@ -83,9 +218,25 @@ void MCTPReactor::trackEndpoint(const std::shared_ptr<MCTPEndpoint>& ep)
               "MCTP_ENDPOINT", ep->describe());
         return;
     }
+    std::string path = MCTPDEndpoint::path(ep);
     std::vector<Association> associations{
         {"configured_by", "configures", *item}};
-    server.associate(MCTPDEndpoint::path(ep), associations);
+    server.associate(path, associations);
+
+    // Health is published alongside the association, so is reachable from the
+    // inventory item
+    auto [entry, _] = endpointHealth.insert_or_assign(
+        path, TrackedHealth{.inventory = *item});
+    publishHealth(entry->first, entry->second, Clock::now());
+
+    std::optional<uint8_t> staticEID = ep->device()->staticEID();
+    if (staticEID && *staticEID != ep->eid())
+    {
+        warning(
+            "Endpoint '{MCTP_ENDPOINT}' was assigned EID {MCTP_EID} in place of its static EID {STATIC_EID}",
+            "MCTP_ENDPOINT", ep->describe(), "MCTP_EID", ep->eid(),
+            "STATIC_EID", *staticEID);
+    }
 }
 
 void MCTPReactor::setupEndpoint(const std::shared_ptr<MCTPDevice>& dev)
@@ -111,29 +262,95 @@ void MCTPReactor::setupEndpoint(const std::shared_ptr<MCTPDevice>& dev)
                 "Setup failed for MCTP device at [ {MCTP_DEVICE} ]: {ERROR_MESSAGE}",
                 "MCTP_DEVICE", dev->describe(), "ERROR_MESSAGE", ec.message());
 
-            self->deferSetup(dev);
+            self->setupFailed(dev, ec.message());
             return;
         }
 
         try
         {
             self->trackEndpoint(ep);
+            self->setupCompleted(dev);
         }
         catch (const MCTPException& e)
         {
             error("Failed to track endpoint '{MCTP_ENDPOINT}': {EXCEPTION}",
                   "MCTP_ENDPOINT", ep->describe(), "EXCEPTION", e);
-            self->deferSetup(dev);
+            self->setupFailed(dev, e.what());
         }
     });
 }
 
 void MCTPReactor::tick()
 {
-    auto toSetup = std::exchange(deferred, {});
-    for (const auto& entry : toSetup)
+    Clock::time_point now = Clock::now();
+
+    // Setup may complete before it returns, and take the device out of
+    // deferred
+    std::vector<std::shared_ptr<MCTPDevice>> toSetup;
+    for (auto& [dev, deferral] : deferred)
+    {
+        if (!deferral.pending && !deferral.abandoned &&
+            deferral.due <= now)
+        {
+            deferral.pending = true;
+            toSetup.emplace_back(dev);
+        }
+    }
+    for (const auto& dev : toSetup)
+    {
+        setupEndpoint(dev);
+    }
+
+    now = Clock::now();
+    for (auto& [path, tracked] : endpointHealth)
+    {
+        if (!tracked.degradedSince)
+        {
+            continue;
+        }
+
+        // Keep the time spent degraded current
+        publishHealth(path, tracked, now);
+
+        auto period = degradedLogPeriods.find(tracked.inventory);
+        if (tracked.logged || period == degradedLogPeriods.end())
+        {
+            continue;
+        }
+
+        auto degraded = std::chrono::duration_cast<std::chrono::seconds>(
+            now - *tracked.degradedSince);
+        if (degraded >= period->second && healthServer != nullptr)
+        {
+            tracked.logged = true;
+            healthServer->logDegraded(path, tracked.inventory, degraded);
+        }
+    }
+}
+
+void MCTPReactor::setDegradedLogPeriod(
+    const std::string& path, std::optional<std::chrono::seconds> period)
+{
+    if (period)
+    {
+        degradedLogPeriods[path] = *period;
+    }
+    else
+    {
+        degradedLogPeriods.erase(path);
+    }
+}
+
+void MCTPReactor::setRetryPolicy(const std::string& path,
+                                 std::optional<RetryPolicy> policy)
+{
+    if (policy)
+    {
+        retryPolicies[path] = *policy;
+    }
+    else
     {
-        setupEndpoint(entry);
+        retryPolicies.erase(path);
     }
 }
 
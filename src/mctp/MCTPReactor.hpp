@@ -4,6 +4,10 @@
 #include "MCTPEndpoint.hpp"
 #include "Utils.hpp"
 
+#include <chrono>
+#include <cstdint>
+#include <map>
+#include <optional>
 #include <string>
 #include <vector>
 
@@ -16,6 +20,46 @@ struct AssociationServer
     virtual void disassociate(const std::string& path) = 0;
 };
 
+// How an endpoint has fared since it was set up
+struct EndpointHealth
+{
+    bool available = true;
+    // Transitions into and out of the degraded state
+    uint64_t degraded = 0;
+    uint64_t recovered = 0;
+    // In total, including any current stretch
+    std::chrono::seconds degradedTime{0};
+};
+
+struct EndpointHealthServer
+{
+    virtual ~EndpointHealthServer() = default;
+
+    virtual void publish(const std::string& path,
+                         const EndpointHealth& health) = 0;
+    virtual void withdraw(const std::string& path) = 0;
+
+    // The endpoint at path has been degraded for longer than the period
+    // configured for its inventory item
+    virtual void logDegraded(const std::string& path,
+                             const std::string& inventory,
+                             std::chrono::seconds degraded) = 0;
+};
+
+// How the setup of a device is retried after it fails. Retries happen on the
+// reactor's tick, so delays are rounded up to its period.
+struct RetryPolicy
+{
+    std::chrono::seconds initialDelay{5};
+    double backoffFactor = 2.0;
+    std::chrono::seconds maxDelay{300};
+    // Give up after this many failed attempts, or retry forever without one
+    std::optional<unsigned int> maxAttempts;
+
+    // The delay after the given number of failed attempts
+    std::chrono::seconds delay(unsigned int attempts) const;
+};
+
 class MCTPReactor : public std::enable_shared_from_this<MCTPReactor>
 {
     using MCTPDeviceFactory = std::function<std::shared_ptr<MCTPDevice>(
@@ -26,7 +70,10 @@ class MCTPReactor : public std::enable_shared_from_this<MCTPReactor>
     MCTPReactor() = delete;
     MCTPReactor(const MCTPReactor&) = delete;
     MCTPReactor(MCTPReactor&&) = delete;
-    explicit MCTPReactor(AssociationServer& server) : server(server) {}
+    explicit MCTPReactor(AssociationServer& server,
+                         EndpointHealthServer* healthServer = nullptr) :
+        server(server), healthServer(healthServer)
+    {}
     ~MCTPReactor() = default;
     MCTPReactor& operator=(const MCTPReactor&) = delete;
     MCTPReactor& operator=(MCTPReactor&&) = delete;
@@ -37,17 +84,62 @@ class MCTPReactor : public std::enable_shared_from_this<MCTPReactor>
                           const std::shared_ptr<MCTPDevice>& device);
     void unmanageMCTPDevice(const std::string& path);
 
+    // Endpoints of the device at the inventory path are logged once they have
+    // been degraded for longer than period, or not at all without one
+    void setDegradedLogPeriod(const std::string& path,
+                              std::optional<std::chrono::seconds> period);
+
+    // The setup of the device at the inventory path is retried with the
+    // policy, or with the default policy without one
+    void setRetryPolicy(const std::string& path,
+                        std::optional<RetryPolicy> policy);
+
   private:
+    using Clock = std::chrono::steady_clock;
+
+    struct TrackedHealth
+    {
+        std::string inventory;
+        // With the time of completed degraded stretches
+        EndpointHealth health;
+        std::optional<Clock::time_point> degradedSince;
+        bool logged = false;
+    };
+
+    struct Deferral
+    {
+        unsigned int attempts = 0;
+        bool abandoned = false;
+        Clock::time_point due;
+        // A setup attempt is in flight
+        bool pending = false;
+    };
+
     static std::optional<std::string> findSMBusInterface(int bus);
 
     AssociationServer& server;
+    EndpointHealthServer* healthServer;
     MCTPDeviceRepository devices;
 
+    // By endpoint path
+    std::map<std::string, TrackedHealth> endpointHealth;
+    // By inventory path
+    std::map<std::string, std::chrono::seconds> degradedLogPeriods;
+    // By inventory path
+    std::map<std::string, RetryPolicy> retryPolicies;
+
     // Tracks MCTP devices that have failed their setup
-    std::set<std::shared_ptr<MCTPDevice>> deferred;
+    std::map<std::shared_ptr<MCTPDevice>, Deferral> deferred;
 
     void deferSetup(const std::shared_ptr<MCTPDevice>& dev);
+    void setupFailed(const std::shared_ptr<MCTPDevice>& dev,
+                     const std::string& reason);
+    void setupCompleted(const std::shared_ptr<MCTPDevice>& dev);
     void setupEndpoint(const std::shared_ptr<MCTPDevice>& dev);
     void trackEndpoint(const std::shared_ptr<MCTPEndpoint>& ep);
     void untrackEndpoint(const std::shared_ptr<MCTPEndpoint>& ep);
+    void endpointDegraded(const std::shared_ptr<MCTPEndpoint>& ep);
+    void endpointAvailable(const std::shared_ptr<MCTPEndpoint>& ep);
+    void publishHealth(const std::string& path, const TrackedHealth& tracked,
+                       Clock::time_point now);
 };
@@ -1,6 +1,8 @@
 #include "MCTPEndpoint.hpp"
 #include "MCTPReactor.hpp"
+#include "Reactor.hpp"
 #include "Utils.hpp"
+#include "VariantVisitors.hpp"
 
 #include <boost/asio/io_context.hpp>
 #include <boost/asio/post.hpp>
@@ -14,7 +16,7 @@
 #include <sdbusplus/message/native_types.hpp>
 
 #include <chrono>
-#include <cstdlib>
+#include <cstdint>
 #include <format>
 #include <functional>
 #include <map>
@@ -22,11 +24,16 @@
 #include <optional>
 #include <set>
 #include <stdexcept>
+#include <string>
 #include <system_error>
+#include <variant>
 #include <vector>
 
 PHOSPHOR_LOG2_USING;
 
+namespace mctpreactor
+{
+
 class DBusAssociationServer : public AssociationServer
 {
   public:
@@ -37,7 +44,7 @@ class DBusAssociationServer : public AssociationServer
         const std::shared_ptr<sdbusplus::asio::connection>& connection) :
         server(connection)
     {
-        server.add_manager("/xyz/openbmc_project/mctp");
+        reactor::addManager(server, "/xyz/openbmc_project/mctp");
     }
     ~DBusAssociationServer() override = default;
     DBusAssociationServer& operator=(const DBusAssociationServer&) = delete;
@@ -73,6 +80,207 @@ class DBusAssociationServer : public AssociationServer
         objects;
 };
 
+class DBusEndpointHealthServer : public EndpointHealthServer
+{
+  public:
+    DBusEndpointHealthServer() = delete;
+    DBusEndpointHealthServer(const DBusEndpointHealthServer&) = delete;
+    DBusEndpointHealthServer(DBusEndpointHealthServer&&) = delete;
+    DBusEndpointHealthServer(
+        const std::shared_ptr<sdbusplus::asio::connection>& connection,
+        sdbusplus::asio::object_server& server) :
+        connection(connection), server(server)
+    {}
+    ~DBusEndpointHealthServer() override = default;
+    DBusEndpointHealthServer&
+        operator=(const DBusEndpointHealthServer&) = delete;
+    DBusEndpointHealthServer& operator=(DBusEndpointHealthServer&&) = delete;
+
+    void publish(const std::string& path,
+                 const EndpointHealth& health) override
+    {
+        auto entry = objects.find(path);
+        if (entry == objects.end())
+        {
+            Interfaces added{
+                server.add_interface(path, operationalInterface),
+                server.add_interface(path, availabilityInterface)};
+            added.operational->register_property("Functional",
+                                                 health.available);
+            added.availability->register_property("Available",
+                                                  health.available);
+            added.operational->initialize();
+            added.availability->initialize();
+            objects.emplace(path, added);
+            return;
+        }
+
+        Interfaces& ifaces = entry->second;
+        ifaces.operational->set_property("Functional", health.available);
+        ifaces.availability->set_property("Available", health.available);
+    }
+
+    void withdraw(const std::string& path) override
+    {
+        auto entry = objects.find(path);
+        if (entry == objects.end())
+        {
+            return;
+        }
+        server.remove_interface(entry->second.operational);
+        server.remove_interface(entry->second.availability);
+        objects.erase(entry);
+    }
+
+    void logDegraded(const std::string& path, const std::string& inventory,
+                     std::chrono::seconds degraded) override
+    {
+        warning(
+            "MCTP endpoint at '{MCTP_ENDPOINT}' has been degraded for {SECONDS} seconds",
+            "MCTP_ENDPOINT", path, "SECONDS", degraded.count());
+
+        std::map<std::string, std::string> additionalData = {
+            {"ENDPOINT_PATH", path},
+            {"INVENTORY_PATH", inventory},
+            {"DEGRADED_SECONDS", std::to_string(degraded.count())}};
+        connection->async_method_call(
+            [path](const boost::system::error_code& ec,
+                   const sdbusplus::message::object_path& /*entry*/) {
+                if (ec)
+                {
+                    error(
+                        "Failed to log degraded MCTP endpoint '{MCTP_ENDPOINT}': {ERROR_MESSAGE}",
+                        "MCTP_ENDPOINT", path, "ERROR_MESSAGE", ec.message());
+                }
+            },
+            "xyz.openbmc_project.Logging", "/xyz/openbmc_project/logging",
+            "xyz.openbmc_project.Logging.Create", "Create",
+            "xyz.openbmc_project.Common.Error.Unavailable",
+            "xyz.openbmc_project.Logging.Entry.Level.Warning", additionalData);
+    }
+
+  private:
+    static constexpr const char* operationalInterface =
+        "xyz.openbmc_project.State.Decorator.OperationalStatus";
+    static constexpr const char* availabilityInterface =
+        "xyz.openbmc_project.State.Decorator.Availability";
+
+    struct Interfaces
+    {
+        std::shared_ptr<sdbusplus::asio::dbus_interface> operational;
+        std::shared_ptr<sdbusplus::asio::dbus_interface> availability;
+    };
+
+    std::shared_ptr<sdbusplus::asio::connection> connection;
+    sdbusplus::asio::object_server& server;
+    std::map<std::string, Interfaces> objects;
+};
+
+// The configuration record of whichever MCTP device type the config holds
+static std::optional<SensorBaseConfigMap>
+    matchDeviceConfig(const SensorData& config)
+{
+    std::optional<SensorBaseConfigMap> iface;
+    // NOLINTBEGIN(bugprone-assignment-in-if-condition)
+    if ((iface = I2CMCTPDDevice::match(config)) ||
+        (iface = I3CMCTPDDevice::match(config)) ||
+        (iface = PCIeVDMMCTPDDevice::match(config)))
+    {
+        return iface;
+    }
+    // NOLINTEND(bugprone-assignment-in-if-condition)
+    return std::nullopt;
+}
+
+// The "DegradedLogSeconds" of a device's configuration, after which an
+// endpoint that stays degraded is logged
+static std::optional<std::chrono::seconds>
+    degradedLogPeriodFromConfig(const SensorData& config)
+{
+    std::optional<SensorBaseConfigMap> iface = matchDeviceConfig(config);
+    if (!iface)
+    {
+        return std::nullopt;
+    }
+
+    auto period = iface->find("DegradedLogSeconds");
+    if (period == iface->end())
+    {
+        return std::nullopt;
+    }
+
+    try
+    {
+        return std::chrono::seconds(
+            std::visit(VariantToUnsignedIntVisitor(), period->second));
+    }
+    catch (const std::invalid_argument& ex)
+    {
+        error("Invalid DegradedLogSeconds: {EXCEPTION}", "EXCEPTION", ex);
+        return std::nullopt;
+    }
+}
+
+// The retry policy of a device's configuration, from the "Retry*" members
+// that override the defaults
+static std::optional<RetryPolicy>
+    retryPolicyFromConfig(const SensorData& config)
+{
+    std::optional<SensorBaseConfigMap> iface = matchDeviceConfig(config);
+    if (!iface)
+    {
+        return std::nullopt;
+    }
+
+    RetryPolicy policy;
+    bool configured = false;
+    try
+    {
+        if (auto it = iface->find("RetryInitialSeconds"); it != iface->end())
+        {
+            policy.initialDelay = std::chrono::seconds(
+                std::visit(VariantToUnsignedIntVisitor(), it->second));
+            configured = true;
+        }
+        if (auto it = iface->find("RetryBackoffFactor"); it != iface->end())
+        {
+            policy.backoffFactor =
+                std::visit(VariantToDoubleVisitor(), it->second);
+            configured = true;
+        }
+        if (auto it = iface->find("RetryMaxSeconds"); it != iface->end())
+        {
+            policy.maxDelay = std::chrono::seconds(
+                std::visit(VariantToUnsignedIntVisitor(), it->second));
+            configured = true;
+        }
+        if (auto it = iface->find("RetryMaxAttempts"); it != iface->end())
+        {
+            policy.maxAttempts =
+                std::visit(VariantToUnsignedIntVisitor(), it->second);
+            configured = true;
+        }
+    }
+    catch (const std::invalid_argument& ex)
+    {
+        error("Invalid retry policy: {EXCEPTION}", "EXCEPTION", ex);
+        return std::nullopt;
+    }
+
+    if (policy.backoffFactor < 1.0)
+    {
+        error("Invalid RetryBackoffFactor {FACTOR}, must be at least 1",
+              "FACTOR", policy.backoffFactor);
+        return std::nullopt;
+    }
+
+    if (!configured)
+    {
+        return std::nullopt;
+    }
+    return policy;
+}
+
 static std::shared_ptr<MCTPDevice> deviceFromConfig(
     const std::shared_ptr<sdbusplus::asio::connection>& connection,
     const SensorData& config)
@@ -85,6 +293,16 @@ static std::shared_ptr<MCTPDevice> deviceFromConfig(
         {
             return I2CMCTPDDevice::from(connection, *iface);
         }
+        // NOLINTNEXTLINE(bugprone-assignment-in-if-condition)
+        if ((iface = I3CMCTPDDevice::match(config)))
+        {
+            return I3CMCTPDDevice::from(connection, *iface);
+        }
+        // NOLINTNEXTLINE(bugprone-assignment-in-if-condition)
+        if ((iface = PCIeVDMMCTPDDevice::match(config)))
+        {
+            return PCIeVDMMCTPDDevice::from(connection, *iface);
+        }
     }
     catch (const std::invalid_argument& ex)
     {
@@ -102,7 +320,11 @@ static void addInventory(
           exposed] = msg.unpack<sdbusplus::message::object_path, SensorData>();
     try
     {
+        // Ahead of the device, so its first failed setup follows the policy
+        reactor->setRetryPolicy(path, retryPolicyFromConfig(exposed));
         reactor->manageMCTPDevice(path, deviceFromConfig(connection, exposed));
+        reactor->setDegradedLogPeriod(path,
+                                      degradedLogPeriodFromConfig(exposed));
     }
     catch (const std::logic_error& e)
     {
@@ -125,9 +347,12 @@ static void removeInventory(const std::shared_ptr<MCTPReactor>& reactor,
         msg.unpack<sdbusplus::message::object_path, std::set<std::string>>();
     try
     {
-        if (I2CMCTPDDevice::match(removed))
+        if (I2CMCTPDDevice::match(removed) || I3CMCTPDDevice::match(removed) ||
+            PCIeVDMMCTPDDevice::match(removed))
         {
             reactor->unmanageMCTPDevice(path.str);
+            reactor->setDegradedLogPeriod(path.str, std::nullopt);
+            reactor->setRetryPolicy(path.str, std::nullopt);
         }
     }
     catch (const std::logic_error& e)
@@ -152,8 +377,11 @@ static void manageMCTPEntity(
     {
         try
         {
+            reactor->setRetryPolicy(path, retryPolicyFromConfig(config));
             reactor->manageMCTPDevice(path,
                                       deviceFromConfig(connection, config));
+            reactor->setDegradedLogPeriod(path,
+                                          degradedLogPeriodFromConfig(config));
         }
         catch (const std::logic_error& e)
         {
@@ -170,25 +398,27 @@ static void manageMCTPEntity(
     }
 }
 
-static void exitReactor(boost::asio::io_context* io, sdbusplus::message_t& msg)
+static void exitReactor(sdbusplus::message_t& msg)
 {
     auto name = msg.unpack<std::string>();
     info("Shutting down mctpreactor, lost dependency '{SERVICE_NAME}'",
          "SERVICE_NAME", name);
-    io->stop();
+    reactor::fail("mctp");
 }
 
-int main()
+static void start(boost::asio::io_context& io,
+                  std::shared_ptr<sdbusplus::asio::connection>& systemBus,
+                  sdbusplus::asio::object_server& objectServer)
 {
     constexpr std::chrono::seconds period(5);
 
-    boost::asio::io_context io;
-    auto systemBus = std::make_shared<sdbusplus::asio::connection>(io);
-    DBusAssociationServer associationServer(systemBus);
-    auto reactor = std::make_shared<MCTPReactor>(associationServer);
-    boost::asio::steady_timer clock(io);
+    static DBusAssociationServer associationServer(systemBus);
+    static DBusEndpointHealthServer healthServer(systemBus, objectServer);
+    static auto reactor =
+        std::make_shared<MCTPReactor>(associationServer, &healthServer);
+    static boost::asio::steady_timer clock(io);
 
-    std::function<void(const boost::system::error_code&)> alarm =
+    static std::function<void(const boost::system::error_code&)> alarm =
         [&](const boost::system::error_code& ec) {
             if (ec)
             {
@@ -206,23 +436,23 @@ int main()
     const std::string entityManagerNameLostSpec =
         rules::nameOwnerChanged("xyz.openbmc_project.EntityManager");
 
-    auto entityManagerNameLostMatch = sdbusplus::bus::match_t(
+    static auto entityManagerNameLostMatch = sdbusplus::bus::match_t(
         static_cast<sdbusplus::bus_t&>(*systemBus), entityManagerNameLostSpec,
-        std::bind_front(exitReactor, &io));
+        exitReactor);
 
     const std::string mctpdNameLostSpec =
         rules::nameOwnerChanged("xyz.openbmc_project.MCTP");
 
-    auto mctpdNameLostMatch = sdbusplus::bus::match_t(
+    static auto mctpdNameLostMatch = sdbusplus::bus::match_t(
         static_cast<sdbusplus::bus_t&>(*systemBus), mctpdNameLostSpec,
-        std::bind_front(exitReactor, &io));
+        exitReactor);
 
     const std::string interfacesRemovedMatchSpec =
         rules::sender("xyz.openbmc_project.EntityManager") +
         // Trailing slash on path: Listen for signals on the inventory subtree
         rules::interfacesRemovedAtPath("/xyz/openbmc_project/inventory/");
 
-    auto interfacesRemovedMatch = sdbusplus::bus::match_t(
+    static auto interfacesRemovedMatch = sdbusplus::bus::match_t(
         static_cast<sdbusplus::bus_t&>(*systemBus), interfacesRemovedMatchSpec,
         std::bind_front(removeInventory, reactor));
 
@@ -231,7 +461,7 @@ int main()
         // Trailing slash on path: Listen for signals on the inventory subtree
         rules::interfacesAddedAtPath("/xyz/openbmc_project/inventory/");
 
-    auto interfacesAddedMatch = sdbusplus::bus::match_t(
+    static auto interfacesAddedMatch = sdbusplus::bus::match_t(
         static_cast<sdbusplus::bus_t&>(*systemBus), interfacesAddedMatchSpec,
         std::bind_front(addInventory, systemBus, reactor));
 
@@ -240,10 +470,11 @@ int main()
     boost::asio::post(io, [reactor, systemBus]() {
         auto gsc = std::make_shared<GetSensorConfiguration>(
             systemBus, std::bind_front(manageMCTPEntity, systemBus, reactor));
-        gsc->getConfiguration({"MCTPI2CTarget"});
+        gsc->getConfiguration(
+            {"MCTPI2CTarget", "MCTPI3CTarget", "MCTPPCIeTarget"});
     });
+}
 
-    io.run();
+static const reactor::Registration registration("mctp", start);
 
-    return EXIT_SUCCESS;
-}
+} // namespace mctpreactor
@@ -16,6 +16,7 @@
 
 #include "MCUTempSensor.hpp"
 
+#include "Reactor.hpp"
 #include "SensorPaths.hpp"
 #include "Thresholds.hpp"
 #include "Utils.hpp"
@@ -59,7 +60,8 @@ constexpr const char* sensorType = "MCUTempSensor";
 static constexpr double mcuTempMaxReading = 0xFF;
 static constexpr double mcuTempMinReading = 0;
 
-boost::container::flat_map<std::string, std::unique_ptr<MCUTempSensor>> sensors;
+static boost::container::flat_map<std::string, std::unique_ptr<MCUTempSensor>>
+    sensors;
 
 MCUTempSensor::MCUTempSensor(
     std::shared_ptr<sdbusplus::asio::connection>& conn,
@@ -265,6 +267,7 @@ void createSensors(
                         tempReg);
 
                     sensor->init();
+                    sensor->loadConfigurationRecords(interfaces);
                 }
             }
         },
@@ -272,12 +275,14 @@ void createSensors(
         "org.freedesktop.DBus.ObjectManager", "GetManagedObjects");
 }
 
-int main()
+namespace mcutempsensor
 {
-    boost::asio::io_context io;
-    auto systemBus = std::make_shared<sdbusplus::asio::connection>(io);
-    sdbusplus::asio::object_server objectServer(systemBus, true);
-    objectServer.add_manager("/xyz/openbmc_project/sensors");
+
+static void start(boost::asio::io_context& io,
+                  std::shared_ptr<sdbusplus::asio::connection>& systemBus,
+                  sdbusplus::asio::object_server& objectServer)
+{
+    reactor::addManager(objectServer, "/xyz/openbmc_project/sensors");
 
     systemBus->request_name("xyz.openbmc_project.MCUTempSensor");
 
@@ -285,9 +290,9 @@ int main()
         createSensors(io, objectServer, sensors, systemBus);
     });
 
-    boost::asio::steady_timer configTimer(io);
+    static boost::asio::steady_timer configTimer(io);
 
-    std::function<void(sdbusplus::message_t&)> eventHandler =
+    static std::function<void(sdbusplus::message_t&)> eventHandler =
         [&](sdbusplus::message_t&) {
             configTimer.expires_after(std::chrono::seconds(1));
             // create a timer because normally multiple properties change
@@ -310,10 +315,12 @@ int main()
             });
         };
 
-    std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
+    static std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
         setupPropertiesChangedMatches(
             *systemBus, std::to_array<const char*>({sensorType}), eventHandler);
     setupManufacturingModeMatch(*systemBus);
-    io.run();
-    return 0;
 }
+
+static const reactor::Registration registration("mcu", start);
+
+} // namespace mcutempsensor
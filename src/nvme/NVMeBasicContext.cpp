@@ -1,6 +1,7 @@
 #include "NVMeBasicContext.hpp"
 
 #include "NVMeContext.hpp"
+#include "NVMeMI.hpp"
 #include "NVMeSensor.hpp"
 
 #include <endian.h>
@@ -18,7 +19,6 @@
 #include <array>
 #include <cerrno>
 #include <chrono>
-#include <cmath>
 #include <cstdint>
 #include <cstdio>
 #include <cstring>
@@ -26,8 +26,9 @@
 #include <ios>
 #include <iostream>
 #include <iterator>
-#include <limits>
 #include <memory>
+#include <optional>
+#include <span>
 #include <stdexcept>
 #include <string>
 #include <system_error>
@@ -47,6 +48,13 @@ extern "C"
  * https://nvmexpress.org/wp-content/uploads/NVMe_Management_-_Technical_Note_on_Basic_Management_Command.pdf
  */
 
+/* How long the worker may spend on one query before it's given up on */
+static constexpr auto queryTimeout = std::chrono::seconds(2);
+
+/* Command codes of the status and the vendor blocks */
+static constexpr uint8_t basicStatusCommand = 0x00;
+static constexpr uint8_t basicIdentityCommand = 0x08;
+
 static std::shared_ptr<std::array<uint8_t, 6>>
     encodeBasicQuery(int bus, uint8_t device, uint8_t offset)
 {
@@ -205,7 +213,8 @@ static ssize_t processBasicQueryStream(FileHandle& in, FileHandle& out)
 /* Throws std::error_code on failure */
 /* FIXME: Probably shouldn't do fallible stuff in a constructor */
 NVMeBasicContext::NVMeBasicContext(boost::asio::io_context& io, int rootBus) :
-    NVMeContext::NVMeContext(io, rootBus), io(io), reqStream(io), respStream(io)
+    NVMeContext::NVMeContext(io, rootBus), io(io), reqStream(io),
+    respStream(io), pending(queryTimeout), queryTimer(io)
 {
     std::array<int, 2> responsePipe{};
     std::array<int, 2> requestPipe{};
@@ -269,8 +278,14 @@ void NVMeBasicContext::readAndProcessNVMeSensor()
 
     if (!sensor->readingStateGood())
     {
-        sensor->markAvailable(false);
-        sensor->updateValue(std::numeric_limits<double>::quiet_NaN());
+        sensor->markUnavailable();
+        readAndProcessNVMeSensor();
+        return;
+    }
+
+    /* Leave empty bays alone */
+    if (!sensor->checkPresence())
+    {
         readAndProcessNVMeSensor();
         return;
     }
@@ -282,7 +297,47 @@ void NVMeBasicContext::readAndProcessNVMeSensor()
         return;
     }
 
-    auto command = encodeBasicQuery(sensor->bus, sensor->address, 0x00);
+    if (sensor->needsIdentity())
+    {
+        /* Identify the drive first, then carry on with its status */
+        query(sensor, basicIdentityCommand,
+              [sensor](NVMeBasicContext& self, std::vector<uint8_t>& data) {
+                  std::optional<nvmemi::DriveIdentity> identity =
+                      nvmemi::decodeBasicIdentity(data);
+                  if (identity)
+                  {
+                      sensor->updateIdentity(*identity);
+                  }
+                  else
+                  {
+                      sensor->identityFailed();
+                  }
+
+                  self.queryStatus(sensor);
+              });
+        return;
+    }
+
+    queryStatus(sensor);
+}
+
+void NVMeBasicContext::queryStatus(const std::shared_ptr<NVMeSensor>& sensor)
+{
+    query(sensor, basicStatusCommand,
+          [sensor](NVMeBasicContext& self,
+                   std::vector<uint8_t>& data) mutable {
+              /* Update the sensor */
+              self.processResponse(sensor, data.data(), data.size());
+
+              /* Enqueue processing of the next sensor */
+              self.readAndProcessNVMeSensor();
+          });
+}
+
+void NVMeBasicContext::query(const std::shared_ptr<NVMeSensor>& sensor,
+                             uint8_t offset, Response&& handler)
+{
+    auto command = encodeBasicQuery(sensor->bus, sensor->address, offset);
 
     /* Issue the request */
     boost::asio::async_write(
@@ -294,6 +349,67 @@ void NVMeBasicContext::readAndProcessNVMeSensor()
             }
         });
 
+    pending.push({sensor, std::move(handler)},
+                 std::chrono::steady_clock::now());
+    armQueryTimer();
+
+    if (!reading)
+    {
+        reading = true;
+        readResponse();
+    }
+}
+
+void NVMeBasicContext::armQueryTimer()
+{
+    /*
+     * Every query is timed, including those queued behind one the worker is
+     * stuck on, so a hung drive can't stall the polling of the others.
+     */
+    std::optional<std::chrono::steady_clock::time_point> deadline =
+        pending.nextDeadline();
+    if (!deadline)
+    {
+        queryTimer.cancel();
+        return;
+    }
+
+    queryTimer.expires_at(*deadline);
+    queryTimer.async_wait([weakSelf{weak_from_this()}](
+                              const boost::system::error_code& ec) {
+        if (ec)
+        {
+            return;
+        }
+
+        auto self = std::static_pointer_cast<NVMeBasicContext>(weakSelf.lock());
+        if (!self)
+        {
+            return;
+        }
+
+        /*
+         * The worker can't be interrupted, so the responses are dropped when
+         * they eventually arrive. Carry on with the other drives meanwhile.
+         */
+        bool expired = self->pending.expire(
+            std::chrono::steady_clock::now(), [](PendingQuery& query) {
+                std::cerr << "Timed out querying NVMe device 0x" << std::hex
+                          << static_cast<int>(query.sensor->address)
+                          << " on bus " << std::dec << query.sensor->bus
+                          << "\n";
+                query.sensor->markUnresponsive();
+            });
+        self->armQueryTimer();
+        if (expired)
+        {
+            self->readAndProcessNVMeSensor();
+        }
+    });
+}
+
+void NVMeBasicContext::readResponse()
+{
     auto response = std::make_shared<boost::asio::streambuf>();
     response->prepare(1);
 
@@ -336,8 +452,8 @@ void NVMeBasicContext::readAndProcessNVMeSensor()
             response->prepare(len);
             return len;
         },
-        [weakSelf{weak_from_this()}, sensor, response](
-            const boost::system::error_code& ec, std::size_t length) mutable {
+        [weakSelf{weak_from_this()}, response](
+            const boost::system::error_code& ec, std::size_t length) {
             if (ec)
             {
                 std::cerr << "Got error reading basic query: " << ec << "\n";
@@ -350,23 +466,46 @@ void NVMeBasicContext::readAndProcessNVMeSensor()
                 return;
             }
 
-            if (auto self = weakSelf.lock())
+            auto self =
+                std::static_pointer_cast<NVMeBasicContext>(weakSelf.lock());
+            if (!self)
+            {
+                return;
+            }
+
+            if (self->pending.empty())
             {
-                /* Deserialise the response */
-                response->consume(1); /* Drop the length byte */
-                std::istream is(response.get());
-                std::vector<char> data(response->size());
-                is.read(data.data(), response->size());
+                std::cerr << "Dropping unsolicited basic query response\n";
+                self->readResponse();
+                return;
+            }
 
-                /* Update the sensor */
-                self->processResponse(sensor, data.data(), data.size());
+            /* Responses arrive in the order the queries were issued */
+            std::optional<PendingQuery> query = self->pending.complete();
+            self->armQueryTimer();
+            self->readResponse();
 
-                /* Enqueue processing of the next sensor */
-                self->readAndProcessNVMeSensor();
+            if (!query)
+            {
+                return;
             }
+
+            /* Deserialise the response */
+            response->consume(1); /* Drop the length byte */
+            std::vector<uint8_t> data(response->size());
+            boost::asio::buffer_copy(boost::asio::buffer(data),
+                                     response->data());
+
+            query->response(*self, data);
         });
 }
 
+void NVMeBasicContext::close()
+{
+    NVMeContext::close();
+    queryTimer.cancel();
+}
+
 void NVMeBasicContext::pollNVMeDevices()
 {
     pollCursor = sensors.begin();
@@ -392,44 +531,40 @@ void NVMeBasicContext::pollNVMeDevices()
     });
 }
 
-static double getTemperatureReading(int8_t reading)
+void NVMeBasicContext::processResponse(std::shared_ptr<NVMeSensor>& sensor,
+                                       void* msg, size_t len)
 {
-    if (reading == static_cast<int8_t>(0x80) ||
-        reading == static_cast<int8_t>(0x81))
+    if (msg == nullptr)
     {
-        // 0x80 = No temperature data or temperature data is more the 5 s
-        // old 0x81 = Temperature sensor failure
-        return std::numeric_limits<double>::quiet_NaN();
+        sensor->incrementHealthError();
+        return;
     }
 
-    return reading;
-}
-
-void NVMeBasicContext::processResponse(std::shared_ptr<NVMeSensor>& sensor,
-                                       void* msg, size_t len)
-{
-    if (msg == nullptr || len < 6)
+    std::optional<nvmemi::SubsystemHealth> health = nvmemi::decodeBasicStatus(
+        std::span<const uint8_t>(static_cast<uint8_t*>(msg), len));
+    if (!health)
     {
-        sensor->incrementError();
+        /* The drive may have been swapped by the time it answers again */
+        sensor->forgetIdentity();
+        sensor->incrementHealthError();
         return;
     }
 
-    uint8_t* messageData = static_cast<uint8_t*>(msg);
+    sensor->updateHealth(*health);
 
-    uint8_t status = messageData[0];
-    if (((status & NVME_MI_BASIC_SFLGS_DRIVE_NOT_READY) != 0) ||
-        ((status & NVME_MI_BASIC_SFLGS_DRIVE_FUNCTIONAL) == 0))
+    if (((health->status & NVME_MI_BASIC_SFLGS_DRIVE_NOT_READY) != 0) ||
+        ((health->status & NVME_MI_BASIC_SFLGS_DRIVE_FUNCTIONAL) == 0))
     {
         sensor->markFunctional(false);
         return;
     }
 
-    double value = getTemperatureReading(messageData[2]);
-    if (!std::isfinite(value))
+    std::optional<double> value = health->temperature;
+    if (!value)
     {
         sensor->incrementError();
         return;
     }
 
-    sensor->updateValue(value);
+    sensor->updateValue(*value);
 }
@@ -1,27 +1,55 @@
 #pragma once
 
 #include "NVMeContext.hpp"
+#include "NVMeQueryQueue.hpp"
 
 #include <boost/asio/io_context.hpp>
 #include <boost/asio/posix/stream_descriptor.hpp>
+#include <boost/asio/steady_timer.hpp>
 
+#include <cstdint>
+#include <functional>
+#include <memory>
 #include <thread>
+#include <vector>
 
+/*
+ * Polls drives with the SMBus Basic Management Command from a worker thread,
+ * one per root bus. The channels of a mux share the worker, so a query that
+ * hangs is given up on after a timeout rather than holding up the others.
+ */
 class NVMeBasicContext : public NVMeContext
 {
   public:
     NVMeBasicContext(boost::asio::io_context& io, int rootBus);
     ~NVMeBasicContext() override = default;
+    void close() override;
     void pollNVMeDevices() override;
     void readAndProcessNVMeSensor() override;
     void processResponse(std::shared_ptr<NVMeSensor>& sensor, void* msg,
                          size_t len) override;
 
   private:
+    using Response =
+        std::function<void(NVMeBasicContext&, std::vector<uint8_t>&)>;
+
+    struct PendingQuery
+    {
+        std::shared_ptr<NVMeSensor> sensor;
+        Response response;
+    };
+
     NVMeBasicContext(boost::asio::io_context& io, int rootBus, int cmdOut,
                      int streamIn, int streamOut, int cmdIn);
     boost::asio::io_context& io;
 
+    // Issues a Basic Management Command read from the command code `offset`
+    void query(const std::shared_ptr<NVMeSensor>& sensor, uint8_t offset,
+               Response&& response);
+    void queryStatus(const std::shared_ptr<NVMeSensor>& sensor);
+    void armQueryTimer();
+    void readResponse();
+
     // The IO thread must be destructed after the stream descriptors, so
     // initialise it first. http://eel.is/c++draft/class.base.init#note-6
     //
@@ -40,6 +68,11 @@ class NVMeBasicContext : public NVMeContext
     boost::asio::posix::stream_descriptor reqStream;
     boost::asio::posix::stream_descriptor respStream;
 
+    // Queries written to the worker and not yet answered
+    NVMeQueryQueue<PendingQuery> pending;
+    boost::asio::steady_timer queryTimer;
+    bool reading = false;
+
     enum
     {
         NVME_MI_BASIC_SFLGS_DRIVE_NOT_READY = 0x40,
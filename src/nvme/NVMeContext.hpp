@@ -7,6 +7,7 @@
 
 #include <memory>
 #include <stdexcept>
+#include <utility>
 
 class NVMeContext : public std::enable_shared_from_this<NVMeContext>
 {
@@ -104,6 +105,18 @@ class NVMeContext : public std::enable_shared_from_this<NVMeContext>
     std::list<std::shared_ptr<NVMeSensor>>::iterator pollCursor;
 };
 
-using NVMEMap = boost::container::flat_map<int, std::shared_ptr<NVMeContext>>;
+// How a drive is talked to, from the "Protocol" of its configuration
+enum class NVMeProtocol
+{
+    // The SMBus Basic Management Command
+    basic,
+    // NVMe-MI messages over MCTP
+    mctp,
+};
+
+// One context per protocol and root bus
+using NVMEMap =
+    boost::container::flat_map<std::pair<NVMeProtocol, int>,
+                               std::shared_ptr<NVMeContext>>;
 
 NVMEMap& getNVMEMap();
@@ -0,0 +1,439 @@
+#include "NVMeMCTPContext.hpp"
+
+#include "NVMeContext.hpp"
+#include "NVMeMI.hpp"
+#include "NVMeSensor.hpp"
+#include "Utils.hpp"
+
+#include <linux/mctp.h>
+#include <sys/socket.h>
+
+#include <boost/asio/error.hpp>
+#include <boost/asio/io_context.hpp>
+#include <boost/asio/posix/stream_descriptor.hpp>
+#include <sdbusplus/asio/connection.hpp>
+#include <sdbusplus/message/native_types.hpp>
+
+#include <algorithm>
+#include <cerrno>
+#include <chrono>
+#include <cstdint>
+#include <cstring>
+#include <iostream>
+#include <limits>
+#include <memory>
+#include <optional>
+#include <span>
+#include <stdexcept>
+#include <string>
+#include <utility>
+#include <variant>
+#include <vector>
+
+static constexpr const char* mctpEndpointPrefix = "/xyz/openbmc_project/mctp/";
+static constexpr const char* associationInterface =
+    "xyz.openbmc_project.Association";
+static constexpr auto responseTimeout = std::chrono::seconds(1);
+
+std::optional<NVMeMCTPEndpoint> endpointFromPath(const std::string& path)
+{
+    if (!path.starts_with(mctpEndpointPrefix))
+    {
+        return std::nullopt;
+    }
+    sdbusplus::message::object_path objectPath(path);
+    std::string eid = objectPath.filename();
+    std::string network = objectPath.parent_path().filename();
+    try
+    {
+        size_t eidEnd = 0;
+        size_t networkEnd = 0;
+        unsigned long eidValue = std::stoul(eid, &eidEnd);
+        unsigned long networkValue = std::stoul(network, &networkEnd);
+        if (eidEnd != eid.size() || networkEnd != network.size() ||
+            eidValue > std::numeric_limits<uint8_t>::max() ||
+            networkValue > std::numeric_limits<unsigned int>::max())
+        {
+            return std::nullopt;
+        }
+        return NVMeMCTPEndpoint{static_cast<unsigned int>(networkValue),
+                                static_cast<uint8_t>(eidValue)};
+    }
+    catch (const std::logic_error&)
+    {
+        return std::nullopt;
+    }
+}
+
+NVMeMCTPContext::NVMeMCTPContext(
+    boost::asio::io_context& io, int rootBus,
+    std::shared_ptr<sdbusplus::asio::connection> conn) :
+    NVMeContext::NVMeContext(io, rootBus), dbusConnection(std::move(conn)),
+    socket(io), responseTimer(io)
+{
+    int fd = ::socket(AF_MCTP, SOCK_DGRAM | SOCK_NONBLOCK | SOCK_CLOEXEC, 0);
+    if (fd < 0)
+    {
+        std::cerr << "Failed to open MCTP socket: " << strerror(errno) << "\n";
+        return;
+    }
+    socket.assign(fd);
+}
+
+void NVMeMCTPContext::setEndpoint(const std::string& configurationPath,
+                                  const NVMeMCTPEndpoint& endpoint)
+{
+    staticEndpoints[configurationPath] = endpoint;
+}
+
+void NVMeMCTPContext::close()
+{
+    NVMeContext::close();
+    responseTimer.cancel();
+    socket.cancel();
+}
+
+void NVMeMCTPContext::pollNVMeDevices()
+{
+    pollCursor = sensors.begin();
+
+    scanTimer.expires_after(std::chrono::seconds(1));
+    scanTimer.async_wait([weakSelf{weak_from_this()}](
+                             const boost::system::error_code errorCode) {
+        if (errorCode == boost::asio::error::operation_aborted)
+        {
+            return;
+        }
+
+        if (errorCode)
+        {
+            std::cerr << errorCode.message() << "\n";
+            return;
+        }
+
+        if (auto self = weakSelf.lock())
+        {
+            self->readAndProcessNVMeSensor();
+        }
+    });
+}
+
+void NVMeMCTPContext::readAndProcessNVMeSensor()
+{
+    if (pollCursor == sensors.end())
+    {
+        this->pollNVMeDevices();
+        return;
+    }
+
+    std::shared_ptr<NVMeSensor> sensor = *pollCursor++;
+
+    if (!sensor->readingStateGood())
+    {
+        sensor->markUnavailable();
+        readAndProcessNVMeSensor();
+        return;
+    }
+
+    /* Leave empty bays alone */
+    if (!sensor->checkPresence())
+    {
+        readAndProcessNVMeSensor();
+        return;
+    }
+
+    /* Potentially defer sampling the sensor sensor if it is in error */
+    if (!sensor->sample())
+    {
+        readAndProcessNVMeSensor();
+        return;
+    }
+
+    auto findStatic = staticEndpoints.find(sensor->configurationPath);
+    if (findStatic != staticEndpoints.end())
+    {
+        queryHealth(sensor, findStatic->second);
+        return;
+    }
+
+    auto findResolved = resolvedEndpoints.find(sensor->configurationPath);
+    if (findResolved != resolvedEndpoints.end())
+    {
+        queryHealth(sensor, findResolved->second);
+        return;
+    }
+
+    resolveEndpoint(sensor);
+}
+
+void NVMeMCTPContext::resolveEndpoint(const std::shared_ptr<NVMeSensor>& sensor)
+{
+    std::weak_ptr<NVMeContext> weakSelf = weak_from_this();
+    dbusConnection->async_method_call(
+        [weakSelf, sensor](
+            const boost::system::error_code& ec,
+            const std::variant<std::vector<std::string>>& endpoints) {
+            auto self =
+                std::static_pointer_cast<NVMeMCTPContext>(weakSelf.lock());
+            if (!self)
+            {
+                return;
+            }
+
+            const auto* paths =
+                std::get_if<std::vector<std::string>>(&endpoints);
+            std::optional<NVMeMCTPEndpoint> endpoint;
+            if (!ec && paths != nullptr && !paths->empty())
+            {
+                endpoint = endpointFromPath(paths->front());
+            }
+            if (!endpoint)
+            {
+                // mctpreactor has not set the drive up as an endpoint (yet)
+                sensor->incrementHealthError();
+                self->readAndProcessNVMeSensor();
+                return;
+            }
+
+            self->resolvedEndpoints[sensor->configurationPath] = *endpoint;
+            self->queryHealth(sensor, *endpoint);
+        },
+        mapper::busName, sensor->configurationPath + "/configures",
+        properties::interface, properties::get, associationInterface,
+        "endpoints");
+}
+
+void NVMeMCTPContext::queryHealth(const std::shared_ptr<NVMeSensor>& sensor,
+                                  const NVMeMCTPEndpoint& endpoint)
+{
+    std::weak_ptr<NVMeContext> weakSelf = weak_from_this();
+    exchange(
+        endpoint, nvmemi::encodeSubsystemHealthPoll(),
+        [weakSelf, sensor,
+         endpoint](std::optional<std::vector<uint8_t>> subsystem) mutable {
+            auto self =
+                std::static_pointer_cast<NVMeMCTPContext>(weakSelf.lock());
+            if (!self)
+            {
+                return;
+            }
+
+            if (!subsystem)
+            {
+                // The endpoint may have been reassigned, look it up again, and
+                // the drive may have been swapped
+                self->resolvedEndpoints.erase(sensor->configurationPath);
+                sensor->forgetIdentity();
+                sensor->markUnresponsive();
+                self->readAndProcessNVMeSensor();
+                return;
+            }
+
+            // A fatal controller makes the drive non-functional even while the
+            // subsystem reports it functional
+            self->exchange(
+                endpoint, nvmemi::encodeControllerHealthPoll(),
+                [weakSelf, sensor, endpoint, subsystem{std::move(*subsystem)}](
+                    std::optional<std::vector<uint8_t>> controllers) mutable {
+                    auto context = std::static_pointer_cast<NVMeMCTPContext>(
+                        weakSelf.lock());
+                    if (!context)
+                    {
+                        return;
+                    }
+
+                    std::optional<std::vector<nvmemi::ControllerHealth>>
+                        health;
+                    if (controllers)
+                    {
+                        health = nvmemi::decodeControllerHealth(*controllers);
+                    }
+                    if (health &&
+                        std::ranges::any_of(
+                            *health, &nvmemi::ControllerHealth::fatal))
+                    {
+                        sensor->markFunctional(false);
+                    }
+                    else
+                    {
+                        context->processResponse(sensor, subsystem.data(),
+                                                 subsystem.size());
+                    }
+
+                    if (health && !health->empty() && sensor->needsIdentity())
+                    {
+                        context->queryIdentity(sensor, endpoint,
+                                               health->front().id);
+                        return;
+                    }
+
+                    context->readAndProcessNVMeSensor();
+                });
+        });
+}
+
+void NVMeMCTPContext::queryIdentity(const std::shared_ptr<NVMeSensor>& sensor,
+                                    const NVMeMCTPEndpoint& endpoint,
+                                    uint16_t controllerId)
+{
+    std::weak_ptr<NVMeContext> weakSelf = weak_from_this();
+    exchange(endpoint, nvmemi::encodeIdentifyController(controllerId),
+             [weakSelf, sensor](std::optional<std::vector<uint8_t>> response) {
+                 auto self = std::static_pointer_cast<NVMeMCTPContext>(
+                     weakSelf.lock());
+                 if (!self)
+                 {
+                     return;
+                 }
+
+                 std::optional<nvmemi::DriveIdentity> identity;
+                 if (response)
+                 {
+                     identity = nvmemi::decodeIdentifyController(*response);
+                 }
+                 if (identity)
+                 {
+                     sensor->updateIdentity(*identity);
+                 }
+                 else
+                 {
+                     sensor->identityFailed();
+                 }
+
+                 self->readAndProcessNVMeSensor();
+             });
+}
+
+void NVMeMCTPContext::exchange(const NVMeMCTPEndpoint& endpoint,
+                               const std::vector<uint8_t>& request,
+                               Response&& response)
+{
+    sockaddr_mctp addr{};
+    addr.smctp_family = AF_MCTP;
+    addr.smctp_network = endpoint.network;
+    addr.smctp_addr.s_addr = endpoint.eid;
+    addr.smctp_type = nvmemi::mctpMessageType;
+    addr.smctp_tag = MCTP_TAG_OWNER;
+
+    // NOLINTNEXTLINE(cppcoreguidelines-pro-type-reinterpret-cast)
+    const auto* target = reinterpret_cast<const struct sockaddr*>(&addr);
+    ssize_t sent = ::sendto(socket.native_handle(), request.data(),
+                            request.size(), 0, target, sizeof(addr));
+    if (sent != static_cast<ssize_t>(request.size()))
+    {
+        std::cerr << "Failed to send NVMe-MI request to EID "
+                  << static_cast<int>(endpoint.eid) << ": " << strerror(errno)
+                  << "\n";
+        response(std::nullopt);
+        return;
+    }
+
+    responseTimer.expires_after(responseTimeout);
+    responseTimer.async_wait([weakSelf{weak_from_this()}](
+                                 const boost::system::error_code& ec) {
+        if (ec)
+        {
+            return;
+        }
+        if (auto self =
+                std::static_pointer_cast<NVMeMCTPContext>(weakSelf.lock()))
+        {
+            // Completes the pending receive with operation_aborted
+            self->socket.cancel();
+        }
+    });
+
+    receive(endpoint, std::move(response));
+}
+
+void NVMeMCTPContext::receive(const NVMeMCTPEndpoint& endpoint,
+                              Response&& response)
+{
+    socket.async_wait(
+        boost::asio::posix::stream_descriptor::wait_read,
+        [weakSelf{weak_from_this()}, endpoint,
+         response{std::move(response)}](
+            const boost::system::error_code& ec) mutable {
+            auto self =
+                std::static_pointer_cast<NVMeMCTPContext>(weakSelf.lock());
+            if (!self)
+            {
+                return;
+            }
+            if (ec)
+            {
+                response(std::nullopt);
+                return;
+            }
+
+            sockaddr_mctp addr{};
+            socklen_t addrLen = sizeof(addr);
+            // NOLINTNEXTLINE(cppcoreguidelines-pro-type-reinterpret-cast)
+            auto* source = reinterpret_cast<struct sockaddr*>(&addr);
+            ssize_t len = ::recvfrom(self->socket.native_handle(),
+                                     self->receiveBuffer.data(),
+                                     self->receiveBuffer.size(), MSG_TRUNC,
+                                     source, &addrLen);
+            if (len < 0 && (errno == EAGAIN || errno == EWOULDBLOCK))
+            {
+                self->receive(endpoint, std::move(response));
+                return;
+            }
+            if (len < 0 ||
+                static_cast<size_t>(len) > self->receiveBuffer.size())
+            {
+                self->responseTimer.cancel();
+                response(std::nullopt);
+                return;
+            }
+
+            // Drop anything else, such as a late answer to a request that
+            // has already timed out
+            if (addr.smctp_addr.s_addr != endpoint.eid ||
+                addr.smctp_type != nvmemi::mctpMessageType)
+            {
+                self->receive(endpoint, std::move(response));
+                return;
+            }
+
+            self->responseTimer.cancel();
+            std::span<const uint8_t> message(self->receiveBuffer.data(),
+                                             static_cast<size_t>(len));
+            response(std::vector<uint8_t>(message.begin(), message.end()));
+        });
+}
+
+void NVMeMCTPContext::processResponse(std::shared_ptr<NVMeSensor>& sensor,
+                                      void* msg, size_t len)
+{
+    if (msg == nullptr)
+    {
+        sensor->incrementHealthError();
+        return;
+    }
+
+    std::optional<nvmemi::SubsystemHealth> health =
+        nvmemi::decodeSubsystemHealth(
+            std::span<const uint8_t>(static_cast<uint8_t*>(msg), len));
+    if (!health)
+    {
+        sensor->incrementHealthError();
+        return;
+    }
+
+    sensor->updateHealth(*health);
+
+    if (!health->functional())
+    {
+        sensor->markFunctional(false);
+        return;
+    }
+
+    if (!health->temperature)
+    {
+        sensor->incrementError();
+        return;
+    }
+
+    sensor->updateValue(*health->temperature);
+}
@@ -0,0 +1,72 @@
+#pragma once
+
+#include "NVMeContext.hpp"
+
+#include <boost/asio/io_context.hpp>
+#include <boost/asio/posix/stream_descriptor.hpp>
+#include <boost/asio/steady_timer.hpp>
+#include <sdbusplus/asio/connection.hpp>
+
+#include <array>
+#include <cstdint>
+#include <functional>
+#include <map>
+#include <memory>
+#include <optional>
+#include <string>
+#include <vector>
+
+struct NVMeMCTPEndpoint
+{
+    // The Linux network ID, MCTP_NET_ANY for the default network
+    unsigned int network;
+    uint8_t eid;
+};
+
+// The endpoint of an mctpd object path, /xyz/openbmc_project/mctp/<net>/<eid>
+std::optional<NVMeMCTPEndpoint> endpointFromPath(const std::string& path);
+
+/*
+ * Monitors drives with NVMe-MI messages over the kernel's AF_MCTP sockets.
+ *
+ * A drive is either given a static endpoint from its configuration, or is
+ * reached through the endpoint mctpreactor has configured for the drive's
+ * inventory item, found through the "configures" association.
+ */
+class NVMeMCTPContext : public NVMeContext
+{
+  public:
+    NVMeMCTPContext(boost::asio::io_context& io, int rootBus,
+                    std::shared_ptr<sdbusplus::asio::connection> conn);
+    ~NVMeMCTPContext() override = default;
+
+    void setEndpoint(const std::string& configurationPath,
+                     const NVMeMCTPEndpoint& endpoint);
+
+    void close() override;
+    void pollNVMeDevices() override;
+    void readAndProcessNVMeSensor() override;
+    void processResponse(std::shared_ptr<NVMeSensor>& sensor, void* msg,
+                         size_t len) override;
+
+  private:
+    using Response = std::function<void(std::optional<std::vector<uint8_t>>)>;
+
+    std::shared_ptr<sdbusplus::asio::connection> dbusConnection;
+    boost::asio::posix::stream_descriptor socket;
+    boost::asio::steady_timer responseTimer;
+    std::array<uint8_t, 4096> receiveBuffer{};
+
+    // By configuration path
+    std::map<std::string, NVMeMCTPEndpoint> staticEndpoints;
+    std::map<std::string, NVMeMCTPEndpoint> resolvedEndpoints;
+
+    void resolveEndpoint(const std::shared_ptr<NVMeSensor>& sensor);
+    void queryHealth(const std::shared_ptr<NVMeSensor>& sensor,
+                     const NVMeMCTPEndpoint& endpoint);
+    void queryIdentity(const std::shared_ptr<NVMeSensor>& sensor,
+                       const NVMeMCTPEndpoint& endpoint, uint16_t controllerId);
+    void exchange(const NVMeMCTPEndpoint& endpoint,
+                  const std::vector<uint8_t>& request, Response&& response);
+    void receive(const NVMeMCTPEndpoint& endpoint, Response&& response);
+};
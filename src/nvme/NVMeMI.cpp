@@ -0,0 +1,281 @@
+#include "NVMeMI.hpp"
+
+#include <cstddef>
+#include <cstdint>
+#include <optional>
+#include <span>
+#include <string>
+#include <vector>
+
+namespace nvmemi
+{
+
+// NVMe-MI and NVMe Admin command sets, request or response
+static constexpr uint8_t nmpCommand = 0x08;
+static constexpr uint8_t nmpAdminCommand = 0x10;
+static constexpr uint8_t nmpResponse = 0x80;
+
+static constexpr size_t responseHeaderSize = 7;
+// The status and reserved bytes, then completion queue entry dwords 0, 1, 3
+static constexpr size_t adminResponseHeaderSize = 19;
+static constexpr size_t micSize = 4;
+
+static constexpr uint8_t adminIdentify = 0x06;
+static constexpr uint32_t cnsIdentifyController = 0x01;
+// Identify Controller up to and including the firmware revision
+static constexpr uint32_t identifyControllerSize = 72;
+static constexpr size_t basicIdentitySize = 22;
+
+static constexpr size_t subsystemHealthSize = 8;
+static constexpr size_t basicStatusSize = 6;
+static constexpr size_t controllerHealthSize = 16;
+
+uint32_t crc32c(std::span<const uint8_t> data)
+{
+    uint32_t crc = 0xffffffff;
+    for (uint8_t byte : data)
+    {
+        crc ^= byte;
+        for (int bit = 0; bit < 8; bit++)
+        {
+            crc = (crc >> 1) ^ ((crc & 1U) != 0U ? 0x82f63b78U : 0U);
+        }
+    }
+    return ~crc;
+}
+
+static uint32_t messageIntegrityCheck(std::span<const uint8_t> message)
+{
+    std::vector<uint8_t> covered;
+    covered.reserve(message.size() + 1);
+    covered.push_back(mctpMessageType);
+    covered.insert(covered.end(), message.begin(), message.end());
+    return crc32c(covered);
+}
+
+static void appendLE32(std::vector<uint8_t>& message, uint32_t value)
+{
+    for (int shift = 0; shift < 32; shift += 8)
+    {
+        message.push_back(static_cast<uint8_t>(value >> shift));
+    }
+}
+
+static uint16_t readLE16(std::span<const uint8_t> data, size_t offset)
+{
+    return static_cast<uint16_t>(data[offset] | (data[offset + 1] << 8));
+}
+
+static uint16_t readBE16(std::span<const uint8_t> data, size_t offset)
+{
+    return static_cast<uint16_t>((data[offset] << 8) | data[offset + 1]);
+}
+
+// An ASCII field, without the space padding
+static std::string readString(std::span<const uint8_t> data, size_t offset,
+                              size_t size)
+{
+    std::span<const uint8_t> field = data.subspan(offset, size);
+    std::string value(field.begin(), field.end());
+    size_t end = value.find_last_not_of(std::string(" \0", 2));
+    if (end == std::string::npos)
+    {
+        return {};
+    }
+    value.resize(end + 1);
+    return value;
+}
+
+static std::vector<uint8_t> encodeRequest(Opcode opcode, uint32_t nmd0,
+                                          uint32_t nmd1)
+{
+    // Message header, then the opcode and three reserved bytes
+    std::vector<uint8_t> message = {
+        nmpCommand, 0x00, 0x00, static_cast<uint8_t>(opcode), 0x00, 0x00, 0x00};
+    appendLE32(message, nmd0);
+    appendLE32(message, nmd1);
+    appendLE32(message, messageIntegrityCheck(message));
+    return message;
+}
+
+std::vector<uint8_t> encodeSubsystemHealthPoll()
+{
+    return encodeRequest(Opcode::subsystemHealthStatusPoll, 0, 0);
+}
+
+std::vector<uint8_t> encodeControllerHealthPoll()
+{
+    // Report all controllers, up to 255 of them, starting from ID 0
+    constexpr uint32_t reportAll = 1U << 31;
+    constexpr uint32_t maxEntries = 0xffU << 16;
+    return encodeRequest(Opcode::controllerHealthStatusPoll,
+                         reportAll | maxEntries, 0);
+}
+
+std::vector<uint8_t> encodeIdentifyController(uint16_t controllerId)
+{
+    // Message header, then the opcode, command flags marking the data length
+    // and offset valid, and the controller ID
+    std::vector<uint8_t> message = {nmpAdminCommand,
+                                    0x00,
+                                    0x00,
+                                    adminIdentify,
+                                    0x03,
+                                    static_cast<uint8_t>(controllerId),
+                                    static_cast<uint8_t>(controllerId >> 8)};
+    // Submission queue entry dwords 1 to 5
+    for (int dword = 1; dword <= 5; dword++)
+    {
+        appendLE32(message, 0);
+    }
+    // Data offset and length, then two reserved dwords
+    appendLE32(message, 0);
+    appendLE32(message, identifyControllerSize);
+    appendLE32(message, 0);
+    appendLE32(message, 0);
+    // Submission queue entry dwords 10 to 15
+    appendLE32(message, cnsIdentifyController);
+    for (int dword = 11; dword <= 15; dword++)
+    {
+        appendLE32(message, 0);
+    }
+    appendLE32(message, messageIntegrityCheck(message));
+    return message;
+}
+
+// The response data of a successful response with an intact integrity check
+static std::optional<std::span<const uint8_t>> responseData(
+    std::span<const uint8_t> response, uint8_t nmp = nmpCommand,
+    size_t headerSize = responseHeaderSize)
+{
+    if (response.size() < headerSize + micSize)
+    {
+        return std::nullopt;
+    }
+    std::span<const uint8_t> message =
+        response.first(response.size() - micSize);
+    uint32_t mic = readLE16(response, message.size()) |
+                   (readLE16(response, message.size() + 2) << 16);
+    if (mic != messageIntegrityCheck(message))
+    {
+        return std::nullopt;
+    }
+    if (message[0] != (nmp | nmpResponse) || message[3] != 0x00)
+    {
+        return std::nullopt;
+    }
+    return message.subspan(headerSize);
+}
+
+std::optional<SubsystemHealth>
+    decodeSubsystemHealth(std::span<const uint8_t> response)
+{
+    std::optional<std::span<const uint8_t>> data = responseData(response);
+    if (!data || data->size() < subsystemHealthSize)
+    {
+        return std::nullopt;
+    }
+
+    SubsystemHealth health;
+    health.status = (*data)[0];
+    health.smartWarnings = (*data)[1];
+    health.temperature = compositeTemperature((*data)[2]);
+    health.driveLifeUsed = (*data)[3];
+    health.controllerStatus = readLE16(*data, 4);
+    return health;
+}
+
+std::optional<std::vector<ControllerHealth>>
+    decodeControllerHealth(std::span<const uint8_t> response)
+{
+    std::optional<std::span<const uint8_t>> data = responseData(response);
+    if (!data)
+    {
+        return std::nullopt;
+    }
+
+    // The first byte of the NVMe Management Response holds the entry count
+    size_t entries = response[4];
+    if (data->size() < entries * controllerHealthSize)
+    {
+        return std::nullopt;
+    }
+
+    std::vector<ControllerHealth> controllers;
+    for (size_t ii = 0; ii < entries; ii++)
+    {
+        std::span<const uint8_t> entry =
+            data->subspan(ii * controllerHealthSize, controllerHealthSize);
+        ControllerHealth health;
+        health.id = readLE16(entry, 0);
+        health.status = readLE16(entry, 2);
+        health.temperature = readLE16(entry, 4);
+        health.driveLifeUsed = entry[6];
+        health.spare = entry[7];
+        health.warnings = entry[8];
+        controllers.emplace_back(health);
+    }
+    return controllers;
+}
+
+std::optional<DriveIdentity>
+    decodeIdentifyController(std::span<const uint8_t> response)
+{
+    std::optional<std::span<const uint8_t>> data =
+        responseData(response, nmpAdminCommand, adminResponseHeaderSize);
+    if (!data || data->size() < identifyControllerSize)
+    {
+        return std::nullopt;
+    }
+
+    DriveIdentity identity;
+    identity.vendorId = readLE16(*data, 0);
+    identity.serialNumber = readString(*data, 4, 20);
+    identity.model = readString(*data, 24, 40);
+    identity.firmwareRevision = readString(*data, 64, 8);
+    return identity;
+}
+
+std::optional<DriveIdentity>
+    decodeBasicIdentity(std::span<const uint8_t> response)
+{
+    if (response.size() < basicIdentitySize)
+    {
+        return std::nullopt;
+    }
+
+    DriveIdentity identity;
+    // Sent most significant byte first, unlike the rest of NVMe
+    identity.vendorId = readBE16(response, 0);
+    identity.serialNumber = readString(response, 2, 20);
+    return identity;
+}
+
+std::optional<SubsystemHealth>
+    decodeBasicStatus(std::span<const uint8_t> response)
+{
+    if (response.size() < basicStatusSize)
+    {
+        return std::nullopt;
+    }
+
+    SubsystemHealth health;
+    health.status = response[0];
+    health.smartWarnings = response[1];
+    health.temperature = compositeTemperature(response[2]);
+    health.driveLifeUsed = response[3];
+    return health;
+}
+
+std::optional<double> compositeTemperature(uint8_t reading)
+{
+    // 0x80 = No temperature data or temperature data is more the 5 s old
+    // 0x81 = Temperature sensor failure
+    if (reading == 0x80 || reading == 0x81)
+    {
+        return std::nullopt;
+    }
+    return static_cast<int8_t>(reading);
+}
+
+} // namespace nvmemi
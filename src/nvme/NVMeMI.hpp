@@ -0,0 +1,126 @@
+#pragma once
+
+#include <cstdint>
+#include <optional>
+#include <span>
+#include <string>
+#include <vector>
+
+/*
+ * NVMe Management Interface messages over MCTP
+ *
+ * https://nvmexpress.org/wp-content/uploads/NVM-Express-Management-Interface-Specification-1.2c-2022.10.06-Ratified.pdf
+ *
+ * The kernel's AF_MCTP sockets carry the MCTP message type byte in the socket
+ * address rather than the message body, so the buffers built and parsed here
+ * start at the byte after it. The message integrity check still covers it.
+ */
+namespace nvmemi
+{
+
+// NVMe-MI message type with the integrity check bit set
+constexpr uint8_t mctpMessageType = 0x84;
+
+enum class Opcode : uint8_t
+{
+    subsystemHealthStatusPoll = 0x01,
+    controllerHealthStatusPoll = 0x02,
+};
+
+// SMART Warnings bits
+enum class SmartWarning : uint8_t
+{
+    spareBelowThreshold = 0x01,
+    temperature = 0x02,
+    reliabilityDegraded = 0x04,
+    readOnly = 0x08,
+    volatileBackupFailed = 0x10,
+};
+
+// NVM Subsystem Health Data Structure, Figure 108. The Basic Management
+// Command's status block shares the first four bytes.
+struct SubsystemHealth
+{
+    uint8_t status = 0;
+    // Each bit is cleared while its warning is active
+    uint8_t smartWarnings = 0xff;
+    // Composite temperature in degrees C, nullopt when the drive has none
+    std::optional<double> temperature;
+    uint8_t driveLifeUsed = 0;
+    uint16_t controllerStatus = 0;
+
+    bool functional() const
+    {
+        return (status & 0x20) != 0;
+    }
+
+    bool warning(SmartWarning bit) const
+    {
+        return (smartWarnings & static_cast<uint8_t>(bit)) == 0;
+    }
+};
+
+// Controller Health Data Structure, Figure 113
+struct ControllerHealth
+{
+    uint16_t id = 0;
+    uint16_t status = 0;
+    // Kelvin
+    uint16_t temperature = 0;
+    uint8_t driveLifeUsed = 0;
+    uint8_t spare = 0;
+    uint8_t warnings = 0;
+
+    bool fatal() const
+    {
+        return (status & 0x0002) != 0;
+    }
+};
+
+// What a drive says about itself, from the Basic Management Command's
+// vendor block or from Identify Controller
+struct DriveIdentity
+{
+    // PCI-SIG vendor ID
+    uint16_t vendorId = 0;
+    std::string serialNumber;
+    // Empty when read with the Basic Management Command, which doesn't carry
+    // them
+    std::string model;
+    std::string firmwareRevision;
+};
+
+// CRC-32C as used by the message integrity check
+uint32_t crc32c(std::span<const uint8_t> data);
+
+// A Subsystem Health Status Poll that leaves the status flags as they are
+std::vector<uint8_t> encodeSubsystemHealthPoll();
+
+// A Controller Health Status Poll reporting every controller, from the first
+std::vector<uint8_t> encodeControllerHealthPoll();
+
+std::optional<SubsystemHealth>
+    decodeSubsystemHealth(std::span<const uint8_t> response);
+
+std::optional<std::vector<ControllerHealth>>
+    decodeControllerHealth(std::span<const uint8_t> response);
+
+// An Identify Controller admin command for the controller's identification
+std::vector<uint8_t> encodeIdentifyController(uint16_t controllerId);
+
+std::optional<DriveIdentity>
+    decodeIdentifyController(std::span<const uint8_t> response);
+
+// The vendor block read with Basic Management Command code 8
+std::optional<DriveIdentity>
+    decodeBasicIdentity(std::span<const uint8_t> response);
+
+// The status block read with Basic Management Command code 0
+std::optional<SubsystemHealth>
+    decodeBasicStatus(std::span<const uint8_t> response);
+
+// The shared encoding of the composite temperature of the Basic Management
+// Command and the NVM Subsystem Health Data Structure
+std::optional<double> compositeTemperature(uint8_t reading);
+
+} // namespace nvmemi
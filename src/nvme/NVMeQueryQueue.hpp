@@ -0,0 +1,91 @@
+#pragma once
+
+#include <chrono>
+#include <deque>
+#include <optional>
+#include <utility>
+
+/*
+ * The queries written to a worker that answers them one at a time, in order.
+ * Each query has a deadline of its own, so that one issued behind a query the
+ * worker hangs on is given up on in turn rather than waiting for it. A query
+ * that has been given up on stays queued until the worker answers it, so that
+ * the later answers still go to the right query.
+ */
+template <typename Query>
+class NVMeQueryQueue
+{
+  public:
+    using Clock = std::chrono::steady_clock;
+
+    explicit NVMeQueryQueue(Clock::duration timeout) : timeout(timeout) {}
+
+    void push(Query&& query, Clock::time_point now)
+    {
+        entries.push_back({std::move(query), now + timeout, false});
+    }
+
+    bool empty() const
+    {
+        return entries.empty();
+    }
+
+    // The earliest deadline of the queries not given up on yet. Queries are
+    // pushed in the order of their deadlines.
+    std::optional<Clock::time_point> nextDeadline() const
+    {
+        for (const Entry& entry : entries)
+        {
+            if (!entry.expired)
+            {
+                return entry.deadline;
+            }
+        }
+        return std::nullopt;
+    }
+
+    // Gives up on every query whose deadline has passed by now, handing each
+    // to `handler`. Returns whether there were any.
+    template <typename Handler>
+    bool expire(Clock::time_point now, Handler&& handler)
+    {
+        bool expired = false;
+        for (Entry& entry : entries)
+        {
+            if (!entry.expired && entry.deadline <= now)
+            {
+                entry.expired = true;
+                expired = true;
+                handler(entry.query);
+            }
+        }
+        return expired;
+    }
+
+    // The query the worker has just answered, nullopt if it was given up on
+    std::optional<Query> complete()
+    {
+        if (entries.empty())
+        {
+            return std::nullopt;
+        }
+        Entry entry = std::move(entries.front());
+        entries.pop_front();
+        if (entry.expired)
+        {
+            return std::nullopt;
+        }
+        return std::move(entry.query);
+    }
+
+  private:
+    struct Entry
+    {
+        Query query;
+        Clock::time_point deadline;
+        bool expired;
+    };
+
+    Clock::duration timeout;
+    std::deque<Entry> entries;
+};
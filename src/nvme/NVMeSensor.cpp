@@ -24,18 +24,68 @@
 #include <boost/asio/io_context.hpp>
 #include <sdbusplus/asio/connection.hpp>
 #include <sdbusplus/asio/object_server.hpp>
+#include <sdbusplus/bus/match.hpp>
+#include <sdbusplus/exception.hpp>
+#include <sdbusplus/message.hpp>
 
+#include <algorithm>
+#include <array>
 #include <cstddef>
 #include <cstdint>
+#include <format>
+#include <iostream>
+#include <limits>
+#include <map>
 #include <memory>
 #include <stdexcept>
 #include <string>
 #include <utility>
+#include <variant>
 #include <vector>
 
 static constexpr double maxReading = 127;
 static constexpr double minReading = 0;
 
+// Sitting out at most 255 polls, about as long as a drive in error
+static constexpr unsigned int maxBackoffShift = 8;
+
+static constexpr double maxDriveLifeReading = 255;
+static constexpr double minDriveLifeReading = 0;
+
+static constexpr const char* statusInterfaceName =
+    "xyz.openbmc_project.Nvme.Status";
+
+struct WarningProperty
+{
+    nvmemi::SmartWarning bit;
+    const char* property;
+    const char* description;
+};
+
+static constexpr std::array<WarningProperty, 5> warningProperties = {{
+    {nvmemi::SmartWarning::spareBelowThreshold, "CapacityFault",
+     "available spare is below its threshold"},
+    {nvmemi::SmartWarning::temperature, "TemperatureFault",
+     "temperature is outside its thresholds"},
+    {nvmemi::SmartWarning::reliabilityDegraded, "DegradesFault",
+     "reliability is degraded by media errors"},
+    {nvmemi::SmartWarning::readOnly, "MediaFault",
+     "media has been placed in read only mode"},
+    {nvmemi::SmartWarning::volatileBackupFailed, "BackupDeviceFault",
+     "volatile memory backup device has failed"},
+}};
+
+static std::string hexByte(uint8_t value)
+{
+    return std::format("0x{:02x}", value);
+}
+
+static constexpr const char* inventoryItemInterface =
+    "xyz.openbmc_project.Inventory.Item";
+
+static constexpr const char* nvmeDriveProtocol =
+    "xyz.openbmc_project.Inventory.Item.Drive.DriveProtocol.NVMe";
+
 NVMeSensor::NVMeSensor(sdbusplus::asio::object_server& objectServer,
                        boost::asio::io_context& /*unused*/,
                        std::shared_ptr<sdbusplus::asio::connection>& conn,
@@ -68,6 +118,52 @@ NVMeSensor::NVMeSensor(sdbusplus::asio::object_server& objectServer,
         "/xyz/openbmc_project/sensors/temperature/" + name,
         association::interface);
 
+    statusInterface = objectServer.add_interface(
+        "/xyz/openbmc_project/sensors/temperature/" + name,
+        statusInterfaceName);
+    statusInterface->register_property("SmartWarnings", hexByte(smartWarnings));
+    statusInterface->register_property("StatusFlags", hexByte(0));
+    statusInterface->register_property("DriveLifeUsed", std::string("0"));
+    for (const auto& warning : warningProperties)
+    {
+        statusInterface->register_property(warning.property, false);
+    }
+    if (!statusInterface->initialize())
+    {
+        std::cerr << "error initializing NVMe status interface for " << name
+                  << "\n";
+    }
+
+    // The drive as an inventory item, present once it answers or its bay
+    // reports it, and filled in once it has identified itself
+    std::string itemPath =
+        "/xyz/openbmc_project/inventory/system/drive/" + name;
+    itemIface = objectServer.add_interface(itemPath, inventoryItemInterface);
+    itemIface->register_property("PrettyName", std::string());
+    itemIface->register_property("Present", false);
+    itemIface->initialize();
+    driveIface = objectServer.add_interface(
+        itemPath, "xyz.openbmc_project.Inventory.Item.Drive");
+    driveIface->register_property("Protocol", std::string(nvmeDriveProtocol));
+    driveIface->initialize();
+    assetIface = objectServer.add_interface(
+        itemPath, "xyz.openbmc_project.Inventory.Decorator.Asset");
+    assetIface->register_property("Manufacturer", std::string());
+    assetIface->register_property("Model", std::string());
+    assetIface->register_property("SerialNumber", std::string());
+    assetIface->initialize();
+    revisionIface = objectServer.add_interface(
+        itemPath, "xyz.openbmc_project.Inventory.Decorator.Revision");
+    revisionIface->register_property("Version", std::string());
+    revisionIface->initialize();
+    itemAssoc = objectServer.add_interface(itemPath, association::interface);
+    itemAssoc->register_property(
+        "Associations",
+        std::vector<Association>{
+            {"sensors", "inventory",
+             "/xyz/openbmc_project/sensors/temperature/" + name}});
+    itemAssoc->initialize();
+
     setInitialProperties(sensor_paths::unitDegreesC);
     // Mark as unavailable until the first packet has been received over NVMe
     // MI.
@@ -83,10 +179,22 @@ NVMeSensor::~NVMeSensor()
     }
     objServer.remove_interface(sensorInterface);
     objServer.remove_interface(association);
+    objServer.remove_interface(statusInterface);
+    objServer.remove_interface(itemIface);
+    objServer.remove_interface(driveIface);
+    objServer.remove_interface(assetIface);
+    objServer.remove_interface(revisionIface);
+    objServer.remove_interface(itemAssoc);
 }
 
 bool NVMeSensor::sample()
 {
+    if (backoff > 0)
+    {
+        backoff--;
+        return false;
+    }
+
     if (inError())
     {
         if (scanDelay == 0)
@@ -100,7 +208,259 @@ bool NVMeSensor::sample()
     return scanDelay == 0;
 }
 
+void NVMeSensor::markUnresponsive()
+{
+    incrementError();
+    clearDriveLife();
+    unresponsive = std::min(unresponsive + 1, maxBackoffShift);
+    backoff = (1U << unresponsive) - 1;
+}
+
+void NVMeSensor::incrementHealthError()
+{
+    incrementError();
+    clearDriveLife();
+}
+
+void NVMeSensor::markUnavailable()
+{
+    markAvailable(false);
+    updateValue(std::numeric_limits<double>::quiet_NaN());
+    clearDriveLife();
+}
+
+void NVMeSensor::clearDriveLife()
+{
+    if (driveLife)
+    {
+        driveLife->markAvailable(false);
+        driveLife->updateValue(std::numeric_limits<double>::quiet_NaN());
+    }
+}
+
 void NVMeSensor::checkThresholds()
 {
     thresholds::checkThresholds(this);
 }
+
+void NVMeSensor::updateHealth(const nvmemi::SubsystemHealth& health)
+{
+    // The drive has answered
+    unresponsive = 0;
+    backoff = 0;
+    itemIface->set_property("Present", true);
+
+    if (identityFailedLast && !identityUnsupported)
+    {
+        std::cerr << "NVMe drive " << name
+                  << " does not support identification\n";
+        identityUnsupported = true;
+    }
+
+    for (const auto& warning : warningProperties)
+    {
+        bool active = health.warning(warning.bit);
+        bool wasActive =
+            (smartWarnings & static_cast<uint8_t>(warning.bit)) == 0;
+        if (active && !wasActive)
+        {
+            std::cerr << "NVMe drive " << name << ": " << warning.description
+                      << "\n";
+        }
+        statusInterface->set_property(warning.property, active);
+    }
+    smartWarnings = health.smartWarnings;
+
+    statusInterface->set_property("SmartWarnings", hexByte(smartWarnings));
+    statusInterface->set_property("StatusFlags", hexByte(health.status));
+    statusInterface->set_property("DriveLifeUsed",
+                                  std::to_string(health.driveLifeUsed));
+
+    if (driveLife)
+    {
+        driveLife->updateValue(health.driveLifeUsed);
+    }
+}
+
+void NVMeSensor::updateIdentity(const nvmemi::DriveIdentity& identity)
+{
+    identified = true;
+    identityFailedLast = false;
+
+    // There is no vendor name to be had, only the PCI-SIG vendor ID
+    assetIface->set_property("Manufacturer",
+                             std::format("0x{:04x}", identity.vendorId));
+    assetIface->set_property("SerialNumber", identity.serialNumber);
+    assetIface->set_property("Model", identity.model);
+    revisionIface->set_property("Version", identity.firmwareRevision);
+    itemIface->set_property("PrettyName", identity.model.empty()
+                                              ? identity.serialNumber
+                                              : identity.model);
+}
+
+void NVMeSensor::identityFailed()
+{
+    identityFailedLast = true;
+}
+
+void NVMeSensor::forgetIdentity()
+{
+    identified = false;
+    identityFailedLast = false;
+    identityUnsupported = false;
+}
+
+void NVMeSensor::monitorInventoryPresence(const std::string& inventoryPath)
+{
+    std::weak_ptr<NVMeSensor> weakSelf = weak_from_this();
+    inventoryPresenceMatch = std::make_unique<sdbusplus::bus::match_t>(
+        static_cast<sdbusplus::bus_t&>(*dbusConnection),
+        sdbusplus::bus::match::rules::propertiesChanged(inventoryPath,
+                                                        inventoryItemInterface),
+        [weakSelf](sdbusplus::message_t& message) {
+            auto self = weakSelf.lock();
+            if (!self)
+            {
+                return;
+            }
+
+            // Other properties of the item, such as PrettyName, change too
+            std::string interface;
+            SensorBaseConfigMap changed;
+            try
+            {
+                message.read(interface, changed);
+            }
+            catch (const sdbusplus::exception_t& e)
+            {
+                std::cerr << "Error reading presence of " << self->name << ": "
+                          << e.what() << "\n";
+                return;
+            }
+            auto findPresent = changed.find("Present");
+            if (findPresent == changed.end())
+            {
+                return;
+            }
+            const bool* present = std::get_if<bool>(&findPresent->second);
+            if (present != nullptr)
+            {
+                self->inventoryPresent = *present;
+            }
+        });
+
+    dbusConnection->async_method_call(
+        [weakSelf, inventoryPath](
+            const boost::system::error_code& ec,
+            const std::map<std::string, std::vector<std::string>>& owners) {
+            auto self = weakSelf.lock();
+            if (!self || ec || owners.empty())
+            {
+                return;
+            }
+
+            self->dbusConnection->async_method_call(
+                [weakSelf](const boost::system::error_code& ec,
+                           const std::variant<bool>& present) {
+                    auto self = weakSelf.lock();
+                    if (!self || ec)
+                    {
+                        return;
+                    }
+                    // Don't overwrite a change that has already been signalled
+                    if (!self->inventoryPresent)
+                    {
+                        self->inventoryPresent = std::get<bool>(present);
+                    }
+                },
+                owners.begin()->first, inventoryPath, properties::interface,
+                properties::get, inventoryItemInterface, "Present");
+        },
+        mapper::busName, mapper::path, mapper::interface, "GetObject",
+        inventoryPath, std::vector<std::string>{inventoryItemInterface});
+}
+
+bool NVMeSensor::present() const
+{
+    if (presenceGpio)
+    {
+        return presenceGpio->isPresent();
+    }
+    return inventoryPresent.value_or(true);
+}
+
+bool NVMeSensor::checkPresence()
+{
+    bool isPresent = present();
+    // Without presence detection only an answer tells that there is a drive
+    if (presenceGpio || inventoryPresent)
+    {
+        itemIface->set_property("Present", isPresent);
+    }
+    if (isPresent == wasPresent)
+    {
+        return isPresent;
+    }
+    wasPresent = isPresent;
+
+    if (!isPresent)
+    {
+        // An empty bay is unavailable rather than failed
+        markUnavailable();
+        forgetIdentity();
+        return false;
+    }
+
+    // Don't wait out the back-off of errors from before the drive was
+    // inserted
+    scanDelay = 0;
+    unresponsive = 0;
+    backoff = 0;
+    return true;
+}
+
+NVMeDriveLifeSensor::NVMeDriveLifeSensor(
+    sdbusplus::asio::object_server& objectServer,
+    std::shared_ptr<sdbusplus::asio::connection>& conn,
+    const std::string& sensorName,
+    std::vector<thresholds::Threshold>&& thresholdsIn,
+    const std::string& sensorConfiguration) :
+    Sensor(escapeName(sensorName), std::move(thresholdsIn), sensorConfiguration,
+           NVMeSensor::sensorType, false, false, maxDriveLifeReading,
+           minDriveLifeReading, conn, PowerState::on),
+    objServer(objectServer)
+{
+    sensorInterface = objectServer.add_interface(
+        "/xyz/openbmc_project/sensors/utilization/" + name,
+        "xyz.openbmc_project.Sensor.Value");
+
+    for (const auto& threshold : thresholds)
+    {
+        std::string interface = thresholds::getInterface(threshold.level);
+        thresholdInterfaces[static_cast<size_t>(threshold.level)] =
+            objectServer.add_interface(
+                "/xyz/openbmc_project/sensors/utilization/" + name, interface);
+    }
+    association = objectServer.add_interface(
+        "/xyz/openbmc_project/sensors/utilization/" + name,
+        association::interface);
+
+    setInitialProperties(sensor_paths::unitPercent);
+    // Reported with the temperature, so unavailable until the drive answers
+    markAvailable(false);
+}
+
+NVMeDriveLifeSensor::~NVMeDriveLifeSensor()
+{
+    for (const auto& iface : thresholdInterfaces)
+    {
+        objServer.remove_interface(iface);
+    }
+    objServer.remove_interface(sensorInterface);
+    objServer.remove_interface(association);
+}
+
+void NVMeDriveLifeSensor::checkThresholds()
+{
+    thresholds::checkThresholds(this);
+}
@@ -1,9 +1,38 @@
 #pragma once
 
+#include "NVMeMI.hpp"
+#include "PresenceGpio.hpp"
+
 #include <boost/asio/io_context.hpp>
+#include <sdbusplus/bus/match.hpp>
 #include <sensor.hpp>
 
-class NVMeSensor : public Sensor
+#include <memory>
+#include <optional>
+#include <string>
+#include <vector>
+
+// Percentage Drive Life Used as reported alongside the drive temperature. The
+// drive may report more than 100 once it has outlived its rated endurance.
+class NVMeDriveLifeSensor : public Sensor
+{
+  public:
+    NVMeDriveLifeSensor(sdbusplus::asio::object_server& objectServer,
+                        std::shared_ptr<sdbusplus::asio::connection>& conn,
+                        const std::string& sensorName,
+                        std::vector<thresholds::Threshold>&& thresholds,
+                        const std::string& sensorConfiguration);
+    ~NVMeDriveLifeSensor() override;
+
+  private:
+    sdbusplus::asio::object_server& objServer;
+
+    void checkThresholds() override;
+};
+
+class NVMeSensor :
+    public Sensor,
+    public std::enable_shared_from_this<NVMeSensor>
 {
   public:
     static constexpr const char* sensorType = "NVME1000";
@@ -21,13 +50,72 @@ class NVMeSensor : public Sensor
 
     bool sample();
 
+    // A query went unanswered. Each one in a row doubles the number of polls
+    // the drive sits out, so that it doesn't hold up the others for long.
+    void markUnresponsive();
+
+    // The drive answered, but not with its health. The drive life comes with
+    // the health, so it is unavailable until the next answer as well.
+    void incrementHealthError();
+
+    // Neither the temperature nor the drive life can be read, e.g. because
+    // the host is off
+    void markUnavailable();
+
+    // Publishes the status flags, SMART warnings and drive life that come
+    // with each temperature reading
+    void updateHealth(const nvmemi::SubsystemHealth& health);
+
+    // The drive's identification is read once it answers, and again after
+    // it has stopped answering in case it has been replaced. A drive that
+    // answers its health query but not the identification doesn't support
+    // it, and isn't asked again until then.
+    bool needsIdentity() const
+    {
+        return !identified && !identityUnsupported;
+    }
+    void updateIdentity(const nvmemi::DriveIdentity& identity);
+    void identityFailed();
+    void forgetIdentity();
+
+    // Follows the Present property of an inventory item for the bay, as an
+    // alternative to a presence GPIO
+    void monitorInventoryPresence(const std::string& inventoryPath);
+
+    // Whether the bay should be polled. An empty bay is marked unavailable,
+    // and a newly inserted drive is polled straight away.
+    bool checkPresence();
+
     const int bus;
     const uint8_t address;
 
+    // Only present when the configuration names it
+    std::shared_ptr<NVMeDriveLifeSensor> driveLife;
+    std::shared_ptr<PresenceGpio> presenceGpio;
+
   private:
     const unsigned int scanDelayTicks = 5 * 60;
     sdbusplus::asio::object_server& objServer;
     unsigned int scanDelay{0};
+    unsigned int unresponsive{0};
+    unsigned int backoff{0};
+    std::shared_ptr<sdbusplus::asio::dbus_interface> statusInterface;
+    uint8_t smartWarnings = 0xff;
+    bool identified = false;
+    bool identityFailedLast = false;
+    bool identityUnsupported = false;
+    std::shared_ptr<sdbusplus::asio::dbus_interface> itemIface;
+    std::shared_ptr<sdbusplus::asio::dbus_interface> driveIface;
+    std::shared_ptr<sdbusplus::asio::dbus_interface> assetIface;
+    std::shared_ptr<sdbusplus::asio::dbus_interface> revisionIface;
+    std::shared_ptr<sdbusplus::asio::dbus_interface> itemAssoc;
+    // Unknown until the inventory has been read, and polled meanwhile
+    std::optional<bool> inventoryPresent;
+    std::unique_ptr<sdbusplus::bus::match_t> inventoryPresenceMatch;
+    bool wasPresent = true;
+
+    bool present() const;
+    void clearDriveLife();
 
     void checkThresholds() override;
 };
@@ -16,15 +16,21 @@
 
 #include "NVMeBasicContext.hpp"
 #include "NVMeContext.hpp"
+#include "NVMeMCTPContext.hpp"
 #include "NVMeSensor.hpp"
+#include "PresenceGpio.hpp"
+#include "Reactor.hpp"
 #include "Thresholds.hpp"
 #include "Utils.hpp"
 #include "VariantVisitors.hpp"
 
+#include <linux/mctp.h>
+
 #include <boost/asio/error.hpp>
 #include <boost/asio/io_context.hpp>
 #include <boost/asio/post.hpp>
 #include <boost/asio/steady_timer.hpp>
+#include <boost/container/flat_map.hpp>
 #include <sdbusplus/asio/connection.hpp>
 #include <sdbusplus/asio/object_server.hpp>
 #include <sdbusplus/bus.hpp>
@@ -57,6 +63,9 @@ NVMEMap& getNVMEMap()
     return nvmeDeviceMap;
 }
 
+namespace nvmesensor
+{
+
 static std::optional<int> extractBusNumber(
     const std::string& path, const SensorBaseConfigMap& properties)
 {
@@ -130,18 +139,199 @@ static std::optional<int> deriveRootBus(std::optional<int> busNumber)
     return std::stoi(rootName.substr(0, dash));
 }
 
+static std::optional<NVMeProtocol> extractProtocol(
+    const std::string& path, const SensorBaseConfigMap& properties)
+{
+    auto findProtocol = properties.find("Protocol");
+    if (findProtocol == properties.end())
+    {
+        return NVMeProtocol::basic;
+    }
+
+    const auto* protocol = std::get_if<std::string>(&findProtocol->second);
+    if (protocol != nullptr && *protocol == "Basic")
+    {
+        return NVMeProtocol::basic;
+    }
+    if (protocol != nullptr && *protocol == "MCTP")
+    {
+        return NVMeProtocol::mctp;
+    }
+
+    std::cerr << "unsupported protocol for " << path << ", expected Basic or "
+              << "MCTP\n";
+    return std::nullopt;
+}
+
+// A static endpoint given by "EID" and optionally "Network", otherwise the
+// endpoint mctpreactor configured for the drive is looked up when polling
+static std::optional<NVMeMCTPEndpoint>
+    extractEndpoint(const SensorBaseConfigMap& properties)
+{
+    auto findEid = properties.find("EID");
+    if (findEid == properties.end())
+    {
+        return std::nullopt;
+    }
+
+    NVMeMCTPEndpoint endpoint{MCTP_NET_ANY, 0};
+    endpoint.eid = std::visit(VariantToUnsignedIntVisitor(), findEid->second);
+    auto findNetwork = properties.find("Network");
+    if (findNetwork != properties.end())
+    {
+        endpoint.network =
+            std::visit(VariantToUnsignedIntVisitor(), findNetwork->second);
+    }
+    return endpoint;
+}
+
+static constexpr const char* driveLifeLabel = "DriveLifeUsed";
+
+static std::optional<std::string>
+    extractDriveLifeName(const SensorBaseConfigMap& properties)
+{
+    auto findName = properties.find("DriveLifeUsedName");
+    if (findName == properties.end())
+    {
+        return std::nullopt;
+    }
+
+    return std::visit(VariantToStringVisitor(), findName->second);
+}
+
+// Threshold records labelled for the drive life sensor don't apply to the
+// temperature
+static SensorData temperatureThresholdData(const SensorData& sensorData)
+{
+    SensorData filtered;
+    for (const auto& [intf, cfg] : sensorData)
+    {
+        auto findLabel = cfg.find("Label");
+        if (intf.find("Thresholds") != std::string::npos &&
+            findLabel != cfg.end() &&
+            std::visit(VariantToStringVisitor(), findLabel->second) ==
+                driveLifeLabel)
+        {
+            continue;
+        }
+        filtered.emplace(intf, cfg);
+    }
+    return filtered;
+}
+
+// Shared with sensors from before a configuration change, which may still
+// hold their lines
+static boost::container::flat_map<std::string, std::weak_ptr<PresenceGpio>>
+    presenceGpios;
+
+// Sets up the bay's presence detection from the optional Presence record,
+// either a GPIO as for fans or the Present property of an inventory item
+static void setupPresence(boost::asio::io_context& io,
+                          const SensorData& sensorData,
+                          const std::string& sensorName,
+                          const std::shared_ptr<NVMeSensor>& sensor)
+{
+    auto presenceConfig = sensorData.find(
+        configInterfaceName(NVMeSensor::sensorType) + std::string(".Presence"));
+    if (presenceConfig == sensorData.end())
+    {
+        return;
+    }
+    const SensorBaseConfigMap& presence = presenceConfig->second;
+
+    auto findInventoryPath = presence.find("InventoryPath");
+    if (findInventoryPath != presence.end())
+    {
+        sensor->monitorInventoryPresence(
+            std::visit(VariantToStringVisitor(), findInventoryPath->second));
+        return;
+    }
+
+    auto findPolarity = presence.find("Polarity");
+    auto findPinName = presence.find("PinName");
+    if (findPinName == presence.end() || findPolarity == presence.end())
+    {
+        std::cerr << "Malformed Presence Configuration for " << sensorName
+                  << "\n";
+        return;
+    }
+
+    bool inverted =
+        std::visit(VariantToStringVisitor(), findPolarity->second) == "Low";
+    std::string pinName =
+        std::visit(VariantToStringVisitor(), findPinName->second);
+
+    auto findPresenceGpio = presenceGpios.find(pinName);
+    if (findPresenceGpio != presenceGpios.end())
+    {
+        sensor->presenceGpio = findPresenceGpio->second.lock();
+        if (sensor->presenceGpio)
+        {
+            return;
+        }
+    }
+
+    bool polling = false;
+    auto findMonitorType = presence.find("MonitorType");
+    if (findMonitorType != presence.end())
+    {
+        std::string monitorType =
+            std::visit(VariantToStringVisitor(), findMonitorType->second);
+        polling = monitorType == "Polling";
+        if (!polling && monitorType != "Event")
+        {
+            std::cerr << "Unsupported GPIO MonitorType of " << monitorType
+                      << " for " << sensorName
+                      << " (supported types: Polling, Event (default))\n";
+        }
+    }
+
+    try
+    {
+        std::shared_ptr<PresenceGpio> presenceGpio;
+        if (polling)
+        {
+            presenceGpio = std::make_shared<PollingPresenceGpio>(
+                "NVMe", sensorName, pinName, inverted, io);
+        }
+        else
+        {
+            presenceGpio = std::make_shared<EventPresenceGpio>(
+                "NVMe", sensorName, pinName, inverted, io);
+        }
+        presenceGpio->monitorPresence();
+        presenceGpios[pinName] = presenceGpio;
+        sensor->presenceGpio = presenceGpio;
+    }
+    catch (const std::runtime_error& e)
+    {
+        std::cerr << "Failed to create GPIO monitor object for " << pinName
+                  << " / " << sensorName << ": " << e.what() << "\n";
+    }
+}
+
 static std::shared_ptr<NVMeContext> provideRootBusContext(
-    boost::asio::io_context& io, NVMEMap& map, int rootBus)
+    boost::asio::io_context& io,
+    const std::shared_ptr<sdbusplus::asio::connection>& dbusConnection,
+    NVMEMap& map, NVMeProtocol protocol, int rootBus)
 {
-    auto findRoot = map.find(rootBus);
+    auto findRoot = map.find({protocol, rootBus});
     if (findRoot != map.end())
     {
         return findRoot->second;
     }
 
-    std::shared_ptr<NVMeContext> context =
-        std::make_shared<NVMeBasicContext>(io, rootBus);
-    map[rootBus] = context;
+    std::shared_ptr<NVMeContext> context;
+    if (protocol == NVMeProtocol::mctp)
+    {
+        context =
+            std::make_shared<NVMeMCTPContext>(io, rootBus, dbusConnection);
+    }
+    else
+    {
+        context = std::make_shared<NVMeBasicContext>(io, rootBus);
+    }
+    map[{protocol, rootBus}] = context;
 
     return context;
 }
@@ -179,24 +369,38 @@ static void handleSensorConfigurations(
             extractSensorName(interfacePath, sensorConfig);
         uint8_t slaveAddr = extractSlaveAddr(interfacePath, sensorConfig);
         std::optional<int> rootBus = deriveRootBus(busNumber);
+        std::optional<NVMeProtocol> protocol =
+            extractProtocol(interfacePath, sensorConfig);
 
-        if (!(busNumber && sensorName && rootBus))
+        if (!(busNumber && sensorName && rootBus && protocol))
         {
             continue;
         }
 
         std::vector<thresholds::Threshold> sensorThresholds;
-        if (!parseThresholdsFromConfig(sensorData, sensorThresholds))
+        if (!parseThresholdsFromConfig(temperatureThresholdData(sensorData),
+                                       sensorThresholds))
         {
             std::cerr << "error populating thresholds for " << *sensorName
                       << "\n";
         }
 
+        std::optional<std::string> driveLifeName =
+            extractDriveLifeName(sensorConfig);
+        std::vector<thresholds::Threshold> driveLifeThresholds;
+        const std::string label = driveLifeLabel;
+        if (driveLifeName && !parseThresholdsFromConfig(
+                                 sensorData, driveLifeThresholds, &label))
+        {
+            std::cerr << "error populating thresholds for " << *driveLifeName
+                      << "\n";
+        }
+
         try
         {
             // May throw for an invalid rootBus
-            std::shared_ptr<NVMeContext> context =
-                provideRootBusContext(io, nvmeDeviceMap, *rootBus);
+            std::shared_ptr<NVMeContext> context = provideRootBusContext(
+                io, dbusConnection, nvmeDeviceMap, *protocol, *rootBus);
 
             // Construct the sensor after grabbing the context so we don't
             // glitch D-Bus May throw for an invalid busNumber
@@ -205,8 +409,27 @@ static void handleSensorConfigurations(
                     objectServer, io, dbusConnection, *sensorName,
                     std::move(sensorThresholds), interfacePath, *busNumber,
                     slaveAddr);
+            sensorPtr->loadConfigurationRecords(sensorData);
+
+            if (driveLifeName)
+            {
+                sensorPtr->driveLife = std::make_shared<NVMeDriveLifeSensor>(
+                    objectServer, dbusConnection, *driveLifeName,
+                    std::move(driveLifeThresholds), interfacePath);
+                sensorPtr->driveLife->loadConfigurationRecords(sensorData);
+            }
+
+            setupPresence(io, sensorData, *sensorName, sensorPtr);
 
             context->addSensor(sensorPtr);
+
+            std::optional<NVMeMCTPEndpoint> endpoint =
+                extractEndpoint(sensorConfig);
+            if (protocol == NVMeProtocol::mctp && endpoint)
+            {
+                std::static_pointer_cast<NVMeMCTPContext>(context)->setEndpoint(
+                    interfacePath, *endpoint);
+            }
         }
         catch (const std::invalid_argument& ex)
         {
@@ -267,20 +490,19 @@ static void interfaceRemoved(sdbusplus::message_t& message, NVMEMap& contexts)
     }
 }
 
-int main()
+static void start(boost::asio::io_context& io,
+                  std::shared_ptr<sdbusplus::asio::connection>& systemBus,
+                  sdbusplus::asio::object_server& objectServer)
 {
-    boost::asio::io_context io;
-    auto systemBus = std::make_shared<sdbusplus::asio::connection>(io);
     systemBus->request_name("xyz.openbmc_project.NVMeSensor");
-    sdbusplus::asio::object_server objectServer(systemBus, true);
-    objectServer.add_manager("/xyz/openbmc_project/sensors");
+    reactor::addManager(objectServer, "/xyz/openbmc_project/sensors");
 
     boost::asio::post(io,
                       [&]() { createSensors(io, objectServer, systemBus); });
 
-    boost::asio::steady_timer filterTimer(io);
-    std::function<void(sdbusplus::message_t&)> eventHandler =
-        [&filterTimer, &io, &objectServer, &systemBus](sdbusplus::message_t&) {
+    static boost::asio::steady_timer filterTimer(io);
+    static std::function<void(sdbusplus::message_t&)> eventHandler =
+        [&io, &objectServer, &systemBus](sdbusplus::message_t&) {
             // this implicitly cancels the timer
             filterTimer.expires_after(std::chrono::seconds(1));
 
@@ -300,14 +522,14 @@ int main()
             });
         };
 
-    std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
+    static std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
         setupPropertiesChangedMatches(
             *systemBus, std::to_array<const char*>({NVMeSensor::sensorType}),
             eventHandler);
 
     // Watch for entity-manager to remove configuration interfaces
     // so the corresponding sensors can be removed.
-    auto ifaceRemovedMatch = std::make_unique<sdbusplus::bus::match_t>(
+    static auto ifaceRemovedMatch = std::make_unique<sdbusplus::bus::match_t>(
         static_cast<sdbusplus::bus_t&>(*systemBus),
         "type='signal',member='InterfacesRemoved',arg0path='" +
             std::string(inventoryPath) + "/'",
@@ -316,5 +538,8 @@ int main()
         });
 
     setupManufacturingModeMatch(*systemBus);
-    io.run();
 }
+
+static const reactor::Registration registration("nvme", start);
+
+} // namespace nvmesensor
@@ -18,6 +18,7 @@
 
 #include "DeviceMgmt.hpp"
 #include "SensorPaths.hpp"
+#include "Simulation.hpp"
 #include "Thresholds.hpp"
 #include "Utils.hpp"
 #include "sensor.hpp"
@@ -35,6 +36,7 @@
 #include <iostream>
 #include <limits>
 #include <memory>
+#include <optional>
 #include <stdexcept>
 #include <string>
 #include <utility>
@@ -53,14 +55,19 @@ PSUSensor::PSUSensor(
     const std::string& sensorConfiguration, const PowerState& powerState,
     const std::string& sensorUnits, unsigned int factor, double max, double min,
     double offset, const std::string& label, size_t tSize, double pollRate,
-    const std::shared_ptr<I2CDevice>& i2cDevice) :
+    const std::shared_ptr<I2CDevice>& i2cDevice,
+    const std::shared_ptr<simulation::Source>& simulated) :
     Sensor(escapeName(sensorName), std::move(thresholdsIn), sensorConfiguration,
            objectType, false, false, max, min, conn, powerState),
-    i2cDevice(i2cDevice), objServer(objectServer),
-    inputDev(io, path, boost::asio::random_access_file::read_only),
-    waitTimer(io), path(path), sensorFactor(factor), sensorOffset(offset),
-    thresholdTimer(io)
+    i2cDevice(i2cDevice), objServer(objectServer), inputDev(io), waitTimer(io),
+    path(path), sensorFactor(factor), sensorOffset(offset), thresholdTimer(io),
+    simulated(simulated)
 {
+    if (!simulated)
+    {
+        inputDev.open(path, boost::asio::random_access_file::read_only);
+    }
+
     buffer = std::make_shared<std::array<char, 128>>();
     std::string unitPath = sensor_paths::getPathForUnits(sensorUnits);
     if constexpr (debug)
@@ -119,6 +126,10 @@ PSUSensor::~PSUSensor()
 
 bool PSUSensor::isActive()
 {
+    if (simulated)
+    {
+        return !path.empty();
+    }
     return inputDev.is_open();
 }
 
@@ -132,7 +143,10 @@ void PSUSensor::activate(const std::string& newPath,
     }
     path = newPath;
     i2cDevice = newI2CDevice;
-    inputDev.open(path, boost::asio::random_access_file::read_only);
+    if (!simulated)
+    {
+        inputDev.open(path, boost::asio::random_access_file::read_only);
+    }
     markAvailable(true);
     setupRead();
 }
@@ -157,6 +171,22 @@ void PSUSensor::setupRead()
         return;
     }
 
+    if (simulated)
+    {
+        // Scenario values are already scaled to D-Bus units
+        std::optional<double> reading = simulated->read();
+        if (reading)
+        {
+            updateValue(*reading);
+        }
+        else
+        {
+            incrementError();
+        }
+        restartRead();
+        return;
+    }
+
     if (buffer == nullptr)
     {
         std::cerr << "Buffer was invalid?";
@@ -2,6 +2,7 @@
 
 #include "DeviceMgmt.hpp"
 #include "PwmSensor.hpp"
+#include "Simulation.hpp"
 #include "Thresholds.hpp"
 #include "sensor.hpp"
 
@@ -25,7 +26,8 @@ class PSUSensor : public Sensor, public std::enable_shared_from_this<PSUSensor>
               const PowerState& powerState, const std::string& sensorUnits,
               unsigned int factor, double max, double min, double offset,
               const std::string& label, size_t tSize, double pollRate,
-              const std::shared_ptr<I2CDevice>& i2cDevice);
+              const std::shared_ptr<I2CDevice>& i2cDevice,
+              const std::shared_ptr<simulation::Source>& simulated = nullptr);
     ~PSUSensor() override;
     void setupRead();
     void activate(const std::string& newPath,
@@ -51,6 +53,8 @@ class PSUSensor : public Sensor, public std::enable_shared_from_this<PSUSensor>
     unsigned int sensorFactor;
     double sensorOffset;
     thresholds::ThresholdTimer thresholdTimer;
+    // Readings come from here instead of sysfs when simulating
+    std::shared_ptr<simulation::Source> simulated;
     void restartRead();
     void handleResponse(const boost::system::error_code& err, size_t bytesRead);
     void checkThresholds() override;
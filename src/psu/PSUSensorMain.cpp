@@ -18,7 +18,9 @@
 #include "PSUEvent.hpp"
 #include "PSUSensor.hpp"
 #include "PwmSensor.hpp"
+#include "Reactor.hpp"
 #include "SensorPaths.hpp"
+#include "Simulation.hpp"
 #include "Thresholds.hpp"
 #include "Utils.hpp"
 #include "VariantVisitors.hpp"
@@ -52,6 +54,7 @@
 #include <functional>
 #include <iostream>
 #include <memory>
+#include <optional>
 #include <regex>
 #include <stdexcept>
 #include <string>
@@ -60,6 +63,9 @@
 #include <variant>
 #include <vector>
 
+namespace psusensor
+{
+
 static constexpr bool debug = false;
 static std::regex i2cDevRegex(R"((\/i2c\-\d+\/\d+-[a-fA-F0-9]{4,4})(\/|$))");
 
@@ -295,6 +301,235 @@ static void checkPWMSensor(
         name, pwmPathStr, dbusConnection, objectServer, objPath, "PSU");
 }
 
+// The sensorTable type of a labelMatch entry, which the hwmon file name would
+// otherwise tell
+static std::optional<std::string> simulatedUnitType(const std::string& key)
+{
+    static const boost::container::flat_map<std::string, std::string> types = {
+        {"pin", "power"},     {"pout", "power"},   {"power", "power"},
+        {"maxpin", "power"},  {"vin", "in"},       {"maxvin", "in"},
+        {"in_voltage", "in"}, {"voltage", "in"},   {"vout", "in"},
+        {"vmon", "in"},       {"in", "in"},        {"iin", "curr"},
+        {"iout", "curr"},     {"curr", "curr"},    {"maxiout", "curr"},
+        {"temp", "temp"},     {"maxtemp", "temp"}, {"fan", "fan"}};
+    auto find = types.find(key);
+    if (find == types.end())
+    {
+        return std::nullopt;
+    }
+    return find->second;
+}
+
+// Without hardware there are no pmbus labels to discover, so the sensors are
+// created from the "Labels" of each configuration and play their simulation
+// scenario. Names, limits and thresholds follow the same configuration keys as
+// the hwmon path.
+static void createSimulatedSensors(
+    boost::asio::io_context& io, sdbusplus::asio::object_server& objectServer,
+    std::shared_ptr<sdbusplus::asio::connection>& dbusConnection,
+    const ManagedObjectType& sensorConfigs,
+    const std::shared_ptr<boost::container::flat_set<std::string>>&
+        sensorsChanged)
+{
+    bool firstScan = sensorsChanged == nullptr;
+    for (const auto& [path, cfgData] : sensorConfigs)
+    {
+        const SensorBaseConfigMap* baseConfig = nullptr;
+        std::string sensorType;
+        for (const auto& [type, dt] : sensorTypes)
+        {
+            auto sensorBase = cfgData.find(configInterfaceName(type));
+            if (sensorBase != cfgData.end())
+            {
+                baseConfig = &sensorBase->second;
+                sensorType = type;
+                break;
+            }
+        }
+        if (baseConfig == nullptr)
+        {
+            continue;
+        }
+
+        auto findPSUName = baseConfig->find("Name");
+        if (findPSUName == baseConfig->end())
+        {
+            std::cerr << "could not determine configuration name for "
+                      << path.str << "\n";
+            continue;
+        }
+        const std::string* psuName =
+            std::get_if<std::string>(&(findPSUName->second));
+        if (psuName == nullptr)
+        {
+            std::cerr << "Cannot find psu name, invalid configuration\n";
+            continue;
+        }
+
+        // on rescans, only update sensors we were signaled by
+        if (!firstScan)
+        {
+            std::string psuNameStr = "/" + escapeName(*psuName);
+            auto it =
+                std::find_if(sensorsChanged->begin(), sensorsChanged->end(),
+                             [psuNameStr](std::string& s) {
+                                 return s.ends_with(psuNameStr);
+                             });
+
+            if (it == sensorsChanged->end())
+            {
+                continue;
+            }
+            sensorsChanged->erase(it);
+        }
+
+        auto findLabelObj = baseConfig->find("Labels");
+        if (findLabelObj == baseConfig->end())
+        {
+            std::cerr << "Simulating " << *psuName << " needs Labels\n";
+            continue;
+        }
+        std::vector<std::string> findLabels =
+            std::get<std::vector<std::string>>(findLabelObj->second);
+
+        std::vector<thresholds::Threshold> confThresholds;
+        if (!parseThresholdsFromConfig(cfgData, confThresholds))
+        {
+            std::cerr << "error populating total thresholds\n";
+        }
+        size_t thresholdConfSize = confThresholds.size();
+
+        PowerState readState = getPowerState(*baseConfig);
+        float pollRate = getPollRate(*baseConfig, PSUSensor::defaultSensorPoll);
+
+        int i = 1;
+        std::vector<std::string> psuNames;
+        do
+        {
+            psuNames.push_back(
+                escapeName(std::get<std::string>(findPSUName->second)));
+            findPSUName = baseConfig->find("Name" + std::to_string(i++));
+        } while (findPSUName != baseConfig->end());
+
+        for (const std::string& labelHead : findLabels)
+        {
+            // "pout1" takes the defaults of "pout"
+            std::string labelKey = labelHead.substr(
+                0, labelHead.find_last_not_of("0123456789") + 1);
+            auto findProperty = labelMatch.find(labelKey);
+            std::optional<std::string> unitType = simulatedUnitType(labelKey);
+            if (findProperty == labelMatch.end() || !unitType)
+            {
+                std::cerr << "Could not find matching default property for "
+                          << labelHead << "\n";
+                continue;
+            }
+            PSUProperty psuProperty = findProperty->second;
+            PowerState sensorReadState = readState;
+
+            try
+            {
+                auto findCustomName = baseConfig->find(labelHead + "_Name");
+                if (findCustomName != baseConfig->end())
+                {
+                    psuProperty.labelTypeName = std::visit(
+                        VariantToStringVisitor(), findCustomName->second);
+                }
+                auto findCustomMin = baseConfig->find(labelHead + "_Min");
+                if (findCustomMin != baseConfig->end())
+                {
+                    psuProperty.minReading = std::visit(
+                        VariantToDoubleVisitor(), findCustomMin->second);
+                }
+                auto findCustomMax = baseConfig->find(labelHead + "_Max");
+                if (findCustomMax != baseConfig->end())
+                {
+                    psuProperty.maxReading = std::visit(
+                        VariantToDoubleVisitor(), findCustomMax->second);
+                }
+                auto findPowerState =
+                    baseConfig->find(labelHead + "_PowerState");
+                if (findPowerState != baseConfig->end())
+                {
+                    setReadState(std::visit(VariantToStringVisitor(),
+                                            findPowerState->second),
+                                 sensorReadState);
+                }
+            }
+            catch (const std::invalid_argument&)
+            {
+                std::cerr << "Unable to parse configuration of " << labelHead
+                          << "\n";
+                continue;
+            }
+            if (!(psuProperty.minReading < psuProperty.maxReading))
+            {
+                std::cerr << "Min must be less than Max\n";
+                continue;
+            }
+
+            std::string sensorName = psuProperty.labelTypeName;
+            if (baseConfig->find(labelHead + "_Name") == baseConfig->end())
+            {
+                std::string nameIndexStr = "1";
+                size_t nameIndex = 0;
+                if (labelKey.size() < labelHead.size())
+                {
+                    nameIndexStr = labelHead.substr(labelKey.size());
+                    nameIndex = std::stoul(nameIndexStr);
+                    if (nameIndex > 0)
+                    {
+                        --nameIndex;
+                    }
+                }
+                if (psuNames.size() <= nameIndex)
+                {
+                    std::cerr << "Could not pair " << labelHead
+                              << " with a Name field\n";
+                    continue;
+                }
+                sensorName = psuNames[nameIndex] + " " + sensorName;
+                if (labelHead == "fan" + nameIndexStr)
+                {
+                    sensorName += nameIndexStr;
+                }
+            }
+            else if (sensorName.empty())
+            {
+                continue;
+            }
+
+            std::shared_ptr<simulation::Source> source =
+                simulation::getSource(sensorName);
+            if (!source)
+            {
+                std::cerr << "No simulation scenario for " << sensorName
+                          << "\n";
+                continue;
+            }
+
+            std::vector<thresholds::Threshold> sensorThresholds;
+            if (!parseThresholdsFromConfig(cfgData, sensorThresholds,
+                                           &labelHead))
+            {
+                std::cerr << "error populating thresholds for " << labelHead
+                          << "\n";
+            }
+
+            auto& sensor = sensors[sensorName];
+            sensor = nullptr;
+            sensor = std::make_shared<PSUSensor>(
+                "simulated", sensorType, objectServer, dbusConnection, io,
+                sensorName, std::move(sensorThresholds), path.str,
+                sensorReadState, sensorTable[*unitType], 1,
+                psuProperty.maxReading, psuProperty.minReading, 0.0, labelHead,
+                thresholdConfSize, pollRate, nullptr, source);
+            sensor->loadConfigurationRecords(cfgData);
+            sensor->setupRead();
+        }
+    }
+}
+
 static void createSensorsCallback(
     boost::asio::io_context& io, sdbusplus::asio::object_server& objectServer,
     std::shared_ptr<sdbusplus::asio::connection>& dbusConnection,
@@ -303,6 +538,16 @@ static void createSensorsCallback(
         sensorsChanged,
     bool activateOnly)
 {
+    if (simulation::enabled())
+    {
+        if (!activateOnly)
+        {
+            createSimulatedSensors(io, objectServer, dbusConnection,
+                                   sensorConfigs, sensorsChanged);
+        }
+        return;
+    }
+
     int numCreated = 0;
     bool firstScan = sensorsChanged == nullptr;
 
@@ -964,6 +1209,7 @@ static void createSensorsCallback(
                     psuProperty.maxReading, psuProperty.minReading,
                     psuProperty.sensorOffset, labelHead, thresholdConfSize,
                     pollRate, i2cDev);
+                sensors[sensorName]->loadConfigurationRecords(*sensorData);
                 sensors[sensorName]->setupRead();
                 ++numCreated;
                 if constexpr (debug)
@@ -1132,7 +1378,18 @@ static void powerStateChanged(
     boost::asio::io_context& io, sdbusplus::asio::object_server& objectServer,
     std::shared_ptr<sdbusplus::asio::connection>& dbusConnection)
 {
-    if (newState)
+    if (newState && simulation::enabled())
+    {
+        for (auto& [path, sensor] : sensors)
+        {
+            if (sensor != nullptr && sensor->readState == type &&
+                !sensor->isActive())
+            {
+                sensor->activate("simulated", nullptr);
+            }
+        }
+    }
+    else if (newState)
     {
         createSensors(io, objectServer, dbusConnection, nullptr, true);
     }
@@ -1148,16 +1405,14 @@ static void powerStateChanged(
     }
 }
 
-int main()
+static void start(boost::asio::io_context& io,
+                  std::shared_ptr<sdbusplus::asio::connection>& systemBus,
+                  sdbusplus::asio::object_server& objectServer)
 {
-    boost::asio::io_context io;
-    auto systemBus = std::make_shared<sdbusplus::asio::connection>(io);
-
-    sdbusplus::asio::object_server objectServer(systemBus, true);
-    objectServer.add_manager("/xyz/openbmc_project/sensors");
-    objectServer.add_manager("/xyz/openbmc_project/control");
+    reactor::addManager(objectServer, "/xyz/openbmc_project/sensors");
+    reactor::addManager(objectServer, "/xyz/openbmc_project/control");
     systemBus->request_name("xyz.openbmc_project.PSUSensor");
-    auto sensorsChanged =
+    static auto sensorsChanged =
         std::make_shared<boost::container::flat_set<std::string>>();
 
     propertyInitialize();
@@ -1172,8 +1427,8 @@ int main()
     boost::asio::post(io, [&]() {
         createSensors(io, objectServer, systemBus, nullptr, false);
     });
-    boost::asio::steady_timer filterTimer(io);
-    std::function<void(sdbusplus::message_t&)> eventHandler =
+    static boost::asio::steady_timer filterTimer(io);
+    static std::function<void(sdbusplus::message_t&)> eventHandler =
         [&](sdbusplus::message_t& message) {
             if (message.is_method_error())
             {
@@ -1196,8 +1451,8 @@ int main()
             });
         };
 
-    boost::asio::steady_timer cpuFilterTimer(io);
-    std::function<void(sdbusplus::message_t&)> cpuPresenceHandler =
+    static boost::asio::steady_timer cpuFilterTimer(io);
+    static std::function<void(sdbusplus::message_t&)> cpuPresenceHandler =
         [&](sdbusplus::message_t& message) {
             std::string path = message.get_path();
             boost::to_lower(path);
@@ -1255,7 +1510,7 @@ int main()
             });
         };
 
-    std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
+    static std::vector<std::unique_ptr<sdbusplus::bus::match_t>> matches =
         setupPropertiesChangedMatches(*systemBus, sensorTypes, eventHandler);
 
     matches.emplace_back(std::make_unique<sdbusplus::bus::match_t>(
@@ -1268,5 +1523,8 @@ int main()
     getPresentCpus(systemBus);
 
     setupManufacturingModeMatch(*systemBus);
-    io.run();
 }
+
+static const reactor::Registration registration("psu", start);
+
+} // namespace psusensor
@@ -2,7 +2,10 @@
 
 #include "dbus-sensor_config.h"
 
+#include "SensorHistory.hpp"
 #include "SensorPaths.hpp"
+#include "SensorStatistics.hpp"
+#include "ThresholdLogging.hpp"
 #include "Thresholds.hpp"
 #include "Utils.hpp"
 
@@ -12,14 +15,11 @@
 #include <limits>
 #include <memory>
 #include <string>
+#include <utility>
 #include <vector>
 
 constexpr size_t sensorFailedPollTimeMs = 5000;
 
-// Enable useful logging with sensor instrumentation
-// This is intentionally not DEBUG, avoid clash with usage in .cpp files
-constexpr bool enableInstrumentation = false;
-
 constexpr const char* sensorValueInterface = "xyz.openbmc_project.Sensor.Value";
 constexpr const char* valueMutabilityInterfaceName =
     "xyz.openbmc_project.Sensor.ValueMutability";
@@ -29,17 +29,6 @@ constexpr const char* operationalInterfaceName =
     "xyz.openbmc_project.State.Decorator.OperationalStatus";
 constexpr const size_t errorThreshold = 5;
 
-struct SensorInstrumentation
-{
-    // These are for instrumentation for debugging
-    int numCollectsGood = 0;
-    int numCollectsMiss = 0;
-    int numStreakGreats = 0;
-    int numStreakMisses = 0;
-    double minCollected = 0.0;
-    double maxCollected = 0.0;
-};
-
 struct SetSensorError : sdbusplus::exception_t
 {
     const char* name() const noexcept override
@@ -56,6 +45,38 @@ struct SetSensorError : sdbusplus::exception_t
     }
 };
 
+struct InvalidThresholdError : sdbusplus::exception_t
+{
+    const char* name() const noexcept override
+    {
+        return "xyz.openbmc_project.Common.Error.InvalidArgument";
+    }
+    const char* description() const noexcept override
+    {
+        return "Invalid threshold property value.";
+    }
+    int get_errno() const noexcept override
+    {
+        return EINVAL;
+    }
+};
+
+struct HysteresisNotPersistentError : sdbusplus::exception_t
+{
+    const char* name() const noexcept override
+    {
+        return "xyz.openbmc_project.Common.Error.Unavailable";
+    }
+    const char* description() const noexcept override
+    {
+        return "Threshold configuration has no Hysteresis to persist to.";
+    }
+    int get_errno() const noexcept override
+    {
+        return ENOTSUP;
+    }
+};
+
 struct Sensor
 {
     Sensor(const std::string& name,
@@ -71,11 +92,16 @@ struct Sensor
         minValue(min), thresholds(std::move(thresholdData)),
         hysteresisTrigger((max - min) * 0.01),
         hysteresisPublish((max - min) * 0.0001), dbusConnection(conn),
-        readState(readState),
-        instrumentation(enableInstrumentation
-                            ? std::make_unique<SensorInstrumentation>()
-                            : nullptr)
-    {}
+        readState(readState)
+    {
+        for (const auto& threshold : thresholds)
+        {
+            if (threshold.level == thresholds::Level::RATEOFCHANGE)
+            {
+                rateOfChange.addWindow(threshold.rateWindow);
+            }
+        }
+    }
     virtual ~Sensor() = default;
     virtual void checkThresholds() = 0;
     std::string name;
@@ -106,7 +132,12 @@ struct Sensor
     std::shared_ptr<sdbusplus::asio::connection> dbusConnection;
     PowerState readState;
     size_t errCount{0};
-    std::unique_ptr<SensorInstrumentation> instrumentation;
+    thresholds::RateOfChange rateOfChange;
+    thresholds::EventLog thresholdEvents;
+    std::shared_ptr<sdbusplus::asio::dbus_interface> thresholdEventsInterface;
+    std::shared_ptr<statistics::SensorStatistics> sensorStatistics;
+    std::shared_ptr<history::SensorHistory> sensorHistory;
+    std::shared_ptr<thresholds::LogEntries> thresholdLogEntries;
 
     // This member variable provides a hook that can be used to receive
     // notification whenever this Sensor's value is externally set via D-Bus.
@@ -135,92 +166,6 @@ struct Sensor
         return interface;
     }
 
-    void updateInstrumentation(double readValue) const
-    {
-        // Do nothing if this feature is not enabled
-        if constexpr (!enableInstrumentation)
-        {
-            return;
-        }
-        if (!instrumentation)
-        {
-            return;
-        }
-
-        // Save some typing
-        auto& inst = *instrumentation;
-
-        // Show constants if first reading (even if unsuccessful)
-        if ((inst.numCollectsGood == 0) && (inst.numCollectsMiss == 0))
-        {
-            std::cerr << "Sensor " << name << ": Configuration min=" << minValue
-                      << ", max=" << maxValue << ", type=" << configInterface
-                      << ", path=" << configurationPath << "\n";
-        }
-
-        // Sensors can use "nan" to indicate unavailable reading
-        if (!std::isfinite(readValue))
-        {
-            // Only show this if beginning a new streak
-            if (inst.numStreakMisses == 0)
-            {
-                std::cerr << "Sensor " << name
-                          << ": Missing reading, Reading counts good="
-                          << inst.numCollectsGood
-                          << ", miss=" << inst.numCollectsMiss
-                          << ", Prior good streak=" << inst.numStreakGreats
-                          << "\n";
-            }
-
-            inst.numStreakGreats = 0;
-            ++(inst.numCollectsMiss);
-            ++(inst.numStreakMisses);
-
-            return;
-        }
-
-        // Only show this if beginning a new streak and not the first time
-        if ((inst.numStreakGreats == 0) && (inst.numCollectsGood != 0))
-        {
-            std::cerr << "Sensor " << name
-                      << ": Recovered reading, Reading counts good="
-                      << inst.numCollectsGood
-                      << ", miss=" << inst.numCollectsMiss
-                      << ", Prior miss streak=" << inst.numStreakMisses << "\n";
-        }
-
-        // Initialize min/max if the first successful reading
-        if (inst.numCollectsGood == 0)
-        {
-            std::cerr << "Sensor " << name << ": First reading=" << readValue
-                      << "\n";
-
-            inst.minCollected = readValue;
-            inst.maxCollected = readValue;
-        }
-
-        inst.numStreakMisses = 0;
-        ++(inst.numCollectsGood);
-        ++(inst.numStreakGreats);
-
-        // Only provide subsequent output if new min/max established
-        if (readValue < inst.minCollected)
-        {
-            std::cerr << "Sensor " << name << ": Lowest reading=" << readValue
-                      << "\n";
-
-            inst.minCollected = readValue;
-        }
-
-        if (readValue > inst.maxCollected)
-        {
-            std::cerr << "Sensor " << name << ": Highest reading=" << readValue
-                      << "\n";
-
-            inst.maxCollected = readValue;
-        }
-    }
-
     int setSensorValue(const double& newValue, double& oldValue)
     {
         if (!internalSet)
@@ -276,7 +221,12 @@ struct Sensor
         {
             if (std::isnan(threshold.hysteresis))
             {
-                threshold.hysteresis = hysteresisTrigger;
+                // Rate-of-change limits are in units per second, so the
+                // range of the reading says nothing about them
+                threshold.hysteresis =
+                    threshold.level == thresholds::Level::RATEOFCHANGE
+                        ? std::abs(threshold.value) * 0.1
+                        : hysteresisTrigger;
             }
 
             std::shared_ptr<sdbusplus::asio::dbus_interface> iface =
@@ -292,6 +242,10 @@ struct Sensor
                 propertyLevel(threshold.level, threshold.direction);
             std::string alarm =
                 propertyAlarm(threshold.level, threshold.direction);
+            std::string hysteresis =
+                propertyHysteresis(threshold.level, threshold.direction);
+            std::string writeable =
+                propertyWriteable(threshold.level, threshold.direction);
 
             if ((level.empty()) || (alarm.empty()))
             {
@@ -302,6 +256,10 @@ struct Sensor
             iface->register_property(
                 level, threshold.value,
                 [&, label, thresSize](const double& request, double& oldValue) {
+                    if (!threshold.writeable)
+                    {
+                        throw SetSensorError();
+                    }
                     oldValue = request; // todo, just let the config do this?
                     threshold.value = request;
                     thresholds::persistThreshold(
@@ -319,12 +277,44 @@ struct Sensor
                     return 1;
                 });
             iface->register_property(alarm, false);
+            iface->register_property(
+                hysteresis, threshold.hysteresis,
+                [&, label, thresSize](const double& request, double& oldValue) {
+                    if (!threshold.writeable)
+                    {
+                        throw SetSensorError();
+                    }
+                    if (!std::isfinite(request) || request < 0.0)
+                    {
+                        throw InvalidThresholdError();
+                    }
+                    // It would silently revert to the configuration on the
+                    // next restart
+                    if (!threshold.hysteresisConfigured)
+                    {
+                        throw HysteresisNotPersistentError();
+                    }
+                    oldValue = request;
+                    threshold.hysteresis = request;
+                    thresholds::persistHysteresis(
+                        configurationPath, configInterface, threshold,
+                        dbusConnection, thresSize, label);
+                    value = std::numeric_limits<double>::quiet_NaN();
+                    return 1;
+                });
+            iface->register_property(writeable, threshold.writeable);
         }
         if (!sensorInterface->initialize())
         {
             std::cerr << "error initializing value interface\n";
         }
 
+        sensorStatistics = std::make_shared<statistics::SensorStatistics>(
+            dbusConnection, sensorInterface->get_object_path());
+        sensorHistory = std::make_shared<history::SensorHistory>(
+            dbusConnection, sensorInterface->get_object_path(),
+            std::make_pair(minValue, maxValue));
+
         for (auto& thresIface : thresholdInterfaces)
         {
             if (thresIface)
@@ -336,6 +326,29 @@ struct Sensor
             }
         }
 
+        if (!thresholds.empty() && !thresholdEventsInterface)
+        {
+            thresholdEventsInterface =
+                std::make_shared<sdbusplus::asio::dbus_interface>(
+                    dbusConnection, sensorInterface->get_object_path(),
+                    thresholds::eventsInterfaceName);
+            thresholdEventsInterface->register_method(
+                "GetEvents", [this]() { return thresholdEvents.entries(); });
+            thresholdEventsInterface->register_method(
+                "ClearLatchedAlarms",
+                [this]() { thresholds::clearLatchedAlarms(this); });
+            if (!thresholdEventsInterface->initialize())
+            {
+                std::cerr << "error initializing threshold events interface\n";
+            }
+        }
+
+        if (!thresholds.empty() && !thresholdLogEntries)
+        {
+            thresholdLogEntries = std::make_shared<thresholds::LogEntries>(
+                dbusConnection, name, sensorInterface->get_object_path());
+        }
+
         if (isValueMutable)
         {
             valueMutabilityInterface =
@@ -383,44 +396,45 @@ struct Sensor
         }
     }
 
-    static std::string propertyLevel(const Level lev, const Direction dir)
+    // Applies the Statistics, History and EventLog records of the
+    // configuration the daemon already read for the sensor, once
+    // setInitialProperties has created what they configure
+    void loadConfigurationRecords(const SensorData& sensorData)
     {
-        for (const thresholds::ThresholdDefinition& prop :
-             thresholds::thresProp)
+        if (sensorStatistics)
         {
-            if (prop.level == lev)
-            {
-                if (dir == Direction::HIGH)
-                {
-                    return std::string(prop.levelName) + "High";
-                }
-                if (dir == Direction::LOW)
-                {
-                    return std::string(prop.levelName) + "Low";
-                }
-            }
+            sensorStatistics->loadConfiguration(sensorData, configInterface);
         }
-        return "";
+        if (sensorHistory)
+        {
+            sensorHistory->loadConfiguration(sensorData, configInterface);
+        }
+        if (thresholdLogEntries)
+        {
+            thresholdLogEntries->loadConfiguration(sensorData,
+                                                   configInterface);
+        }
+    }
+
+    static std::string propertyLevel(const Level lev, const Direction dir)
+    {
+        return propertyName(lev, "", dir);
     }
 
     static std::string propertyAlarm(const Level lev, const Direction dir)
     {
-        for (const thresholds::ThresholdDefinition& prop :
-             thresholds::thresProp)
-        {
-            if (prop.level == lev)
-            {
-                if (dir == Direction::HIGH)
-                {
-                    return std::string(prop.levelName) + "AlarmHigh";
-                }
-                if (dir == Direction::LOW)
-                {
-                    return std::string(prop.levelName) + "AlarmLow";
-                }
-            }
-        }
-        return "";
+        return propertyName(lev, "Alarm", dir);
+    }
+
+    static std::string propertyHysteresis(const Level lev,
+                                          const Direction dir)
+    {
+        return propertyName(lev, "Hysteresis", dir);
+    }
+
+    static std::string propertyWriteable(const Level lev, const Direction dir)
+    {
+        return propertyName(lev, "Writeable", dir);
     }
 
     bool readingStateGood() const
@@ -500,7 +514,16 @@ struct Sensor
         }
 
         updateValueProperty(newValue);
-        updateInstrumentation(newValue);
+        rateOfChange.addSample(newValue,
+                               thresholds::RateOfChange::Clock::now());
+        if (sensorStatistics)
+        {
+            sensorStatistics->addSample(newValue);
+        }
+        if (sensorHistory)
+        {
+            sensorHistory->addSample(newValue);
+        }
 
         // Always check thresholds after changing the value,
         // as the test against hysteresisTrigger now takes place in
@@ -544,6 +567,29 @@ struct Sensor
     }
 
   private:
+    // Builds e.g. "WarningAlarmHigh" from Level::WARNING, "Alarm" and
+    // Direction::HIGH
+    static std::string propertyName(const Level lev, const char* infix,
+                                    const Direction dir)
+    {
+        for (const thresholds::ThresholdDefinition& prop :
+             thresholds::thresProp)
+        {
+            if (prop.level == lev)
+            {
+                if (dir == Direction::HIGH)
+                {
+                    return std::string(prop.levelName) + infix + "High";
+                }
+                if (dir == Direction::LOW)
+                {
+                    return std::string(prop.levelName) + infix + "Low";
+                }
+            }
+        }
+        return "";
+    }
+
     // If one of the thresholds for a dbus interface is provided
     // we have to set the other one as dbus properties are never
     // optional.
@@ -576,8 +622,10 @@ struct Sensor
             {
                 continue;
             }
+            std::chrono::milliseconds rateWindow = thisThreshold.rateWindow;
             thresholds.emplace_back(thisThreshold.level, opposite,
-                                    std::numeric_limits<double>::quiet_NaN());
+                                    std::numeric_limits<double>::quiet_NaN())
+                .rateWindow = rateWindow;
         }
     }
 
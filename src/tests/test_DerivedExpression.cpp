@@ -0,0 +1,139 @@
+#include "Expression.hpp"
+#include "Utils.hpp"
+
+#include <limits>
+#include <optional>
+#include <string>
+#include <vector>
+
+#include <gtest/gtest.h>
+
+using derived::Expression;
+using derived::Input;
+using derived::NaNHandling;
+using derived::Operation;
+
+static constexpr double nan = std::numeric_limits<double>::quiet_NaN();
+static const std::string configInterface =
+    "xyz.openbmc_project.Configuration.DerivedSensor";
+
+static Expression makeExpression(
+    Operation operation, size_t inputCount,
+    NaNHandling nanHandling = NaNHandling::propagate)
+{
+    Expression expression;
+    expression.operation = operation;
+    expression.nanHandling = nanHandling;
+    for (size_t ii = 0; ii < inputCount; ii++)
+    {
+        expression.inputs.emplace_back(Input{"in" + std::to_string(ii), 1.0});
+    }
+    return expression;
+}
+
+TEST(DerivedExpression, Operations)
+{
+    std::vector<double> values = {3.0, 1.0, 5.0};
+    EXPECT_EQ(makeExpression(Operation::sum, 3).evaluate(values), 9.0);
+    EXPECT_EQ(makeExpression(Operation::minimum, 3).evaluate(values), 1.0);
+    EXPECT_EQ(makeExpression(Operation::maximum, 3).evaluate(values), 5.0);
+    EXPECT_EQ(makeExpression(Operation::average, 3).evaluate(values), 3.0);
+
+    std::vector<double> pair = {30.0, 22.5};
+    EXPECT_EQ(makeExpression(Operation::difference, 2).evaluate(pair), 7.5);
+}
+
+TEST(DerivedExpression, LinearCombination)
+{
+    Expression expression = makeExpression(Operation::linearCombination, 2);
+    expression.inputs[0].coefficient = 2.0;
+    expression.inputs[1].coefficient = -0.5;
+    expression.offset = 10.0;
+
+    std::vector<double> values = {4.0, 6.0};
+    EXPECT_EQ(expression.evaluate(values), 15.0);
+}
+
+TEST(DerivedExpression, MissingInputMakesUnavailable)
+{
+    // No result, rather than a NaN one, so that the sensor isn't Available
+    std::vector<double> values = {3.0, nan};
+    EXPECT_EQ(makeExpression(Operation::sum, 2).evaluate(values), std::nullopt);
+
+    std::vector<double> none = {nan, nan};
+    EXPECT_EQ(makeExpression(Operation::sum, 2).evaluate(none), std::nullopt);
+}
+
+TEST(DerivedExpression, NaNIgnored)
+{
+    std::vector<double> values = {3.0, nan, 5.0};
+    EXPECT_EQ(makeExpression(Operation::average, 3, NaNHandling::ignore)
+                  .evaluate(values),
+              4.0);
+
+    std::vector<double> pair = {3.0, nan};
+    EXPECT_EQ(makeExpression(Operation::difference, 2, NaNHandling::ignore)
+                  .evaluate(pair),
+              std::nullopt);
+
+    std::vector<double> none = {nan, nan};
+    EXPECT_EQ(
+        makeExpression(Operation::sum, 2, NaNHandling::ignore).evaluate(none),
+        std::nullopt);
+}
+
+TEST(DerivedExpression, NaNAsZero)
+{
+    std::vector<double> values = {3.0, nan, 5.0};
+    EXPECT_EQ(makeExpression(Operation::average, 3, NaNHandling::zero)
+                  .evaluate(values),
+              8.0 / 3.0);
+}
+
+TEST(DerivedExpression, ParseSensorsAndRecords)
+{
+    SensorData data;
+    data[configInterface] = {
+        {"Operation", std::string("LinearCombination")},
+        {"NaNHandling", std::string("Zero")},
+        {"Offset", 1.5},
+        {"Sensors", std::vector<std::string>{"PSU1 Input Power"}}};
+    data[configInterface + ".Inputs1"] = {
+        {"Sensor", std::string("power/Fan Power")}};
+    data[configInterface + ".Inputs0"] = {
+        {"Sensor", std::string("PSU2 Input Power")}, {"Coefficient", 0.5}};
+
+    std::optional<Expression> expression =
+        derived::parseExpression(data, configInterface);
+    ASSERT_TRUE(expression);
+    EXPECT_EQ(expression->operation, Operation::linearCombination);
+    EXPECT_EQ(expression->nanHandling, NaNHandling::zero);
+    EXPECT_DOUBLE_EQ(expression->offset, 1.5);
+    ASSERT_EQ(expression->inputs.size(), 3U);
+    EXPECT_EQ(expression->inputs[0].name, "PSU1_Input_Power");
+    EXPECT_DOUBLE_EQ(expression->inputs[0].coefficient, 1.0);
+    EXPECT_EQ(expression->inputs[1].name, "PSU2_Input_Power");
+    EXPECT_DOUBLE_EQ(expression->inputs[1].coefficient, 0.5);
+    EXPECT_EQ(expression->inputs[2].name, "power/Fan_Power");
+}
+
+TEST(DerivedExpression, ParseRejectsInvalid)
+{
+    SensorData data;
+    data[configInterface] = {
+        {"Operation", std::string("Product")},
+        {"Sensors", std::vector<std::string>{"a", "b"}}};
+    EXPECT_FALSE(derived::parseExpression(data, configInterface));
+
+    data[configInterface]["Operation"] = std::string("Difference");
+    data[configInterface]["Sensors"] = std::vector<std::string>{"a"};
+    EXPECT_FALSE(derived::parseExpression(data, configInterface));
+
+    data[configInterface]["Sensors"] = std::vector<std::string>{};
+    data[configInterface]["Operation"] = std::string("Sum");
+    EXPECT_FALSE(derived::parseExpression(data, configInterface));
+
+    data[configInterface]["Sensors"] = std::vector<std::string>{"a"};
+    data[configInterface]["NaNHandling"] = std::string("Sometimes");
+    EXPECT_FALSE(derived::parseExpression(data, configInterface));
+}
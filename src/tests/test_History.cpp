@@ -0,0 +1,220 @@
+#include "SensorHistory.hpp"
+
+#include <array>
+#include <chrono>
+#include <cstdint>
+#include <cstdlib>
+#include <filesystem>
+#include <limits>
+#include <new>
+#include <string>
+#include <tuple>
+#include <utility>
+#include <vector>
+
+#include <gtest/gtest.h>
+
+namespace fs = std::filesystem;
+using history::RingBuffer;
+using history::Sample;
+
+// Steps of 1/64, so that the readings of the tests are stored exactly
+constexpr std::pair<double, double> testRange{0.0, 65534.0 / 64};
+
+TEST(HistoryRingBuffer, WrapsAround)
+{
+    RingBuffer ring(3, testRange);
+    for (uint64_t ii = 1; ii <= 5; ii++)
+    {
+        ring.push(ii * 10, static_cast<double>(ii));
+    }
+    EXPECT_EQ(ring.size(), 3U);
+
+    std::vector<Sample> samples = ring.query(0, 100);
+    std::vector<Sample> expected{{30, 3.0}, {40, 4.0}, {50, 5.0}};
+    EXPECT_EQ(samples, expected);
+}
+
+TEST(HistoryRingBuffer, QueryRange)
+{
+    RingBuffer ring(10, testRange);
+    for (uint64_t ii = 1; ii <= 5; ii++)
+    {
+        ring.push(ii * 10, static_cast<double>(ii));
+    }
+    std::vector<Sample> expected{{20, 2.0}, {30, 3.0}};
+    EXPECT_EQ(ring.query(15, 30), expected);
+    EXPECT_TRUE(ring.query(60, 100).empty());
+}
+
+TEST(HistoryRingBuffer, QuantizesToRange)
+{
+    RingBuffer ring(4, {-100.0, 100.0});
+    ring.push(10, 12.345);
+    ring.push(20, 500.0);
+    ring.push(30, -500.0);
+
+    std::vector<Sample> samples = ring.query(0, 100);
+    ASSERT_EQ(samples.size(), 3U);
+    EXPECT_NEAR(std::get<1>(samples[0]), 12.345, 200.0 / 65534);
+    EXPECT_EQ(std::get<1>(samples[1]), 100.0);
+    EXPECT_EQ(std::get<1>(samples[2]), -100.0);
+}
+
+TEST(HistoryRingBuffer, KeepsTimestampsAcrossGaps)
+{
+    RingBuffer ring(3, testRange);
+    ring.push(10, 1.0);
+    ring.push(40, 2.0);
+    // Further apart than one record can tell
+    uint64_t later = 40 + (70000 * 10);
+    ring.push(later, 3.0);
+    // Only moves forward in whole periods
+    ring.push(later + 5, 4.0);
+
+    std::vector<Sample> expected{{40, 2.0}, {later, 3.0}};
+    EXPECT_EQ(ring.query(0, later), expected);
+    EXPECT_EQ(ring.size(), 3U);
+
+    // Nothing before a gap longer than the ring holds is kept
+    ring.push(later + (1000000 * 10), 5.0);
+    EXPECT_EQ(ring.size(), 1U);
+}
+
+TEST(HistoryRingBuffer, BackingFileSurvivesRestart)
+{
+    auto dir = std::to_array("./testDirXXXXXX");
+    std::string testDir = mkdtemp(dir.data());
+    if (testDir.empty())
+    {
+        throw std::bad_alloc();
+    }
+    fs::path file = fs::path(testDir) / "history" / "voltage_P12V";
+
+    {
+        RingBuffer ring(4, testRange, file);
+        ring.push(10, 1.5);
+        ring.push(20, 2.5);
+    }
+    {
+        RingBuffer ring(4, testRange, file);
+        std::vector<Sample> expected{{10, 1.5}, {20, 2.5}};
+        EXPECT_EQ(ring.query(0, 100), expected);
+    }
+    {
+        // A different capacity or range invalidates the stored history
+        RingBuffer ring(8, testRange, file);
+        EXPECT_EQ(ring.size(), 0U);
+    }
+    {
+        RingBuffer ring(4, testRange, file);
+        ring.push(10, 1.5);
+    }
+    {
+        RingBuffer ring(4, {0.0, 100.0}, file);
+        EXPECT_EQ(ring.size(), 0U);
+    }
+
+    fs::remove_all(testDir);
+}
+
+TEST(HistoryRingBuffer, WritesOnSync)
+{
+    auto dir = std::to_array("./testDirXXXXXX");
+    std::string testDir = mkdtemp(dir.data());
+    if (testDir.empty())
+    {
+        throw std::bad_alloc();
+    }
+    fs::path file = fs::path(testDir) / "voltage_P12V";
+
+    RingBuffer ring(4, testRange, file);
+    ring.push(10, 1.5);
+    ring.push(20, 2.5);
+    EXPECT_EQ(RingBuffer(4, testRange, file).size(), 0U);
+
+    ring.sync();
+    ring.push(30, 3.5);
+    ring.push(40, 4.5);
+    ring.push(50, 5.5);
+    ring.sync();
+    std::vector<Sample> expected{{20, 2.5}, {30, 3.5}, {40, 4.5}, {50, 5.5}};
+    EXPECT_EQ(RingBuffer(4, testRange, file).query(0, 100), expected);
+
+    fs::remove_all(testDir);
+}
+
+TEST(HistoryFiles, RemovesExpired)
+{
+    auto dir = std::to_array("./testDirXXXXXX");
+    std::string testDir = mkdtemp(dir.data());
+    if (testDir.empty())
+    {
+        throw std::bad_alloc();
+    }
+    fs::path stale = fs::path(testDir) / "voltage_Removed";
+    fs::path current = fs::path(testDir) / "voltage_P12V";
+    {
+        // Keeps its records for 40 seconds
+        RingBuffer staleRing(4, testRange, stale, std::chrono::seconds(10));
+        RingBuffer currentRing(4, testRange, current, std::chrono::seconds(10));
+    }
+    EXPECT_EQ(RingBuffer::fileRetention(stale), std::chrono::seconds(40));
+
+    auto now = fs::file_time_type::clock::now();
+    fs::last_write_time(stale, now - std::chrono::seconds(60));
+    fs::last_write_time(current, now - std::chrono::seconds(20));
+    history::removeExpiredFiles(testDir, now);
+    EXPECT_FALSE(fs::exists(stale));
+    EXPECT_TRUE(fs::exists(current));
+
+    fs::remove_all(testDir);
+}
+
+TEST(HistoryDownsampler, AveragesEachPeriod)
+{
+    RingBuffer ring(10, testRange);
+    history::Downsampler downsampler(std::chrono::seconds(10), ring);
+
+    downsampler.addSample(1.0, 100);
+    downsampler.addSample(3.0, 105);
+    EXPECT_EQ(ring.size(), 0U);
+
+    // Nothing is recorded for the period the sensor was unavailable
+    downsampler.addSample(std::numeric_limits<double>::quiet_NaN(), 112);
+    downsampler.addSample(5.0, 131);
+    downsampler.addSample(6.0, 141);
+
+    std::vector<Sample> expected{{100, 2.0}, {130, 5.0}};
+    EXPECT_EQ(ring.query(0, 1000), expected);
+}
+
+TEST(HistoryConfig, Parse)
+{
+    SensorBaseConfigMap cfg;
+    auto config = history::parseConfig(cfg);
+    ASSERT_TRUE(config);
+    EXPECT_EQ(config->capacity(), 360U);
+    EXPECT_FALSE(config->persistent);
+
+    cfg["Retention"] = 60.0;
+    cfg["Resolution"] = 120.0;
+    EXPECT_FALSE(history::parseConfig(cfg));
+
+    cfg["Resolution"] = 0.0;
+    EXPECT_FALSE(history::parseConfig(cfg));
+
+    cfg["Resolution"] = 1.0;
+    cfg["Persistent"] = true;
+    config = history::parseConfig(cfg);
+    ASSERT_TRUE(config);
+    EXPECT_EQ(config->capacity(), 60U);
+    EXPECT_TRUE(config->persistent);
+}
+
+TEST(HistoryFileName, StripsSensorRoot)
+{
+    EXPECT_EQ(history::SensorHistory::fileName(
+                  "/xyz/openbmc_project/sensors/voltage/P12V"),
+              "voltage_P12V");
+}
@@ -1,7 +1,16 @@
+#include "Thresholds.hpp"
+#include "Utils.hpp"
+#include "ipmb/IpmbController.hpp"
+#include "ipmb/IpmbEvent.hpp"
+#include "ipmb/IpmbSDRSensor.hpp"
 #include "ipmb/IpmbSensor.hpp"
 
+#include <chrono>
 #include <cstddef>
 #include <cstdint>
+#include <optional>
+#include <stdexcept>
+#include <string>
 #include <vector>
 
 #include <gtest/gtest.h>
@@ -288,4 +297,481 @@ TEST(IPMBSensor, FifteenBitInvalid)
                                             responseValue, errCount));
 }
 
+
+TEST(IPMBSensor, PresetRequest)
+{
+    IpmbRequestConfig preset = IpmbSensor::presetRequest(
+        IpmbType::PXE1410CVR, IpmbSubType::temp, 0x40, 0x03);
+    EXPECT_EQ(preset.netfn, ipmi::me_bridge::netFn);
+    EXPECT_EQ(preset.command, ipmi::me_bridge::sendRawPmbus);
+    EXPECT_EQ(preset.initCommand, ipmi::me_bridge::sendRawPmbus);
+    ASSERT_TRUE(preset.commandData);
+    EXPECT_EQ((*preset.commandData)[4], 0x03);
+    EXPECT_EQ((*preset.commandData)[5], 0x40);
+    EXPECT_EQ(preset.readingFormat, ReadingFormat::linearElevenBit);
+    EXPECT_FALSE(preset.maxValue);
+
+    preset = IpmbSensor::presetRequest(IpmbType::custom, IpmbSubType::util, 0,
+                                       0);
+    EXPECT_FALSE(preset.netfn);
+    EXPECT_FALSE(preset.command);
+    EXPECT_EQ(preset.maxValue, 100.0);
+
+    EXPECT_THROW(IpmbSensor::presetRequest(IpmbType::none, IpmbSubType::temp,
+                                           0, 0),
+                 std::runtime_error);
+}
+
+TEST(IPMBSensor, RequestConfigOverridesPreset)
+{
+    SensorBaseConfigMap cfg = {
+        {"Command", uint64_t{0xd9}},
+        {"CommandData",
+         std::vector<std::string>{"0x57", "0x01", "0x00", "22"}},
+        {"ReadingFormat", std::string("ElevenBitShift")},
+        {"MaxValue", 125.0},
+    };
+    IpmbRequestConfig request = IpmbSensor::parseRequestConfig(cfg);
+    EXPECT_FALSE(request.netfn);
+    EXPECT_EQ(request.command, 0xd9);
+    EXPECT_EQ(request.commandData,
+              (std::vector<uint8_t>{0x57, 0x01, 0x00, 0x16}));
+
+    IpmbRequestConfig preset = IpmbSensor::presetRequest(
+        IpmbType::meSensor, IpmbSubType::temp, 0x40, 0x03);
+    preset.update(request);
+    EXPECT_EQ(preset.commandAddress, 1);
+    EXPECT_EQ(preset.netfn, ipmi::sensor::netFn);
+    EXPECT_EQ(preset.command, 0xd9);
+    EXPECT_EQ(preset.commandData, request.commandData);
+    EXPECT_EQ(preset.readingFormat, ReadingFormat::elevenBitShift);
+    EXPECT_EQ(preset.maxValue, 125.0);
+}
+
+TEST(IPMBSensor, RequestConfigInvalid)
+{
+    SensorBaseConfigMap cfg = {{"NetFn", uint64_t{0x100}}};
+    EXPECT_THROW(IpmbSensor::parseRequestConfig(cfg), std::invalid_argument);
+
+    cfg = {{"CommandData", std::vector<std::string>{"0x57", "0x1ff"}}};
+    EXPECT_THROW(IpmbSensor::parseRequestConfig(cfg), std::invalid_argument);
+
+    cfg = {{"InitData", std::vector<std::string>{"page"}}};
+    EXPECT_THROW(IpmbSensor::parseRequestConfig(cfg), std::invalid_argument);
+
+    cfg = {{"CommandData", std::string("0x57")}};
+    EXPECT_THROW(IpmbSensor::parseRequestConfig(cfg), std::invalid_argument);
+
+    cfg = {{"ReadingFormat", std::string("TwelveBit")}};
+    EXPECT_THROW(IpmbSensor::parseRequestConfig(cfg), std::invalid_argument);
+}
+
+// A full sensor record as IpmbSDRDevice collects it, with the next record ID
+// ahead of each 16 bytes of the record
+static std::vector<uint8_t> sdrType01Record()
+{
+    std::vector<uint8_t> data(60, 0);
+    data[sdr::sdrType] = 0x01;
+    data[sdr::sdrSensorNum] = 0x21;
+    data[sdrtype01::readingTypeByte] = sdr::readingTypeThreshold;
+    // Thresholds are readable, all but upper non-recoverable
+    data[sdrtype01::sensorCapability] = 0x04;
+    data[sdrtype01::readingMaskLSB] = 0x1f;
+    data[sdrtype01::sdrNegHandle] = 0x80;
+    data[sdrtype01::sdrUnitType] = sdrtype01::unitDegreesC;
+    data[sdrtype01::mDataByte] = 2;
+    data[sdrtype01::bDataByte] = 20;
+    // B exponent of -1
+    data[sdrtype01::rbExpDataByte] = 0x0f;
+    data[sdrtype01::upperNonRecoverableThreshold] = 50;
+    data[sdrtype01::upperCriticalThreshold] = 45;
+    data[sdrtype01::upperNonCriticalThreshold] = 40;
+    data[sdrtype01::lowerNonCriticalThreshold] = 0;
+    data[sdrtype01::lowerCriticalThreshold] = 0xfb;
+    data[sdrtype01::lowerNonRecoverableThreshold] = 0xf0;
+    data[sdrtype01::positiveHysteresis] = 2;
+    data[sdrtype01::negativeHysteresis] = 3;
+    return data;
+}
+
+TEST(IPMBSDR, Type01Record)
+{
+    std::vector<uint8_t> data = sdrType01Record();
+    IpmbSDRDevice::checkSDRType01Threshold(data, 7, "CPU Temp");
+
+    ASSERT_EQ(sensorRecord[7].size(), 1);
+    const SensorInfo& info = sensorRecord[7][0];
+    EXPECT_EQ(info.sensorReadName, "CPU Temp");
+    EXPECT_EQ(info.sensorNumber, 0x21);
+    EXPECT_NE(info.sensCap, sdrtype01::sdrSensNoThres);
+    // 2 * 45 + 2.0, and 2 * -5 + 2.0 as the readings are two's complement
+    EXPECT_DOUBLE_EQ(info.thresUpperCri, 92.0);
+    EXPECT_DOUBLE_EQ(info.thresLowerCri, -8.0);
+    EXPECT_DOUBLE_EQ(info.thresUpperNonRec, 102.0);
+    EXPECT_DOUBLE_EQ(info.thresUpperNonCri, 82.0);
+    EXPECT_DOUBLE_EQ(info.thresLowerNonCri, 2.0);
+    EXPECT_DOUBLE_EQ(info.thresLowerNonRec, -30.0);
+    EXPECT_EQ(info.readableThresholds, 0x1f);
+    // Without B
+    EXPECT_DOUBLE_EQ(info.hysteresisPositive, 4.0);
+    EXPECT_DOUBLE_EQ(info.hysteresisNegative, 6.0);
+    EXPECT_FALSE(info.isDiscrete());
+
+    const SensorValConversion& conversion = sensorValRecord[7][0x21];
+    EXPECT_EQ(IpmbSDRDevice::sensorTypeName(info, conversion), "temperature");
+    EXPECT_DOUBLE_EQ(IpmbSDRDevice::sensorReading(conversion, 0xff), 0.0);
+
+    sensorRecord.erase(7);
+    sensorValRecord.erase(7);
+}
+
+TEST(IPMBSDR, Type01NegativeM)
+{
+    std::vector<uint8_t> data = sdrType01Record();
+    data[sdrtype01::mDataByte] = 0xff;
+    data[sdrtype01::mTolDataByte] = 0xc0;
+    IpmbSDRDevice::checkSDRType01Threshold(data, 7, "Inverted");
+
+    const SensorValConversion& conversion = sensorValRecord[7][0x21];
+    EXPECT_EQ(conversion.mValue, -1);
+    EXPECT_DOUBLE_EQ(IpmbSDRDevice::sensorReading(conversion, 10), -8.0);
+
+    sensorRecord.erase(7);
+    sensorValRecord.erase(7);
+}
+
+TEST(IPMBSDR, ReadingRange)
+{
+    SensorValConversion conversion = {1, 0.0, 1.0, 0x00};
+    EXPECT_EQ(IpmbSDRDevice::readingRange(conversion),
+              std::make_pair(0.0, 255.0));
+
+    conversion.negRead = 0x80;
+    EXPECT_EQ(IpmbSDRDevice::readingRange(conversion),
+              std::make_pair(-128.0, 127.0));
+
+    conversion.negRead = 0x40;
+    EXPECT_EQ(IpmbSDRDevice::readingRange(conversion),
+              std::make_pair(-127.0, 127.0));
+
+    conversion = {-2, 0.0, 1.0, 0x00};
+    EXPECT_EQ(IpmbSDRDevice::readingRange(conversion),
+              std::make_pair(-510.0, 0.0));
+}
+
+TEST(IPMBSDR, SensorTypeName)
+{
+    SensorInfo info;
+    SensorValConversion conversion;
+
+    info.sensorUnit = sdrtype01::unitVolts;
+    EXPECT_EQ(IpmbSDRDevice::sensorTypeName(info, conversion), "voltage");
+    info.sensorUnit = sdrtype01::unitAmps;
+    EXPECT_EQ(IpmbSDRDevice::sensorTypeName(info, conversion), "current");
+    info.sensorUnit = sdrtype01::unitWatts;
+    EXPECT_EQ(IpmbSDRDevice::sensorTypeName(info, conversion), "power");
+
+    // RPM
+    info.sensorUnit = 18;
+    EXPECT_EQ(IpmbSDRDevice::sensorTypeName(info, conversion), std::nullopt);
+
+    conversion.negRead = sdrtype01::unitPercentage;
+    EXPECT_EQ(IpmbSDRDevice::sensorTypeName(info, conversion), "utilization");
+
+    conversion.negRead = 0xc0;
+    EXPECT_EQ(IpmbSDRDevice::sensorTypeName(info, conversion), std::nullopt);
+}
+
+TEST(IPMBSDR, RepositoryInfo)
+{
+    std::vector<uint8_t> data = {0x51, 0x2a, 0x00, 0xff, 0xff, 0x04, 0x03,
+                                 0x02, 0x01, 0x08, 0x07, 0x06, 0x05, 0x00};
+    std::optional<SDRRepositoryInfo> info =
+        IpmbSDRDevice::parseRepositoryInfo(data);
+    ASSERT_TRUE(info);
+    EXPECT_EQ(info->recordCount, 42);
+    EXPECT_EQ(info->additionTimestamp, 0x01020304);
+    EXPECT_EQ(info->eraseTimestamp, 0x05060708);
+
+    data.pop_back();
+    EXPECT_FALSE(IpmbSDRDevice::parseRepositoryInfo(data));
+}
+
+
+TEST(IPMBSDR, Type01Discrete)
+{
+    std::vector<uint8_t> data = sdrType01Record();
+    // Generic availability
+    data[sdrtype01::readingTypeByte] = 0x08;
+    data[sdrtype01::readingMaskLSB] = 0x03;
+    // Not linear, which doesn't matter without readings
+    data[sdrtype01::sdrLinearByte] = 0x07;
+    IpmbSDRDevice::checkSDRType01Threshold(data, 7, "Presence");
+
+    ASSERT_EQ(sensorRecord[7].size(), 1);
+    const SensorInfo& info = sensorRecord[7][0];
+    EXPECT_TRUE(info.isDiscrete());
+    EXPECT_EQ(info.readingType, 0x08);
+    EXPECT_EQ(info.readingMask, 0x03);
+    EXPECT_FALSE(sensorValRecord[7].contains(0x21));
+
+    sensorRecord.erase(7);
+    sensorValRecord.erase(7);
+}
+
+// Sets a record byte, counted from 1 as in the specification
+static void setRecordBytes(std::vector<uint8_t>& data, size_t byte,
+                           const std::string& bytes)
+{
+    for (char value : bytes)
+    {
+        data[sdr::recordIndex(byte++)] = static_cast<uint8_t>(value);
+    }
+}
+
+TEST(IPMBSDR, Type02Shared)
+{
+    std::vector<uint8_t> data(sdr::recordIndex(48) + 1, 0);
+    data[sdr::sdrType] = 0x02;
+    data[sdr::sdrSensorNum] = 0x40;
+    // Processor, sensor-specific
+    data[sdrtype02::sensorTypeByte] = 0x07;
+    data[sdrtype02::readingTypeByte] = 0x6f;
+    data[sdrtype02::readingMaskLSB] = 0x81;
+    data[sdrtype02::readingMaskMSB] = 0x80;
+    // Three sensors, named with letters from A
+    data[sdrtype02::recordSharing] = 0x13;
+    data[sdrtype02::nameLengthByte] = 0xc4;
+    setRecordBytes(data, 33, "CPU ");
+
+    IpmbSDRDevice::checkSDRType02(
+        data, 7, IpmbSDRDevice::recordName(data, sdrtype02::nameLengthByte));
+
+    ASSERT_EQ(sensorRecord[7].size(), 3);
+    for (uint8_t ii = 0; ii < 3; ii++)
+    {
+        const SensorInfo& info = sensorRecord[7][ii];
+        EXPECT_EQ(info.sensorReadName, std::string("CPU ") +
+                                           static_cast<char>('A' + ii));
+        EXPECT_EQ(info.sensorNumber, 0x40 + ii);
+        EXPECT_EQ(info.recordType, SDRType::sdrType02);
+        EXPECT_EQ(info.sensorType, 0x07);
+        EXPECT_EQ(info.readingMask, 0x81);
+        EXPECT_TRUE(info.isDiscrete());
+    }
+
+    sensorRecord.erase(7);
+}
+
+TEST(IPMBSDR, Type03NameAcrossResponses)
+{
+    std::vector<uint8_t> data(sdr::recordIndex(33) + 1, 0);
+    data[sdr::sdrType] = 0x03;
+    data[sdr::sdrSensorNum] = 0x50;
+    data[sdrtype03::sensorTypeByte] = 0x08;
+    data[sdrtype03::readingTypeByte] = 0x6f;
+    // Two sensors, numbered from 1
+    data[sdrtype03::recordSharing] = 0x02;
+    data[sdrtype03::recordSharingOffset] = 0x01;
+    data[sdrtype03::nameLengthByte] = 0xd0;
+    setRecordBytes(data, 18, "Power Supply Evt");
+
+    IpmbSDRDevice::checkSDRType03(
+        data, 7, IpmbSDRDevice::recordName(data, sdrtype03::nameLengthByte));
+
+    ASSERT_EQ(sensorRecord[7].size(), 2);
+    EXPECT_EQ(sensorRecord[7][0].sensorReadName, "Power Supply Evt1");
+    EXPECT_EQ(sensorRecord[7][1].sensorReadName, "Power Supply Evt2");
+    EXPECT_EQ(sensorRecord[7][1].sensorNumber, 0x51);
+    EXPECT_EQ(sensorRecord[7][1].recordType, SDRType::sdrType03);
+    EXPECT_TRUE(sensorRecord[7][1].isDiscrete());
+
+    sensorRecord.erase(7);
+}
+
+TEST(IPMBSDR, DiscreteState)
+{
+    EXPECT_EQ(ipmi::sensor::discreteState({0x00, 0xc0, 0x05, 0x81}), 0x0105);
+    EXPECT_EQ(ipmi::sensor::discreteState({0x00, 0xc0, 0x02}), 0x0002);
+    // Reading unavailable
+    EXPECT_EQ(ipmi::sensor::discreteState({0x00, 0x20, 0x02}), std::nullopt);
+    EXPECT_EQ(ipmi::sensor::discreteState({0x00, 0xc0}), std::nullopt);
+}
+
+TEST(IPMBSDR, DiscreteStatus)
+{
+    using ipmi::sensor::discreteStatus;
+
+    // Generic presence: absent, then present
+    EXPECT_FALSE(discreteStatus(0x25, 0x08, 0x0001).present);
+    EXPECT_TRUE(discreteStatus(0x25, 0x08, 0x0002).present);
+    // Severity: non-critical from OK, then critical from less severe
+    EXPECT_TRUE(discreteStatus(0x2c, 0x07, 0x0002).functional);
+    EXPECT_FALSE(discreteStatus(0x2c, 0x07, 0x0004).functional);
+    // Processor: present, then present with IERR
+    ipmi::sensor::DiscreteStatus status = discreteStatus(0x07, 0x6f, 0x0080);
+    EXPECT_TRUE(status.present);
+    EXPECT_TRUE(status.functional);
+    status = discreteStatus(0x07, 0x6f, 0x0081);
+    EXPECT_TRUE(status.present);
+    EXPECT_FALSE(status.functional);
+    // Power supply predictive failure isn't a failure yet
+    EXPECT_TRUE(discreteStatus(0x08, 0x6f, 0x0005).functional);
+    // Sensor specific offsets of other types don't bear on either
+    status = discreteStatus(0x05, 0x6f, 0x0001);
+    EXPECT_TRUE(status.present);
+    EXPECT_TRUE(status.functional);
+}
+
+TEST(IPMBSDR, Thresholds)
+{
+    SensorInfo info;
+    info.readableThresholds = sdrtype01::thresholdUpperNonCritical |
+                              sdrtype01::thresholdLowerCritical;
+    info.thresUpperNonCri = 80.0;
+    info.thresLowerCri = 5.0;
+    info.hysteresisPositive = 2.0;
+    info.hysteresisNegative = 3.0;
+
+    std::vector<thresholds::Threshold> list = sdrThresholds(info);
+    ASSERT_EQ(list.size(), 2);
+    EXPECT_EQ(list[0].level, thresholds::Level::WARNING);
+    EXPECT_EQ(list[0].direction, thresholds::Direction::HIGH);
+    EXPECT_DOUBLE_EQ(list[0].value, 80.0);
+    EXPECT_DOUBLE_EQ(list[0].hysteresis, 2.0);
+    EXPECT_FALSE(list[0].writeable);
+    EXPECT_EQ(list[1].level, thresholds::Level::CRITICAL);
+    EXPECT_EQ(list[1].direction, thresholds::Direction::LOW);
+    EXPECT_DOUBLE_EQ(list[1].value, 5.0);
+    EXPECT_DOUBLE_EQ(list[1].hysteresis, 3.0);
+}
+
+TEST(IPMBController, UnreachableAfterFailures)
+{
+    IpmbControllerHealth health;
+    IpmbControllerHealth::Clock::time_point now{};
+
+    for (size_t i = 1; i < IpmbControllerHealth::failureThreshold; i++)
+    {
+        EXPECT_FALSE(health.requestUnanswered(now));
+        EXPECT_TRUE(health.reachable);
+        EXPECT_TRUE(health.requestAllowed(now));
+    }
+    EXPECT_TRUE(health.requestUnanswered(now));
+    EXPECT_FALSE(health.reachable);
+    EXPECT_EQ(health.backoff, IpmbControllerHealth::minBackoff);
+
+    // An answer in between starts the count again
+    IpmbControllerHealth answered;
+    EXPECT_FALSE(answered.requestUnanswered(now));
+    EXPECT_FALSE(answered.requestAnswered());
+    EXPECT_EQ(answered.failures, 0);
+}
+
+TEST(IPMBController, ProbeBackoff)
+{
+    IpmbControllerHealth health;
+    IpmbControllerHealth::Clock::time_point now{};
+    for (size_t i = 0; i < IpmbControllerHealth::failureThreshold; i++)
+    {
+        health.requestUnanswered(now);
+    }
+    ASSERT_FALSE(health.reachable);
+
+    EXPECT_FALSE(health.requestAllowed(now));
+    now += IpmbControllerHealth::minBackoff;
+    // A single probe is let through
+    EXPECT_TRUE(health.requestAllowed(now));
+    EXPECT_FALSE(health.requestAllowed(now));
+
+    EXPECT_FALSE(health.requestUnanswered(now));
+    EXPECT_EQ(health.backoff, IpmbControllerHealth::minBackoff * 2);
+    EXPECT_FALSE(
+        health.requestAllowed(now + IpmbControllerHealth::minBackoff));
+    EXPECT_TRUE(
+        health.requestAllowed(now + IpmbControllerHealth::minBackoff * 2));
+
+    for (size_t i = 0; i < 10; i++)
+    {
+        health.requestUnanswered(now);
+    }
+    EXPECT_EQ(health.backoff, IpmbControllerHealth::maxBackoff);
+
+    EXPECT_TRUE(health.requestAnswered());
+    EXPECT_TRUE(health.reachable);
+    EXPECT_EQ(health.failures, 0);
+    EXPECT_TRUE(health.requestAllowed(now));
+}
+
+TEST(IPMBController, RequestTiming)
+{
+    IpmbRequestTiming defaults = requestTimingFromConfig({});
+    EXPECT_EQ(defaults.timeout, std::chrono::milliseconds(2000));
+    EXPECT_EQ(defaults.retries, 1);
+
+    SensorBaseConfigMap cfg;
+    cfg["RequestTimeout"] = 0.5;
+    cfg["RequestRetries"] = static_cast<uint64_t>(3);
+    IpmbRequestTiming timing = requestTimingFromConfig(cfg);
+    EXPECT_EQ(timing.timeout, std::chrono::milliseconds(500));
+    EXPECT_EQ(timing.retries, 3);
+
+    cfg["RequestTimeout"] = 0.0;
+    EXPECT_EQ(requestTimingFromConfig(cfg).timeout,
+              std::chrono::milliseconds(2000));
+}
+
+TEST(IPMBEvent, ThresholdEvent)
+{
+    // Upper critical going high, asserted, with the trigger reading 0x5a
+    std::optional<PlatformEvent> event =
+        PlatformEvent::parse({0x04, 0x01, 0x12, 0x01, 0x59, 0x5a, 0x50});
+    ASSERT_TRUE(event);
+    EXPECT_EQ(event->sensorType, 0x01);
+    EXPECT_EQ(event->sensorNumber, 0x12);
+    EXPECT_TRUE(event->assertion);
+    EXPECT_EQ(event->offset, 0x09);
+    EXPECT_EQ(event->triggerReading, 0x5a);
+    auto crossed = event->threshold();
+    ASSERT_TRUE(crossed);
+    EXPECT_EQ(crossed->first, thresholds::Level::CRITICAL);
+    EXPECT_EQ(crossed->second, thresholds::Direction::HIGH);
+
+    // Lower non-critical going low, deasserted, without event data
+    event = PlatformEvent::parse({0x04, 0x02, 0x13, 0x81, 0x00, 0xff, 0xff});
+    ASSERT_TRUE(event);
+    EXPECT_FALSE(event->assertion);
+    EXPECT_EQ(event->triggerReading, std::nullopt);
+    crossed = event->threshold();
+    ASSERT_TRUE(crossed);
+    EXPECT_EQ(crossed->first, thresholds::Level::WARNING);
+    EXPECT_EQ(crossed->second, thresholds::Direction::LOW);
+
+    // Offsets past upper non-recoverable going high aren't thresholds
+    event = PlatformEvent::parse({0x04, 0x01, 0x12, 0x01, 0x0c, 0xff, 0xff});
+    ASSERT_TRUE(event);
+    EXPECT_EQ(event->threshold(), std::nullopt);
+}
+
+TEST(IPMBEvent, DiscreteEvent)
+{
+    // Sensor-specific processor event, offset 1 (thermal trip)
+    std::optional<PlatformEvent> event =
+        PlatformEvent::parse({0x04, 0x07, 0x20, 0x6f, 0xa1, 0x00, 0x00});
+    ASSERT_TRUE(event);
+    EXPECT_EQ(event->eventType, 0x6f);
+    EXPECT_TRUE(event->assertion);
+    EXPECT_EQ(event->offset, 0x01);
+    EXPECT_EQ(event->triggerReading, std::nullopt);
+    EXPECT_EQ(event->threshold(), std::nullopt);
+}
+
+TEST(IPMBEvent, InvalidEvent)
+{
+    EXPECT_EQ(PlatformEvent::parse({0x04, 0x01, 0x12, 0x01, 0x59}),
+              std::nullopt);
+    EXPECT_EQ(PlatformEvent::parse({0x03, 0x01, 0x12, 0x01, 0x59, 0x5a, 0x50}),
+              std::nullopt);
+}
+
 } // namespace
@@ -1,7 +1,10 @@
 #include "MCTPEndpoint.hpp"
 #include "Utils.hpp"
 
+#include <cstdint>
+#include <optional>
 #include <stdexcept>
+#include <vector>
 
 #include <gtest/gtest.h>
 
@@ -86,3 +89,106 @@ TEST(I2CMCTPDDevice, fromBadIfaceNoName)
     };
     EXPECT_THROW(I2CMCTPDDevice::from({}, iface), std::invalid_argument);
 }
+
+TEST(I3CMCTPDDevice, matchRelevantConfig)
+{
+    SensorData config{{"xyz.openbmc_project.Configuration.MCTPI3CTarget", {}}};
+    EXPECT_TRUE(I3CMCTPDDevice::match(config));
+    EXPECT_FALSE(I2CMCTPDDevice::match(config));
+}
+
+TEST(I3CMCTPDDevice, fromBadIfaceWrongType)
+{
+    SensorBaseConfigMap iface{
+        {"Bus", "0"},
+        {"Name", "test"},
+        {"ProvisionedID", "1"},
+        {"Type", "MCTPI2CTarget"},
+    };
+    EXPECT_THROW(I3CMCTPDDevice::from({}, iface), std::invalid_argument);
+}
+
+TEST(I3CMCTPDDevice, fromBadIfaceNoPID)
+{
+    SensorBaseConfigMap iface{
+        {"Bus", "0"},
+        {"Name", "test"},
+        {"Type", "MCTPI3CTarget"},
+    };
+    EXPECT_THROW(I3CMCTPDDevice::from({}, iface), std::invalid_argument);
+}
+
+TEST(I3CMCTPDDevice, fromBadIfaceWidePID)
+{
+    SensorBaseConfigMap iface{
+        {"Bus", "0"},
+        {"Name", "test"},
+        {"ProvisionedID", "281474976710656"},
+        {"Type", "MCTPI3CTarget"},
+    };
+    EXPECT_THROW(I3CMCTPDDevice::from({}, iface), std::invalid_argument);
+}
+
+TEST(I3CMCTPDDevice, physaddrFromPID)
+{
+    std::vector<uint8_t> expected{0x04, 0x6a, 0x00, 0x00, 0x12, 0x34};
+    EXPECT_EQ(I3CMCTPDDevice::physaddrFromPID(0x046a00001234), expected);
+}
+
+TEST(PCIeVDMMCTPDDevice, matchRelevantConfig)
+{
+    SensorData config{
+        {"xyz.openbmc_project.Configuration.MCTPPCIeTarget", {}}};
+    EXPECT_TRUE(PCIeVDMMCTPDDevice::match(config));
+    EXPECT_FALSE(I2CMCTPDDevice::match(config));
+}
+
+TEST(PCIeVDMMCTPDDevice, fromBadIfaceNoInterface)
+{
+    SensorBaseConfigMap iface{
+        {"Bus", "3"},
+        {"Device", "0"},
+        {"Function", "0"},
+        {"Name", "test"},
+        {"Type", "MCTPPCIeTarget"},
+    };
+    EXPECT_THROW(PCIeVDMMCTPDDevice::from({}, iface), std::invalid_argument);
+}
+
+TEST(PCIeVDMMCTPDDevice, fromBadIfaceBadDevice)
+{
+    SensorBaseConfigMap iface{
+        {"Bus", "3"},
+        {"Device", "32"},
+        {"Function", "0"},
+        {"Interface", "mctppcie0"},
+        {"Name", "test"},
+        {"Type", "MCTPPCIeTarget"},
+    };
+    EXPECT_THROW(PCIeVDMMCTPDDevice::from({}, iface), std::invalid_argument);
+}
+
+TEST(PCIeVDMMCTPDDevice, physaddrFromBDF)
+{
+    std::vector<uint8_t> expected{0x3b, 0x0a};
+    EXPECT_EQ(PCIeVDMMCTPDDevice::physaddrFromBDF(0x3b, 0x01, 0x02), expected);
+}
+
+TEST(MCTPDDevice, staticEIDFromConfigDynamic)
+{
+    SensorBaseConfigMap iface{{"Type", "MCTPI2CTarget"}};
+    EXPECT_EQ(MCTPDDevice::staticEIDFromConfig(iface), std::nullopt);
+}
+
+TEST(MCTPDDevice, staticEIDFromConfig)
+{
+    SensorBaseConfigMap iface{{"EID", uint64_t{20}}};
+    EXPECT_EQ(MCTPDDevice::staticEIDFromConfig(iface), 20);
+}
+
+TEST(MCTPDDevice, staticEIDFromConfigReservedEID)
+{
+    SensorBaseConfigMap iface{{"EID", uint64_t{7}}};
+    EXPECT_THROW(MCTPDDevice::staticEIDFromConfig(iface),
+                 std::invalid_argument);
+}
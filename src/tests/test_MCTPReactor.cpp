@@ -2,9 +2,11 @@
 #include "MCTPReactor.hpp"
 #include "Utils.hpp"
 
+#include <chrono>
 #include <cstdint>
 #include <functional>
 #include <memory>
+#include <optional>
 #include <string>
 #include <system_error>
 #include <vector>
@@ -24,6 +26,7 @@ class MockMCTPDevice : public MCTPDevice
                 (override));
     MOCK_METHOD(void, remove, (), (override));
     MOCK_METHOD(std::string, describe, (), (const, override));
+    MOCK_METHOD(std::optional<uint8_t>, staticEID, (), (const, override));
 };
 
 class MockMCTPEndpoint : public MCTPEndpoint
@@ -53,6 +56,21 @@ class MockAssociationServer : public AssociationServer
     MOCK_METHOD(void, disassociate, (const std::string& path), (override));
 };
 
+class MockEndpointHealthServer : public EndpointHealthServer
+{
+  public:
+    ~MockEndpointHealthServer() override = default;
+
+    MOCK_METHOD(void, publish,
+                (const std::string& path, const EndpointHealth& health),
+                (override));
+    MOCK_METHOD(void, withdraw, (const std::string& path), (override));
+    MOCK_METHOD(void, logDegraded,
+                (const std::string& path, const std::string& inventory,
+                 std::chrono::seconds degraded),
+                (override));
+};
+
 class MCTPReactorFixture : public testing::Test
 {
   protected:
@@ -152,6 +170,9 @@ TEST_F(MCTPReactorFixture, manageMockDeviceDeferredSetup)
             std::make_error_code(std::errc::permission_denied), endpoint))
         .WillOnce(testing::InvokeArgument<0>(std::error_code(), endpoint));
 
+    // Retry on the next tick
+    RetryPolicy immediate{.initialDelay = std::chrono::seconds(0)};
+    reactor->setRetryPolicy("/test", immediate);
     reactor->manageMCTPDevice("/test", device);
     reactor->tick();
     reactor->unmanageMCTPDevice("/test");
@@ -248,3 +269,167 @@ TEST(MCTPReactor, replaceConfiguration)
     EXPECT_TRUE(testing::Mock::VerifyAndClearExpectations(replacement.get()));
     EXPECT_TRUE(testing::Mock::VerifyAndClearExpectations(endpoint.get()));
 }
+
+static auto healthIs(bool available, uint64_t degraded, uint64_t recovered)
+{
+    return testing::AllOf(
+        testing::Field(&EndpointHealth::available, available),
+        testing::Field(&EndpointHealth::degraded, degraded),
+        testing::Field(&EndpointHealth::recovered, recovered));
+}
+
+TEST_F(MCTPReactorFixture, endpointHealthTransitions)
+{
+    MockEndpointHealthServer health;
+    reactor = std::make_shared<MCTPReactor>(assoc, &health);
+
+    MCTPEndpoint::Event degradedHandler;
+    MCTPEndpoint::Event availableHandler;
+    MCTPEndpoint::Event removeHandler;
+
+    EXPECT_CALL(assoc, associate("/xyz/openbmc_project/mctp/1/9", testing::_));
+    EXPECT_CALL(assoc, disassociate("/xyz/openbmc_project/mctp/1/9"));
+    EXPECT_CALL(*endpoint, subscribe(testing::_, testing::_, testing::_))
+        .WillOnce(testing::DoAll(testing::SaveArg<0>(&degradedHandler),
+                                 testing::SaveArg<1>(&availableHandler),
+                                 testing::SaveArg<2>(&removeHandler)));
+    EXPECT_CALL(*endpoint, remove()).WillOnce(testing::Invoke([&]() {
+        removeHandler(endpoint);
+    }));
+    EXPECT_CALL(*device, remove()).WillOnce(testing::Invoke([&]() {
+        endpoint->remove();
+    }));
+    EXPECT_CALL(*device, setup(testing::_))
+        .WillOnce(testing::InvokeArgument<0>(std::error_code(), endpoint));
+
+    {
+        testing::InSequence seq;
+        EXPECT_CALL(health, publish("/xyz/openbmc_project/mctp/1/9",
+                                    healthIs(true, 0, 0)));
+        EXPECT_CALL(health, publish("/xyz/openbmc_project/mctp/1/9",
+                                    healthIs(false, 1, 0)));
+        EXPECT_CALL(health, publish("/xyz/openbmc_project/mctp/1/9",
+                                    healthIs(true, 1, 1)));
+        EXPECT_CALL(health, withdraw("/xyz/openbmc_project/mctp/1/9"));
+    }
+    EXPECT_CALL(health, logDegraded(testing::_, testing::_, testing::_))
+        .Times(0);
+
+    reactor->manageMCTPDevice("/test", device);
+    degradedHandler(endpoint);
+    // Repeated notifications aren't transitions
+    degradedHandler(endpoint);
+    availableHandler(endpoint);
+    availableHandler(endpoint);
+    reactor->unmanageMCTPDevice("/test");
+}
+
+TEST_F(MCTPReactorFixture, endpointDegradedLogged)
+{
+    MockEndpointHealthServer health;
+    reactor = std::make_shared<MCTPReactor>(assoc, &health);
+
+    MCTPEndpoint::Event degradedHandler;
+    MCTPEndpoint::Event removeHandler;
+
+    EXPECT_CALL(assoc, associate("/xyz/openbmc_project/mctp/1/9", testing::_));
+    EXPECT_CALL(assoc, disassociate("/xyz/openbmc_project/mctp/1/9"));
+    EXPECT_CALL(*endpoint, subscribe(testing::_, testing::_, testing::_))
+        .WillOnce(testing::DoAll(testing::SaveArg<0>(&degradedHandler),
+                                 testing::SaveArg<2>(&removeHandler)));
+    EXPECT_CALL(*endpoint, remove()).WillOnce(testing::Invoke([&]() {
+        removeHandler(endpoint);
+    }));
+    EXPECT_CALL(*device, remove()).WillOnce(testing::Invoke([&]() {
+        endpoint->remove();
+    }));
+    EXPECT_CALL(*device, setup(testing::_))
+        .WillOnce(testing::InvokeArgument<0>(std::error_code(), endpoint));
+
+    EXPECT_CALL(health, publish(testing::_, testing::_))
+        .Times(testing::AtLeast(2));
+    EXPECT_CALL(health, withdraw("/xyz/openbmc_project/mctp/1/9"));
+    // Only once for each stretch of degradation
+    EXPECT_CALL(health, logDegraded("/xyz/openbmc_project/mctp/1/9", "/test",
+                                    testing::_));
+
+    reactor->manageMCTPDevice("/test", device);
+    reactor->setDegradedLogPeriod("/test", std::chrono::seconds(0));
+    degradedHandler(endpoint);
+    reactor->tick();
+    reactor->tick();
+    reactor->unmanageMCTPDevice("/test");
+}
+
+TEST(RetryPolicy, delay)
+{
+    RetryPolicy policy{.initialDelay = std::chrono::seconds(5),
+                       .backoffFactor = 2.0,
+                       .maxDelay = std::chrono::seconds(60)};
+    EXPECT_EQ(policy.delay(1), std::chrono::seconds(5));
+    EXPECT_EQ(policy.delay(2), std::chrono::seconds(10));
+    EXPECT_EQ(policy.delay(4), std::chrono::seconds(40));
+    EXPECT_EQ(policy.delay(5), std::chrono::seconds(60));
+    EXPECT_EQ(policy.delay(100), std::chrono::seconds(60));
+}
+
+TEST_F(MCTPReactorFixture, setupRetryBackoff)
+{
+    EXPECT_CALL(*device, remove());
+    // Not retried on the tick, as the first retry is an hour away
+    EXPECT_CALL(*device, setup(testing::_))
+        .WillOnce(testing::InvokeArgument<0>(
+            std::make_error_code(std::errc::permission_denied), endpoint));
+
+    reactor->setRetryPolicy("/test",
+                            RetryPolicy{.initialDelay = std::chrono::hours(1)});
+    reactor->manageMCTPDevice("/test", device);
+    reactor->tick();
+    reactor->unmanageMCTPDevice("/test");
+}
+
+TEST_F(MCTPReactorFixture, setupRetryAbandoned)
+{
+    EXPECT_CALL(*device, remove());
+    // Not retried after the second attempt
+    EXPECT_CALL(*device, setup(testing::_))
+        .Times(2)
+        .WillRepeatedly(testing::InvokeArgument<0>(
+            std::make_error_code(std::errc::permission_denied), endpoint));
+
+    reactor->setRetryPolicy("/test",
+                            RetryPolicy{.initialDelay = std::chrono::seconds(0),
+                                        .maxAttempts = 2});
+    reactor->manageMCTPDevice("/test", device);
+    reactor->tick();
+    reactor->tick();
+    reactor->unmanageMCTPDevice("/test");
+}
+
+TEST_F(MCTPReactorFixture, setupRetrySucceeds)
+{
+    MCTPEndpoint::Event removeHandler;
+
+    EXPECT_CALL(assoc, associate("/xyz/openbmc_project/mctp/1/9", testing::_));
+    EXPECT_CALL(assoc, disassociate("/xyz/openbmc_project/mctp/1/9"));
+    EXPECT_CALL(*endpoint, subscribe(testing::_, testing::_, testing::_))
+        .WillOnce(testing::SaveArg<2>(&removeHandler));
+    EXPECT_CALL(*endpoint, remove()).WillOnce(testing::Invoke([&]() {
+        removeHandler(endpoint);
+    }));
+    EXPECT_CALL(*device, remove()).WillOnce(testing::Invoke([&]() {
+        endpoint->remove();
+    }));
+    // Nothing is left to retry on the last tick
+    EXPECT_CALL(*device, setup(testing::_))
+        .WillOnce(testing::InvokeArgument<0>(
+            std::make_error_code(std::errc::permission_denied), endpoint))
+        .WillOnce(testing::InvokeArgument<0>(std::error_code(), endpoint));
+
+    reactor->setRetryPolicy(
+        "/test", RetryPolicy{.initialDelay = std::chrono::seconds(0)});
+    reactor->manageMCTPDevice("/test", device);
+    reactor->tick();
+    reactor->tick();
+    reactor->unmanageMCTPDevice("/test");
+}
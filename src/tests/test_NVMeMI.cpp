@@ -0,0 +1,192 @@
+#include "NVMeMI.hpp"
+
+#include <cstdint>
+#include <optional>
+#include <string>
+#include <string_view>
+#include <vector>
+
+#include <gtest/gtest.h>
+
+// Completes a response with its message integrity check
+static std::vector<uint8_t> withMic(std::vector<uint8_t> message)
+{
+    std::vector<uint8_t> covered = {nvmemi::mctpMessageType};
+    covered.insert(covered.end(), message.begin(), message.end());
+    uint32_t mic = nvmemi::crc32c(covered);
+    for (int shift = 0; shift < 32; shift += 8)
+    {
+        message.push_back(static_cast<uint8_t>(mic >> shift));
+    }
+    return message;
+}
+
+TEST(NVMeMI, Crc32c)
+{
+    std::string_view check = "123456789";
+    std::vector<uint8_t> data(check.begin(), check.end());
+    EXPECT_EQ(nvmemi::crc32c(data), 0xe3069283U);
+}
+
+TEST(NVMeMI, EncodeSubsystemHealthPoll)
+{
+    std::vector<uint8_t> request = nvmemi::encodeSubsystemHealthPoll();
+    ASSERT_EQ(request.size(), 19U);
+    EXPECT_EQ(request[0], 0x08);
+    EXPECT_EQ(request[3], 0x01);
+    std::vector<uint8_t> message(request.begin(), request.end() - 4);
+    EXPECT_EQ(withMic(message), request);
+}
+
+TEST(NVMeMI, EncodeControllerHealthPoll)
+{
+    std::vector<uint8_t> request = nvmemi::encodeControllerHealthPoll();
+    ASSERT_EQ(request.size(), 19U);
+    EXPECT_EQ(request[3], 0x02);
+    // Report all, 255 entries from controller 0
+    EXPECT_EQ(request[7], 0x00);
+    EXPECT_EQ(request[8], 0x00);
+    EXPECT_EQ(request[9], 0xff);
+    EXPECT_EQ(request[10], 0x80);
+}
+
+TEST(NVMeMI, DecodeSubsystemHealth)
+{
+    std::vector<uint8_t> response = withMic(
+        {0x88, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x38, 0xff, 0x23, 0x05,
+         0x01, 0x00, 0x00, 0x00});
+    std::optional<nvmemi::SubsystemHealth> health =
+        nvmemi::decodeSubsystemHealth(response);
+    ASSERT_TRUE(health);
+    EXPECT_TRUE(health->functional());
+    EXPECT_EQ(health->smartWarnings, 0xff);
+    ASSERT_TRUE(health->temperature);
+    EXPECT_DOUBLE_EQ(*health->temperature, 35.0);
+    EXPECT_EQ(health->driveLifeUsed, 5);
+    EXPECT_EQ(health->controllerStatus, 1);
+
+    // A corrupted message fails its integrity check
+    response[9] = 0x24;
+    EXPECT_FALSE(nvmemi::decodeSubsystemHealth(response));
+
+    // An error status carries no health data
+    EXPECT_FALSE(nvmemi::decodeSubsystemHealth(withMic(
+        {0x88, 0x00, 0x00, 0x04, 0x00, 0x00, 0x00, 0x38, 0xff, 0x23, 0x05,
+         0x01, 0x00, 0x00, 0x00})));
+}
+
+TEST(NVMeMI, DecodeControllerHealth)
+{
+    std::vector<uint8_t> message = {0x88, 0x00, 0x00, 0x00, 0x02, 0x00, 0x00};
+    std::vector<uint8_t> first = {0x00, 0x00, 0x01, 0x00, 0x34, 0x01, 0x05,
+                                  0x64, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00,
+                                  0x00, 0x00};
+    std::vector<uint8_t> second = {0x01, 0x00, 0x03, 0x00, 0x34, 0x01, 0x05,
+                                   0x64, 0x01, 0x00, 0x00, 0x00, 0x00, 0x00,
+                                   0x00, 0x00};
+    message.insert(message.end(), first.begin(), first.end());
+    message.insert(message.end(), second.begin(), second.end());
+
+    std::optional<std::vector<nvmemi::ControllerHealth>> controllers =
+        nvmemi::decodeControllerHealth(withMic(message));
+    ASSERT_TRUE(controllers);
+    ASSERT_EQ(controllers->size(), 2U);
+    EXPECT_EQ((*controllers)[0].id, 0);
+    EXPECT_FALSE((*controllers)[0].fatal());
+    EXPECT_EQ((*controllers)[0].temperature, 308);
+    EXPECT_EQ((*controllers)[1].id, 1);
+    EXPECT_TRUE((*controllers)[1].fatal());
+    EXPECT_EQ((*controllers)[1].warnings, 0x01);
+
+    // More entries than data
+    message[4] = 0x03;
+    EXPECT_FALSE(nvmemi::decodeControllerHealth(withMic(message)));
+}
+
+TEST(NVMeMI, CompositeTemperature)
+{
+    EXPECT_DOUBLE_EQ(*nvmemi::compositeTemperature(0x00), 0.0);
+    EXPECT_DOUBLE_EQ(*nvmemi::compositeTemperature(0x7f), 127.0);
+    EXPECT_DOUBLE_EQ(*nvmemi::compositeTemperature(0xff), -1.0);
+    EXPECT_FALSE(nvmemi::compositeTemperature(0x80));
+    EXPECT_FALSE(nvmemi::compositeTemperature(0x81));
+}
+
+TEST(NVMeMI, DecodeBasicStatus)
+{
+    // Functional, temperature and read only warnings, 35 C, 7% used
+    std::vector<uint8_t> status = {0x3b, 0xf5, 0x23, 0x07, 0x00, 0x00};
+    std::optional<nvmemi::SubsystemHealth> health =
+        nvmemi::decodeBasicStatus(status);
+    ASSERT_TRUE(health);
+    EXPECT_TRUE(health->functional());
+    EXPECT_DOUBLE_EQ(*health->temperature, 35.0);
+    EXPECT_EQ(health->driveLifeUsed, 7);
+    EXPECT_TRUE(health->warning(nvmemi::SmartWarning::temperature));
+    EXPECT_TRUE(health->warning(nvmemi::SmartWarning::readOnly));
+    EXPECT_FALSE(health->warning(nvmemi::SmartWarning::spareBelowThreshold));
+    EXPECT_FALSE(health->warning(nvmemi::SmartWarning::reliabilityDegraded));
+    EXPECT_FALSE(health->warning(nvmemi::SmartWarning::volatileBackupFailed));
+
+    status.resize(5);
+    EXPECT_FALSE(nvmemi::decodeBasicStatus(status));
+}
+
+TEST(NVMeMI, EncodeIdentifyController)
+{
+    std::vector<uint8_t> request = nvmemi::encodeIdentifyController(0x0102);
+    ASSERT_EQ(request.size(), 71U);
+    EXPECT_EQ(request[0], 0x10);
+    EXPECT_EQ(request[3], 0x06);
+    EXPECT_EQ(request[5], 0x02);
+    EXPECT_EQ(request[6], 0x01);
+    // 72 bytes of data, Identify Controller
+    EXPECT_EQ(request[31], 72);
+    EXPECT_EQ(request[43], 0x01);
+    std::vector<uint8_t> message(request.begin(), request.end() - 4);
+    EXPECT_EQ(withMic(message), request);
+}
+
+TEST(NVMeMI, DecodeIdentifyController)
+{
+    std::vector<uint8_t> message(19, 0x00);
+    message[0] = 0x90;
+    std::string serial = "S4EVNX0R123456      ";
+    std::string model = "SAMPLE NVME 1TB";
+    model.resize(40, ' ');
+    std::string firmware = "1B2QJXD7";
+    message.insert(message.end(), {0x4d, 0x14, 0x4d, 0x14});
+    message.insert(message.end(), serial.begin(), serial.end());
+    message.insert(message.end(), model.begin(), model.end());
+    message.insert(message.end(), firmware.begin(), firmware.end());
+
+    std::optional<nvmemi::DriveIdentity> identity =
+        nvmemi::decodeIdentifyController(withMic(message));
+    ASSERT_TRUE(identity);
+    EXPECT_EQ(identity->vendorId, 0x144d);
+    EXPECT_EQ(identity->serialNumber, "S4EVNX0R123456");
+    EXPECT_EQ(identity->model, "SAMPLE NVME 1TB");
+    EXPECT_EQ(identity->firmwareRevision, "1B2QJXD7");
+
+    // An MI command response doesn't do
+    message[0] = 0x88;
+    EXPECT_FALSE(nvmemi::decodeIdentifyController(withMic(message)));
+}
+
+TEST(NVMeMI, DecodeBasicIdentity)
+{
+    std::vector<uint8_t> block = {0x14, 0x4d};
+    std::string serial = "S4EVNX0R123456";
+    block.insert(block.end(), serial.begin(), serial.end());
+    block.resize(22, ' ');
+
+    std::optional<nvmemi::DriveIdentity> identity =
+        nvmemi::decodeBasicIdentity(block);
+    ASSERT_TRUE(identity);
+    EXPECT_EQ(identity->vendorId, 0x144d);
+    EXPECT_EQ(identity->serialNumber, "S4EVNX0R123456");
+    EXPECT_TRUE(identity->model.empty());
+
+    block.resize(21);
+    EXPECT_FALSE(nvmemi::decodeBasicIdentity(block));
+}
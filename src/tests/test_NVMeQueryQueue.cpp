@@ -0,0 +1,51 @@
+#include "NVMeQueryQueue.hpp"
+
+#include <chrono>
+#include <optional>
+#include <vector>
+
+#include <gtest/gtest.h>
+
+using Queue = NVMeQueryQueue<int>;
+using namespace std::chrono_literals;
+
+TEST(NVMeQueryQueue, Answered)
+{
+    Queue queue(2s);
+    Queue::Clock::time_point start{};
+
+    queue.push(1, start);
+    EXPECT_EQ(queue.nextDeadline(), start + 2s);
+    EXPECT_FALSE(queue.expire(start + 1s, [](int&) { FAIL(); }));
+    EXPECT_EQ(queue.complete(), 1);
+    EXPECT_TRUE(queue.empty());
+    EXPECT_FALSE(queue.nextDeadline());
+}
+
+TEST(NVMeQueryQueue, OneDriveHangs)
+{
+    Queue queue(2s);
+    Queue::Clock::time_point start{};
+    std::vector<int> timedOut;
+    auto record = [&timedOut](int& drive) { timedOut.push_back(drive); };
+
+    // The worker hangs on drive 1
+    queue.push(1, start);
+    EXPECT_TRUE(queue.expire(start + 2s, record));
+    EXPECT_EQ(timedOut, std::vector<int>{1});
+
+    // Drive 2 is queued behind it and timed on its own
+    queue.push(2, start + 2s);
+    EXPECT_EQ(queue.nextDeadline(), start + 4s);
+    EXPECT_FALSE(queue.expire(start + 3s, record));
+    EXPECT_TRUE(queue.expire(start + 4s, record));
+    EXPECT_EQ(timedOut, (std::vector<int>{1, 2}));
+    EXPECT_FALSE(queue.nextDeadline());
+
+    // The late answers are dropped, the next query is answered again
+    queue.push(1, start + 4s);
+    EXPECT_EQ(queue.complete(), std::nullopt);
+    EXPECT_EQ(queue.complete(), std::nullopt);
+    EXPECT_EQ(queue.complete(), 1);
+    EXPECT_TRUE(queue.empty());
+}
@@ -0,0 +1,99 @@
+#include "Simulation.hpp"
+
+#include <nlohmann/json.hpp>
+
+#include <chrono>
+#include <optional>
+#include <random>
+
+#include <gtest/gtest.h>
+
+using namespace std::chrono_literals;
+using simulation::Scenario;
+using simulation::ScenarioFile;
+
+static Scenario parse(const char* text)
+{
+    std::optional<Scenario> scenario =
+        simulation::parseScenario(nlohmann::json::parse(text));
+    EXPECT_TRUE(scenario);
+    return scenario.value_or(Scenario{});
+}
+
+TEST(Simulation, RampAndStep)
+{
+    Scenario scenario = parse(R"({
+        "Loop": false,
+        "Steps": [
+            {"Type": "Ramp", "Duration": 10, "From": 20, "To": 40},
+            {"Type": "Step", "Duration": 4, "From": 1, "To": 2, "At": 1}
+        ]})");
+    std::mt19937 rng(scenario.seed);
+    EXPECT_EQ(scenario.length(), 14s);
+    EXPECT_DOUBLE_EQ(*scenario.valueAt(0s, rng), 20.0);
+    EXPECT_DOUBLE_EQ(*scenario.valueAt(5s, rng), 30.0);
+    EXPECT_DOUBLE_EQ(*scenario.valueAt(10500ms, rng), 1.0);
+    EXPECT_DOUBLE_EQ(*scenario.valueAt(11s, rng), 2.0);
+
+    // The end of the last step is held
+    EXPECT_DOUBLE_EQ(*scenario.valueAt(100s, rng), 2.0);
+}
+
+TEST(Simulation, SineLoopsAndFaults)
+{
+    Scenario scenario = parse(R"({
+        "Steps": [
+            {"Type": "Sine", "Duration": 4, "Value": 50, "Amplitude": 10,
+             "Period": 4},
+            {"Type": "Fault", "Duration": 2}
+        ]})");
+    std::mt19937 rng(scenario.seed);
+    EXPECT_NEAR(*scenario.valueAt(1s, rng), 60.0, 1e-9);
+    EXPECT_NEAR(*scenario.valueAt(3s, rng), 40.0, 1e-9);
+    EXPECT_FALSE(scenario.valueAt(5s, rng));
+
+    // Loops by default
+    EXPECT_NEAR(*scenario.valueAt(7s, rng), 60.0, 1e-9);
+}
+
+TEST(Simulation, NoiseIsRepeatable)
+{
+    Scenario scenario = parse(R"({
+        "Seed": 7,
+        "Steps": [
+            {"Type": "Noise", "Duration": 1, "Value": 5, "Amplitude": 1}
+        ]})");
+    std::mt19937 first(scenario.seed);
+    std::mt19937 second(scenario.seed);
+    EXPECT_DOUBLE_EQ(*scenario.valueAt(0s, first),
+                     *scenario.valueAt(0s, second));
+}
+
+TEST(Simulation, RejectsInvalid)
+{
+    EXPECT_FALSE(simulation::parseScenario(nlohmann::json::parse(R"({})")));
+    EXPECT_FALSE(simulation::parseScenario(nlohmann::json::parse(
+        R"({"Steps": [{"Type": "Square", "Duration": 1}]})")));
+    EXPECT_FALSE(simulation::parseScenario(
+        nlohmann::json::parse(R"({"Steps": [{"Type": "Constant"}]})")));
+}
+
+TEST(Simulation, FileLookup)
+{
+    std::optional<ScenarioFile> file =
+        ScenarioFile::parse(nlohmann::json::parse(R"({
+        "Sensors": {
+            "CPU0 Temp": {"Steps": [
+                {"Type": "Constant", "Duration": 1, "Value": 45}]}
+        }})"));
+    ASSERT_TRUE(file);
+    EXPECT_NE(file->find("CPU0_Temp"), nullptr);
+    EXPECT_EQ(file->find("CPU1 Temp"), nullptr);
+
+    file = ScenarioFile::parse(nlohmann::json::parse(R"({
+        "Default": {"Steps": [
+            {"Type": "Constant", "Duration": 1, "Value": 1}]}
+        })"));
+    ASSERT_TRUE(file);
+    EXPECT_NE(file->find("CPU1 Temp"), nullptr);
+}
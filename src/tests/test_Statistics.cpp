@@ -0,0 +1,101 @@
+#include "SensorStatistics.hpp"
+
+#include <chrono>
+#include <cmath>
+#include <limits>
+
+#include <gtest/gtest.h>
+
+using statistics::Summary;
+using statistics::Window;
+using namespace std::chrono_literals;
+
+TEST(StatisticsWindow, EmptyWindowHasNoSamples)
+{
+    Window window(60s);
+    Summary summary = window.summarize(Window::Clock::time_point{});
+    EXPECT_EQ(summary.count, 0U);
+    EXPECT_TRUE(std::isnan(summary.minimum));
+    EXPECT_TRUE(std::isnan(summary.maximum));
+    EXPECT_TRUE(std::isnan(summary.average));
+    EXPECT_TRUE(std::isnan(summary.standardDeviation));
+}
+
+TEST(StatisticsWindow, Aggregates)
+{
+    Window window(60s);
+    Window::Clock::time_point now{};
+    for (double value : {2.0, 4.0, 4.0, 4.0, 5.0, 5.0, 7.0, 9.0})
+    {
+        window.addSample(value, now);
+        now += 5s;
+    }
+
+    Summary summary = window.summarize(now);
+    EXPECT_EQ(summary.count, 8U);
+    EXPECT_DOUBLE_EQ(summary.minimum, 2.0);
+    EXPECT_DOUBLE_EQ(summary.maximum, 9.0);
+    EXPECT_DOUBLE_EQ(summary.average, 5.0);
+    EXPECT_DOUBLE_EQ(summary.standardDeviation, 2.0);
+}
+
+TEST(StatisticsWindow, IgnoresNaN)
+{
+    Window window(60s);
+    Window::Clock::time_point now{};
+    window.addSample(1.0, now);
+    window.addSample(std::numeric_limits<double>::quiet_NaN(), now);
+    window.addSample(3.0, now);
+
+    Summary summary = window.summarize(now);
+    EXPECT_EQ(summary.count, 2U);
+    EXPECT_DOUBLE_EQ(summary.average, 2.0);
+}
+
+TEST(StatisticsWindow, OldSamplesExpire)
+{
+    Window window(10s, 10);
+    Window::Clock::time_point now{};
+    window.addSample(100.0, now);
+    now += 5s;
+    window.addSample(1.0, now);
+
+    EXPECT_EQ(window.summarize(now).count, 2U);
+
+    now += 6s;
+    Summary summary = window.summarize(now);
+    EXPECT_EQ(summary.count, 1U);
+    EXPECT_DOUBLE_EQ(summary.maximum, 1.0);
+
+    now += 10s;
+    EXPECT_EQ(window.summarize(now).count, 0U);
+}
+
+TEST(StatisticsWindow, Reset)
+{
+    Window window(60s);
+    Window::Clock::time_point now{};
+    window.addSample(1.0, now);
+    window.reset();
+    EXPECT_EQ(window.summarize(now).count, 0U);
+}
+
+TEST(StatisticsConfig, ParseWindowConfig)
+{
+    SensorBaseConfigMap cfg;
+    EXPECT_FALSE(statistics::parseWindowConfig(cfg));
+
+    cfg["Interval"] = uint64_t{0};
+    EXPECT_FALSE(statistics::parseWindowConfig(cfg));
+
+    cfg["Interval"] = 300.0;
+    auto config = statistics::parseWindowConfig(cfg);
+    ASSERT_TRUE(config);
+    EXPECT_EQ(config->interval, 300s);
+    EXPECT_EQ(config->bucketCount, statistics::defaultBucketCount);
+
+    cfg["Buckets"] = uint64_t{30};
+    config = statistics::parseWindowConfig(cfg);
+    ASSERT_TRUE(config);
+    EXPECT_EQ(config->bucketCount, 30U);
+}
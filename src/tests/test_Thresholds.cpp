@@ -0,0 +1,273 @@
+#include "ThresholdLogging.hpp"
+#include "Thresholds.hpp"
+#include "Utils.hpp"
+
+#include <chrono>
+#include <cmath>
+#include <limits>
+#include <string>
+#include <vector>
+
+#include <gtest/gtest.h>
+
+using namespace std::chrono_literals;
+using thresholds::RateOfChange;
+
+TEST(RateOfChange, NeedsTwoSamples)
+{
+    RateOfChange rate;
+    rate.addWindow(10s);
+    RateOfChange::Clock::time_point now{};
+    EXPECT_TRUE(std::isnan(rate.slope(10s, now)));
+    rate.addSample(1.0, now);
+    EXPECT_TRUE(std::isnan(rate.slope(10s, now)));
+}
+
+TEST(RateOfChange, DisabledWithoutWindow)
+{
+    RateOfChange rate;
+    RateOfChange::Clock::time_point now{};
+    rate.addSample(1.0, now);
+    rate.addSample(2.0, now + 1s);
+    EXPECT_FALSE(rate.enabled());
+    EXPECT_TRUE(std::isnan(rate.slope(10s, now + 1s)));
+}
+
+TEST(RateOfChange, LinearSlope)
+{
+    RateOfChange rate;
+    rate.addWindow(10s);
+    RateOfChange::Clock::time_point now{};
+    for (int ii = 0; ii < 10; ii++)
+    {
+        rate.addSample(20.0 + (0.5 * ii), now);
+        now += 1s;
+    }
+    now -= 1s;
+    EXPECT_NEAR(rate.slope(10s, now), 0.5, 1e-9);
+}
+
+TEST(RateOfChange, OnlyRecentSamplesCount)
+{
+    RateOfChange rate;
+    rate.addWindow(20s);
+    RateOfChange::Clock::time_point now{};
+    // Flat for a while, then falling at 2 units per second
+    for (int ii = 0; ii < 10; ii++)
+    {
+        rate.addSample(50.0, now);
+        now += 1s;
+    }
+    for (int ii = 1; ii <= 5; ii++)
+    {
+        rate.addSample(50.0 - (2.0 * ii), now);
+        now += 1s;
+    }
+    now -= 1s;
+    EXPECT_NEAR(rate.slope(4s, now), -2.0, 1e-9);
+    EXPECT_GT(rate.slope(20s, now), -2.0);
+}
+
+TEST(ParseThresholds, RateOfChange)
+{
+    SensorData data;
+    data["xyz.openbmc_project.Configuration.ADC.Thresholds0"] = {
+        {"Direction", std::string("greater than")},
+        {"Type", std::string("RateOfChange")},
+        {"Value", 1.5},
+        {"Window", 30.0}};
+    data["xyz.openbmc_project.Configuration.ADC.Thresholds1"] = {
+        {"Direction", std::string("less than")},
+        {"Severity", 1.0},
+        {"Value", 10.0}};
+
+    std::vector<thresholds::Threshold> parsed;
+    ASSERT_TRUE(thresholds::parseThresholdsFromConfig(data, parsed));
+    ASSERT_EQ(parsed.size(), 2U);
+
+    EXPECT_EQ(parsed[0].level, thresholds::Level::RATEOFCHANGE);
+    EXPECT_EQ(parsed[0].direction, thresholds::Direction::HIGH);
+    EXPECT_DOUBLE_EQ(parsed[0].value, 1.5);
+    EXPECT_EQ(parsed[0].rateWindow, 30s);
+
+    EXPECT_EQ(parsed[1].level, thresholds::Level::CRITICAL);
+    EXPECT_EQ(parsed[1].direction, thresholds::Direction::LOW);
+}
+
+TEST(ParseThresholds, SeverityDoesNotSelectRateOfChange)
+{
+    SensorData data;
+    data["xyz.openbmc_project.Configuration.ADC.Thresholds0"] = {
+        {"Direction", std::string("greater than")},
+        {"Severity", 5.0},
+        {"Value", 1.5}};
+
+    std::vector<thresholds::Threshold> parsed;
+    ASSERT_TRUE(thresholds::parseThresholdsFromConfig(data, parsed));
+    EXPECT_TRUE(parsed.empty());
+}
+
+TEST(ThresholdDebounce, DefaultActsImmediately)
+{
+    thresholds::Threshold threshold(thresholds::Level::WARNING,
+                                    thresholds::Direction::HIGH, 10.0);
+    EXPECT_TRUE(threshold.debounce(true));
+    EXPECT_TRUE(threshold.debounce(false));
+}
+
+TEST(ThresholdDebounce, ConsecutiveSamples)
+{
+    thresholds::Threshold threshold(thresholds::Level::WARNING,
+                                    thresholds::Direction::HIGH, 10.0);
+    threshold.assertSamples = 3;
+    threshold.deassertSamples = 2;
+
+    EXPECT_FALSE(threshold.debounce(true));
+    EXPECT_FALSE(threshold.debounce(true));
+    // A good reading breaks the streak
+    EXPECT_FALSE(threshold.debounce(false));
+    EXPECT_FALSE(threshold.debounce(true));
+    EXPECT_FALSE(threshold.debounce(true));
+    EXPECT_TRUE(threshold.debounce(true));
+    EXPECT_TRUE(threshold.debounce(true));
+
+    EXPECT_FALSE(threshold.debounce(false));
+    threshold.resetDebounce();
+    EXPECT_FALSE(threshold.debounce(false));
+    EXPECT_TRUE(threshold.debounce(false));
+}
+
+TEST(ParseThresholds, Debounce)
+{
+    SensorData data;
+    data["xyz.openbmc_project.Configuration.ADC.Thresholds0"] = {
+        {"AssertSamples", 3.0},
+        {"DeassertSamples", 0.0},
+        {"Direction", std::string("greater than")},
+        {"Severity", 0.0},
+        {"Value", 1.5}};
+
+    std::vector<thresholds::Threshold> parsed;
+    ASSERT_TRUE(thresholds::parseThresholdsFromConfig(data, parsed));
+    ASSERT_EQ(parsed.size(), 1U);
+    EXPECT_EQ(parsed[0].assertSamples, 3U);
+    EXPECT_EQ(parsed[0].deassertSamples, 1U);
+}
+
+TEST(ParseThresholds, Writeable)
+{
+    SensorData data;
+    data["xyz.openbmc_project.Configuration.ADC.Thresholds0"] = {
+        {"Direction", std::string("greater than")},
+        {"Severity", 0.0},
+        {"Value", 1.5}};
+    data["xyz.openbmc_project.Configuration.ADC.Thresholds1"] = {
+        {"Direction", std::string("greater than")},
+        {"Severity", 1.0},
+        {"Value", 2.5},
+        {"Writeable", false}};
+
+    std::vector<thresholds::Threshold> parsed;
+    ASSERT_TRUE(thresholds::parseThresholdsFromConfig(data, parsed));
+    ASSERT_EQ(parsed.size(), 2U);
+    EXPECT_TRUE(parsed[0].writeable);
+    EXPECT_FALSE(parsed[1].writeable);
+}
+
+TEST(ThresholdEventLog, KeepsMostRecent)
+{
+    thresholds::EventLog log(2);
+    log.record({1, thresholds::Level::WARNING, thresholds::Direction::HIGH,
+                true, 10.5});
+    log.record({2, thresholds::Level::CRITICAL, thresholds::Direction::LOW,
+                true, 1.0});
+    log.record({3, thresholds::Level::WARNING, thresholds::Direction::HIGH,
+                false, 9.0});
+
+    std::vector<thresholds::EventLog::Entry> expected{
+        {2, "Critical", "Low", true, 1.0},
+        {3, "Warning", "High", false, 9.0}};
+    EXPECT_EQ(log.entries(), expected);
+}
+
+TEST(ParseThresholds, Latched)
+{
+    SensorData data;
+    data["xyz.openbmc_project.Configuration.ADC.Thresholds0"] = {
+        {"Direction", std::string("greater than")},
+        {"Latched", true},
+        {"Severity", 1.0},
+        {"Value", 1.5}};
+
+    std::vector<thresholds::Threshold> parsed;
+    ASSERT_TRUE(thresholds::parseThresholdsFromConfig(data, parsed));
+    ASSERT_EQ(parsed.size(), 1U);
+    EXPECT_TRUE(parsed[0].latched);
+}
+
+TEST(ThresholdLatch, ClearedBeyondHysteresis)
+{
+    thresholds::Threshold high(thresholds::Level::CRITICAL,
+                               thresholds::Direction::HIGH, 90.0, 5.0);
+    EXPECT_FALSE(high.cleared(95.0));
+    EXPECT_FALSE(high.cleared(88.0));
+    EXPECT_TRUE(high.cleared(84.0));
+    EXPECT_FALSE(high.cleared(std::numeric_limits<double>::quiet_NaN()));
+
+    thresholds::Threshold low(thresholds::Level::WARNING,
+                              thresholds::Direction::LOW, 10.0, 1.0);
+    EXPECT_FALSE(low.cleared(10.5));
+    EXPECT_TRUE(low.cleared(11.5));
+}
+
+TEST(ThresholdLogging, MessageId)
+{
+    EXPECT_EQ(thresholds::messageId(thresholds::Level::CRITICAL,
+                                    thresholds::Direction::HIGH),
+              "xyz.openbmc_project.Sensor.Threshold."
+              "ReadingAboveUpperCriticalThreshold");
+    EXPECT_EQ(thresholds::messageId(thresholds::Level::SOFTSHUTDOWN,
+                                    thresholds::Direction::LOW),
+              "xyz.openbmc_project.Sensor.Threshold."
+              "ReadingBelowLowerSoftShutdownThreshold");
+    // There are no rate of change events
+    EXPECT_EQ(thresholds::messageId(thresholds::Level::RATEOFCHANGE,
+                                    thresholds::Direction::HIGH),
+              "xyz.openbmc_project.Sensor.Threshold."
+              "ReadingAboveUpperWarningThreshold");
+}
+
+TEST(ThresholdLogging, RedfishMessageId)
+{
+    EXPECT_EQ(thresholds::redfishMessageId(thresholds::Level::CRITICAL,
+                                           thresholds::Direction::HIGH),
+              "OpenBMC.0.1.SensorThresholdCriticalHighGoingHigh");
+    EXPECT_EQ(thresholds::redfishMessageId(thresholds::Level::WARNING,
+                                           thresholds::Direction::LOW),
+              "OpenBMC.0.1.SensorThresholdWarningLowGoingLow");
+    // Levels the registry does not know are reported as the closer one
+    EXPECT_EQ(thresholds::redfishMessageId(thresholds::Level::HARDSHUTDOWN,
+                                           thresholds::Direction::HIGH),
+              "OpenBMC.0.1.SensorThresholdCriticalHighGoingHigh");
+    EXPECT_EQ(thresholds::redfishMessageId(thresholds::Level::PERFORMANCELOSS,
+                                           thresholds::Direction::LOW),
+              "OpenBMC.0.1.SensorThresholdWarningLowGoingLow");
+}
+
+TEST(ThresholdLogging, Severity)
+{
+    EXPECT_EQ(thresholds::logSeverity(thresholds::Level::SOFTSHUTDOWN),
+              "xyz.openbmc_project.Logging.Entry.Level.Critical");
+    EXPECT_EQ(thresholds::logSeverity(thresholds::Level::RATEOFCHANGE),
+              "xyz.openbmc_project.Logging.Entry.Level.Warning");
+}
+
+TEST(ThresholdLogging, ParseConfig)
+{
+    SensorBaseConfigMap cfg;
+    EXPECT_FALSE(thresholds::parseEventLogConfig(cfg));
+    cfg["EventLog"] = true;
+    EXPECT_EQ(thresholds::parseEventLogConfig(cfg), true);
+    cfg["EventLog"] = false;
+    EXPECT_EQ(thresholds::parseEventLogConfig(cfg), false);
+}